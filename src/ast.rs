@@ -1,4 +1,5 @@
 use crate::runtime::Type;
+use crate::span::Span;
 
 #[derive(Debug, Clone)]
 pub struct Program {
@@ -12,11 +13,25 @@ impl Program {
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
-pub enum Statement {
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+impl Statement {
+    pub fn new(kind: StatementKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum StatementKind {
     Let(String, Option<Type>, Expr),
     Const(String, Option<Type>, Expr),
     Expression(Expr),
-    Assignment(String, Expr),
+    Assignment(LValue, Expr),
+    // `a[i] += 1`, the target is found once
+    CompoundAssignment(LValue, BinaryOpKind, Expr),
     Import(Vec<String>, Option<Vec<String>>),
     If(Vec<Branch>, Option<Block>),
     Return(Expr),
@@ -25,12 +40,95 @@ pub enum Statement {
     For(String, Expr, Block),
     While(Expr, Block),
     Type(String, Type),
+    Struct(String, Vec<Field>),
+    Enum(String, Vec<Variant>),
+    Match(Expr, Vec<MatchArm>),
+    Trait(String, Vec<MethodSig>),
+    Impl(String, Option<String>, Vec<Method>),
+    Export(Box<Statement>),
     Break,
     Continue,
 }
 
+impl StatementKind {
+    /// the name a declaration binds, `None` for other statements
+    pub fn declared_name(&self) -> Option<&String> {
+        match self {
+            StatementKind::Let(name, ..)
+            | StatementKind::Const(name, ..)
+            | StatementKind::Fn(name, ..)
+            | StatementKind::Module(name, _)
+            | StatementKind::Type(name, _)
+            | StatementKind::Struct(name, _)
+            | StatementKind::Enum(name, _)
+            | StatementKind::Trait(name, _) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// short human readable name of the statement, used in diagnostics
+    pub fn describe(&self) -> &'static str {
+        match self {
+            StatementKind::Let(..) => "let statement",
+            StatementKind::Const(..) => "const statement",
+            StatementKind::Expression(_) => "expression statement",
+            StatementKind::Assignment(..) | StatementKind::CompoundAssignment(..) => "assignment",
+            StatementKind::Import(..) => "import statement",
+            StatementKind::If(..) => "if statement",
+            StatementKind::Return(_) => "return statement",
+            StatementKind::Fn(..) => "fn statement",
+            StatementKind::Module(..) => "module statement",
+            StatementKind::For(..) => "for loop",
+            StatementKind::While(..) => "while loop",
+            StatementKind::Type(..) => "type alias",
+            StatementKind::Struct(..) => "struct declaration",
+            StatementKind::Enum(..) => "enum declaration",
+            StatementKind::Match(..) => "match statement",
+            StatementKind::Trait(..) => "trait declaration",
+            StatementKind::Impl(..) => "impl block",
+            StatementKind::Export(_) => "export statement",
+            StatementKind::Break => "break statement",
+            StatementKind::Continue => "continue statement",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
-pub enum Expr {
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    pub fn binary(lhs: Expr, op: BinaryOpKind, rhs: Expr) -> Self {
+        let span = lhs.span.to(rhs.span);
+        Expr::new(ExprKind::BinaryOp(Box::new(lhs), op, Box::new(rhs)), span)
+    }
+
+    /// `expr` wrapped in a `SafeChain` when one of its links is a `?.`
+    pub fn chain(expr: Expr) -> Self {
+        let mut link = &expr;
+        loop {
+            link = match &link.kind {
+                ExprKind::Call(next, _)
+                | ExprKind::MethodCall(next, _)
+                | ExprKind::Index(next, _) => next,
+                ExprKind::SafeMethodCall(..) | ExprKind::SafeIndex(..) => break,
+                _ => return expr,
+            };
+        }
+
+        let span = expr.span;
+        Expr::new(ExprKind::SafeChain(Box::new(expr)), span)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum ExprKind {
     Null,
     Int(i32),
     Float(f32),
@@ -38,43 +136,201 @@ pub enum Expr {
     Bool(bool),
     List(Vec<Expr>),
     Object(Vec<Prop>),
-    Identifier(String),
+    Struct(String, Vec<Prop>),
+    // the slot is filled in by the resolver, names looked up at runtime have none
+    Identifier(String, Option<Slot>),
     Call(Box<Expr>, Vec<Expr>),
     MethodCall(Box<Expr>, Box<Expr>),
-    ModuleCall(Vec<String>, Box<Expr>),
+    // the slot of the first name when it is a variable
+    ModuleCall(Vec<String>, Option<Slot>, Box<Expr>),
     Index(Box<Expr>, Box<Expr>),
+    // `object?.member` and `list?.[index]`, they end their chain with `null` when the left side is `null`
+    SafeMethodCall(Box<Expr>, Box<Expr>),
+    SafeIndex(Box<Expr>, Box<Expr>),
+    // calls, members and indexes after each other that contain a `?.`
+    SafeChain(Box<Expr>),
     BinaryOp(Box<Expr>, BinaryOpKind, Box<Expr>),
     UnaryOp(UnaryOpKind, Box<Expr>),
     Fn(Vec<Arg>, Option<Type>, Block),
     Module(Block),
     If(Vec<Branch>, Option<Block>),
+    Match(Box<Expr>, Vec<MatchArm>),
     Tuple(Vec<Expr>),
     Range(Box<Expr>, Box<Expr>),
+    // a string with values written into it, `"x={x}"`
+    Interpolation(Vec<Fragment>),
+}
+
+/// a piece of an interpolated string
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum Fragment {
+    Text(String),
+    Value(Expr, Option<FormatSpec>),
+}
+
+/// how a value of an interpolated string is written, `{price:>8.2}`.
+/// it follows the format specs of Rust: `[[fill]align][0][width][.precision][type]`
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct FormatSpec {
+    pub fill: char,
+    pub align: Option<Align>,
+    // numbers are padded with zeros after their sign
+    pub zero: bool,
+    pub width: Option<usize>,
+    pub precision: Option<usize>,
+    // `x`, `X`, `o` or `b` write ints in another base
+    pub radix: Option<char>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// where the resolver found a variable. `depth` counts the functions between the code
+/// using it and the one declaring it, `index` is its slot in the frame of that function
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Slot {
+    pub depth: u32,
+    pub index: u32,
+}
+
+/// the left side of an assignment, a variable and the items and properties
+/// the value is written through, `matrix[i][j]` or `point.x`
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct LValue {
+    pub name: String,
+    pub slot: Option<Slot>,
+    pub path: Vec<Access>,
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum Access {
+    Index(Expr),
+    Property(String),
+}
+
+impl LValue {
+    /// `None` when `expr` isn't a variable or an item or a property of one
+    pub fn from_expr(expr: Expr) -> Option<LValue> {
+        match expr.kind {
+            ExprKind::Identifier(name, slot) => Some(LValue {
+                name,
+                slot,
+                path: vec![],
+            }),
+            ExprKind::Index(target, index) => {
+                let mut lvalue = LValue::from_expr(*target)?;
+                lvalue.path.push(Access::Index(*index));
+                Some(lvalue)
+            }
+            ExprKind::MethodCall(target, member) => {
+                let name = match member.kind {
+                    ExprKind::Identifier(name, _) => name,
+                    ExprKind::Int(n) => n.to_string(),
+                    _ => return None,
+                };
+
+                let mut lvalue = LValue::from_expr(*target)?;
+                lvalue.path.push(Access::Property(name));
+                Some(lvalue)
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct Prop {
     pub key: String,
     pub value: Expr,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct Arg {
     pub ident: String,
     pub datatype: Type,
+    pub span: Span,
+}
+
+// a function declared in an `impl` block, `self` is not part of `args`
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Method {
+    pub name: String,
+    pub args: Vec<Arg>,
+    pub ret_type: Option<Type>,
+    pub block: Block,
+    pub span: Span,
+}
+
+// a method a trait requires, `self` is not part of `args`
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct MethodSig {
+    pub name: String,
+    pub args: Vec<Arg>,
+    pub ret_type: Option<Type>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Field {
+    pub name: String,
+    pub datatype: Type,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Variant {
+    pub name: String,
+    pub fields: Vec<Type>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: ArmBody,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum ArmBody {
+    Expr(Expr),
+    Block(Block),
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum PatternKind {
+    Wildcard,
+    Literal(Expr),
+    // a binding, or a unit variant of the matched enum
+    Ident(String),
+    Variant(Option<String>, String, Vec<Pattern>),
+    Tuple(Vec<Pattern>),
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct Branch {
     pub condition: Expr,
     pub statements: Block,
+    pub span: Span,
 }
 
 impl Branch {
-    pub fn new(condition: Expr, statements: Block) -> Self {
+    pub fn new(condition: Expr, statements: Block, span: Span) -> Self {
         Self {
             condition,
             statements,
+            span,
         }
     }
 
@@ -82,10 +338,11 @@ impl Branch {
         cond: Expr,
         if_block: Vec<Statement>,
         else_if_block: Statement,
-    ) -> Statement {
-        if let Statement::If(mut branches, else_stmt) = else_if_block {
-            branches.insert(0, Branch::new(cond, if_block));
-            return Statement::If(branches, else_stmt);
+        span: Span,
+    ) -> StatementKind {
+        if let StatementKind::If(mut branches, else_stmt) = else_if_block.kind {
+            branches.insert(0, Branch::new(cond, if_block, span));
+            StatementKind::If(branches, else_stmt)
         } else {
             panic!("grammar error: if statement");
         }
@@ -95,10 +352,11 @@ impl Branch {
         cond: Expr,
         if_block: Vec<Statement>,
         else_if_block: Expr,
-    ) -> Expr {
-        if let Expr::If(mut branches, else_stmt) = else_if_block {
-            branches.insert(0, Branch::new(cond, if_block));
-            return Expr::If(branches, else_stmt);
+        span: Span,
+    ) -> ExprKind {
+        if let ExprKind::If(mut branches, else_stmt) = else_if_block.kind {
+            branches.insert(0, Branch::new(cond, if_block, span));
+            ExprKind::If(branches, else_stmt)
         } else {
             panic!("grammar error: if statement");
         }
@@ -112,13 +370,22 @@ pub fn append<T>(mut accum: Vec<T>, item: T) -> Vec<T> {
 
 pub type Block = Vec<Statement>;
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub enum BinaryOpKind {
     // arithmatic
     Add,
     Sub,
     Mul,
     Div,
+    Rem,
+    Pow,
+    // bitwise
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
     // relational
     EQ,
     NE,
@@ -129,10 +396,14 @@ pub enum BinaryOpKind {
     // logical
     And,
     Or,
+    // `lhs ?? rhs`
+    Coalesce,
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub enum UnaryOpKind {
     Not,
+    Neg,
+    BitNot,
     Typeof,
 }
@@ -0,0 +1,1899 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::ast::UnaryOpKind;
+use crate::ast::{
+    Access, Arg, ArmBody, BinaryOpKind, Block, Expr, ExprKind, Field, Fragment, LValue, MatchArm,
+    MethodSig, Pattern, PatternKind, Program, Prop, Statement, StatementKind, Variant,
+};
+use crate::diagnostic::Diagnostic;
+use crate::grammar;
+use crate::runtime::error::ErrorKind;
+use crate::runtime::eval::module::find_module;
+use crate::runtime::value::BuiltinType;
+use crate::runtime::{Prototypes, Type};
+use crate::span::{Source, Span};
+
+/// checks the program before it runs and reports every type error it can prove.
+/// types that are only known at runtime (builtins, modules, method calls) are skipped.
+/// unannotated functions get their inferred return type filled in, so the interpreter
+/// sees the same function types as the checker.
+/// imported files are looked up like at runtime, `root` is the directory of the main script
+pub fn check(program: &mut Program, root: Option<&Path>) -> Vec<Diagnostic> {
+    let mut checker = Checker::new();
+    checker.root = root.map(Path::to_path_buf);
+
+    checker.check_block(&mut program.statements);
+
+    checker.errors
+}
+
+/// a checker that remembers the declarations of the programs it has checked, for the REPL
+pub struct Session(Checker);
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self(Checker::new())
+    }
+
+    /// checks `program` as a continuation of the programs before it.
+    /// its declarations are only kept when it has no errors
+    pub fn check(&mut self, program: &mut Program, root: Option<&Path>) -> Vec<Diagnostic> {
+        self.0.root = root.map(Path::to_path_buf);
+
+        let scopes = self.0.scopes.clone();
+        let methods = self.0.methods.clone();
+        let impls = self.0.impls.clone();
+        let types = self.0.types.clone();
+
+        for statement in program.statements.iter_mut() {
+            self.0.check_statement(statement);
+        }
+
+        let errors = std::mem::take(&mut self.0.errors);
+        if !errors.is_empty() {
+            self.0.scopes = scopes;
+            self.0.methods = methods;
+            self.0.impls = impls;
+            self.0.types = types;
+        }
+        errors
+    }
+
+    /// records a variable defined by the host, `None` when its type isn't known
+    pub fn define(&mut self, name: &str, datatype: Option<Type>) {
+        self.0.scopes[0].insert(name.to_string(), Binding::Value(datatype, true));
+    }
+
+    /// the type of `expr` if it is known before running it
+    pub fn type_of(&mut self, expr: &mut Expr) -> Result<Option<Type>, Vec<Diagnostic>> {
+        let datatype = self.0.check_expression(expr);
+
+        let errors = std::mem::take(&mut self.0.errors);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(datatype)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Binding {
+    // `None` when the type can't be known before running the program
+    Value(Option<Type>, bool),
+    Alias(Type),
+    Struct(Vec<Field>),
+    Enum(Vec<Variant>),
+    Trait(Vec<MethodSig>),
+    // the items a module exports
+    Module(HashMap<String, Binding>),
+}
+
+/// what checking an imported file gives to the files importing it
+#[derive(Clone)]
+struct FileModule {
+    items: HashMap<String, Binding>,
+    methods: HashMap<String, HashMap<String, Option<Type>>>,
+    impls: HashMap<String, Vec<String>>,
+    types: HashMap<String, Binding>,
+    // the spans of the bindings point into it
+    _source: Arc<Source>,
+}
+
+struct FnContext {
+    expected: Option<Type>,
+    returns: Vec<(Option<Type>, Span)>,
+}
+
+struct Checker {
+    scopes: Vec<HashMap<String, Binding>>,
+    // the types of the methods of `impl` blocks by prototype, they belong to the type
+    // rather than to a scope, like at runtime
+    methods: HashMap<String, HashMap<String, Option<Type>>>,
+    // the traits each prototype implements
+    impls: HashMap<String, Vec<String>>,
+    // the types declared in modules by name. their values reach code that can't name them,
+    // like at runtime they are known by their name there
+    types: HashMap<String, Binding>,
+    // the directory of the main script
+    root: Option<PathBuf>,
+    // imported files by canonical path, `None` while they are checked or when they can't be read
+    files: HashMap<PathBuf, Option<FileModule>>,
+    functions: Vec<FnContext>,
+    errors: Vec<Diagnostic>,
+}
+
+impl Checker {
+    fn new() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+            methods: HashMap::new(),
+            impls: HashMap::new(),
+            types: HashMap::new(),
+            root: None,
+            files: HashMap::new(),
+            functions: vec![],
+            errors: vec![],
+        }
+    }
+
+    fn error(&mut self, kind: ErrorKind, span: Span) {
+        self.errors
+            .push(Diagnostic::new(kind.to_string(), Some(span)));
+    }
+
+    fn mismatch(&mut self, expected: Type, found: Type, span: Span) {
+        self.error(ErrorKind::TypeMismatch { expected, found }, span);
+    }
+
+    fn lookup(&self, name: &String) -> Option<&Binding> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(binding) = scope.get(name) {
+                return Some(binding);
+            }
+        }
+        None
+    }
+
+    /// the declaration of the type `name`. a type of a module is named by its path, and the
+    /// types of modules are also found by their name alone
+    fn lookup_type(&self, name: &String) -> Option<Binding> {
+        if let Some((module, name)) = split_path(name) {
+            return self.module_items(&module)?.get(name).cloned();
+        }
+
+        self.lookup(name).or_else(|| self.types.get(name)).cloned()
+    }
+
+    /// the items a module in scope exports, `None` when they are only known at runtime
+    fn module_items(&self, paths: &[String]) -> Option<HashMap<String, Binding>> {
+        let (first, rest) = paths.split_first()?;
+        let mut items = match self.lookup(first)? {
+            Binding::Module(items) => items,
+            _ => return None,
+        };
+
+        for name in rest {
+            items = match items.get(name)? {
+                Binding::Module(items) => items,
+                _ => return None,
+            };
+        }
+        Some(items.clone())
+    }
+
+    /// the items the module at `paths` exports, a module in scope or a file imported at `span`.
+    /// `None` when they are only known at runtime, like the items of builtin modules
+    fn import(&mut self, paths: &[String], span: Span) -> Option<HashMap<String, Binding>> {
+        if self.lookup(&paths[0]).is_some() {
+            return self.module_items(paths);
+        }
+
+        let path = find_module(self.root.as_deref(), paths, span)?;
+        let key = fs::canonicalize(&path).ok()?;
+
+        if !self.files.contains_key(&key) {
+            // a file importing itself back sees nothing, the cycle is reported when it runs
+            self.files.insert(key.clone(), None);
+            let file = self.check_file(&path);
+            self.files.insert(key.clone(), file);
+        }
+
+        let file = self.files.get(&key)?.clone()?;
+
+        for (prototype, methods) in file.methods {
+            let own = self.methods.entry(prototype).or_default();
+            for (name, datatype) in methods {
+                own.entry(name).or_insert(datatype);
+            }
+        }
+        for (prototype, traits) in file.impls {
+            let own = self.impls.entry(prototype).or_default();
+            for name in traits {
+                if !own.contains(&name) {
+                    own.push(name);
+                }
+            }
+        }
+        for (name, binding) in file.types {
+            self.types.entry(name).or_insert(binding);
+        }
+
+        Some(file.items)
+    }
+
+    /// checks the imported file at `path` for what it declares, its errors are reported
+    /// when it runs
+    fn check_file(&mut self, path: &Path) -> Option<FileModule> {
+        let code = fs::read_to_string(path).ok()?;
+        let source = Source::add(&path.to_string_lossy(), &code);
+        let mut program = grammar::programParser::new()
+            .parse(source.id, 0, &code)
+            .ok()?;
+
+        let mut checker = Checker::new();
+        checker.root = self.root.clone();
+        checker.files = std::mem::take(&mut self.files);
+
+        let items = checker.check_items(&mut program.statements);
+        self.files = checker.files;
+
+        Some(FileModule {
+            items,
+            methods: checker.methods,
+            impls: checker.impls,
+            types: checker.types,
+            _source: source,
+        })
+    }
+
+    fn declare(&mut self, name: &String, binding: Binding, span: Span) {
+        let scope = self
+            .scopes
+            .last_mut()
+            .expect("checker scopes shouldn't be empty");
+
+        if scope.contains_key(name) {
+            self.error(
+                ErrorKind::AlreadyDefined {
+                    name: name.to_string(),
+                },
+                span,
+            );
+            return;
+        }
+
+        scope.insert(name.to_string(), binding);
+    }
+
+    fn check_block(&mut self, statements: &mut Block) {
+        self.scopes.push(HashMap::new());
+
+        for statement in statements {
+            self.check_statement(statement);
+        }
+
+        self.scopes.pop();
+    }
+
+    /// a module only holds declarations, anything else would fail when the module is built.
+    /// returns the items it exports
+    fn check_module(&mut self, statements: &mut Block) -> HashMap<String, Binding> {
+        for statement in statements.iter() {
+            let kind = match &statement.kind {
+                StatementKind::Export(inner) => &inner.kind,
+                kind => kind,
+            };
+
+            match kind {
+                StatementKind::Let(..)
+                | StatementKind::Const(..)
+                | StatementKind::Fn(..)
+                | StatementKind::Module(..)
+                | StatementKind::Struct(..)
+                | StatementKind::Enum(..)
+                | StatementKind::Trait(..)
+                | StatementKind::Type(..)
+                | StatementKind::Impl(..) => {}
+                other => self.error(
+                    ErrorKind::NotSupportedInModule {
+                        statement: other.describe().to_string(),
+                    },
+                    statement.span,
+                ),
+            }
+        }
+
+        self.check_items(statements)
+    }
+
+    /// checks the statements of a module or a file in a scope of their own, returns the items
+    /// they export. their types are remembered by name
+    fn check_items(&mut self, statements: &mut Block) -> HashMap<String, Binding> {
+        self.scopes.push(HashMap::new());
+        for statement in statements.iter_mut() {
+            self.check_statement(statement);
+        }
+        let scope = self.scopes.pop().unwrap_or_default();
+
+        let mut items = HashMap::new();
+        for statement in statements.iter() {
+            let (kind, exported) = match &statement.kind {
+                StatementKind::Export(inner) => (&inner.kind, true),
+                kind => (kind, false),
+            };
+
+            let Some(name) = kind.declared_name() else {
+                continue;
+            };
+            let Some(binding) = scope.get(name) else {
+                continue;
+            };
+
+            if let Binding::Alias(_) | Binding::Struct(_) | Binding::Enum(_) | Binding::Trait(_) =
+                binding
+            {
+                self.types
+                    .entry(name.to_string())
+                    .or_insert(binding.clone());
+            }
+            if exported {
+                items.insert(name.to_string(), binding.clone());
+            }
+        }
+        items
+    }
+
+    /// resolves aliases declared with `type` down to builtin types
+    fn resolve(&mut self, datatype: &Type, span: Span) -> Option<Type> {
+        match datatype {
+            Type::Alias(name) => {
+                if datatype.is_opaque() {
+                    return Some(datatype.clone());
+                }
+
+                // the value of a type of a module is known by the name it was declared with
+                let declared = split_path(name).map_or(name.as_str(), |(_, name)| name);
+
+                match self.lookup_type(name) {
+                    Some(Binding::Alias(t)) => self.resolve(&t, span),
+                    Some(binding @ (Binding::Struct(_) | Binding::Enum(_) | Binding::Trait(_))) => {
+                        self.types.entry(declared.to_string()).or_insert(binding);
+                        Some(Type::Alias(declared.to_string()))
+                    }
+                    Some(Binding::Value(..)) | Some(Binding::Module(_)) => {
+                        self.error(
+                            ErrorKind::NotAType {
+                                name: name.to_string(),
+                            },
+                            span,
+                        );
+                        None
+                    }
+                    // the types of modules the checker can't see are checked when the program runs
+                    None if split_path(name)
+                        .is_some_and(|(module, _)| self.module_items(&module).is_none()) =>
+                    {
+                        None
+                    }
+                    None => {
+                        self.error(
+                            ErrorKind::UndefinedType {
+                                name: name.to_string(),
+                            },
+                            span,
+                        );
+                        None
+                    }
+                }
+            }
+            Type::Builtin(BuiltinType::List(t)) => {
+                let t = self.resolve(t, span)?;
+                Some(Type::Builtin(BuiltinType::List(Box::new(t))))
+            }
+            Type::Builtin(BuiltinType::Tuple(items)) => {
+                let mut types = vec![];
+                for item in items {
+                    types.push(self.resolve(item, span));
+                }
+                let types = types.into_iter().collect::<Option<Vec<Type>>>()?;
+                Some(Type::Builtin(BuiltinType::Tuple(types)))
+            }
+            Type::Builtin(BuiltinType::Fn(args, ret_type)) => {
+                let mut types = vec![];
+                for arg in args {
+                    types.push(self.resolve(arg, span));
+                }
+                let ret_type = self.resolve(ret_type, span);
+                let types = types.into_iter().collect::<Option<Vec<Type>>>()?;
+                Some(Type::Builtin(BuiltinType::Fn(types, Box::new(ret_type?))))
+            }
+            t => Some(t.clone()),
+        }
+    }
+
+    fn check_statement(&mut self, statement: &mut Statement) {
+        let span = statement.span;
+
+        match &mut statement.kind {
+            StatementKind::Expression(expr) => {
+                self.check_expression(expr);
+            }
+            StatementKind::Let(name, datatype, expr) => {
+                self.check_variable(name, datatype, expr, true, span);
+            }
+            StatementKind::Const(name, datatype, expr) => {
+                self.check_variable(name, datatype, expr, false, span);
+            }
+            StatementKind::Assignment(lvalue, expr) => {
+                let found = self.check_expression(expr);
+                let (expected, stored) = self.check_lvalue(lvalue, span);
+
+                self.check_store(lvalue, stored, expected, found, expr.span, span);
+            }
+            StatementKind::CompoundAssignment(lvalue, op, expr) => {
+                let (expected, stored) = self.check_lvalue(lvalue, span);
+                let rhs = self.check_expression(expr);
+                let found = self.check_binary(expected.clone(), op, rhs, span);
+
+                self.check_store(lvalue, stored, expected, found, span, span);
+            }
+            StatementKind::Import(paths, items) => {
+                let module = self.import(paths, span);
+
+                match items {
+                    Some(items) => {
+                        for item in items.iter() {
+                            let binding = module
+                                .as_ref()
+                                .and_then(|module| module.get(item).cloned())
+                                .unwrap_or(Binding::Value(None, false));
+                            self.declare(item, binding, span);
+                        }
+                    }
+                    None => {
+                        if let Some(name) = paths.last() {
+                            let binding = match module {
+                                Some(items) => Binding::Module(items),
+                                None => Binding::Value(None, false),
+                            };
+                            self.declare(name, binding, span);
+                        }
+                    }
+                }
+            }
+            StatementKind::If(branches, else_block) => {
+                for branch in branches {
+                    self.check_condition(&mut branch.condition);
+                    self.check_block(&mut branch.statements);
+                }
+
+                if let Some(block) = else_block {
+                    self.check_block(block);
+                }
+            }
+            StatementKind::Return(expr) => {
+                let found = self.check_expression(expr);
+
+                if let Some(function) = self.functions.last_mut() {
+                    function.returns.push((found.clone(), expr.span));
+
+                    if let (Some(expected), Some(found)) = (function.expected.clone(), found) {
+                        if !fits(self, &expected, &found) {
+                            self.mismatch(expected, found, expr.span);
+                        }
+                    }
+                }
+            }
+            StatementKind::Fn(name, args, ret_type, block) => {
+                // declared before the body is checked, so recursive calls resolve
+                let datatype = match ret_type {
+                    Some(ret_type) => fn_type(self, args, ret_type, span),
+                    None => None,
+                };
+                self.declare(name, Binding::Value(datatype, false), span);
+
+                let datatype = self.check_fn(args, ret_type, block, span);
+
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.insert(name.to_string(), Binding::Value(datatype, false));
+                }
+            }
+            StatementKind::Module(name, block) => {
+                let items = self.check_module(block);
+                self.declare(name, Binding::Module(items), span);
+            }
+            StatementKind::For(name, iter, block) => {
+                let item = match self.check_expression(iter) {
+                    Some(Type::Builtin(BuiltinType::List(t))) => Some(*t),
+                    Some(Type::Builtin(BuiltinType::Tuple(_))) => None,
+                    Some(t) if !t.is_opaque() => {
+                        self.error(
+                            ErrorKind::NotIterable {
+                                datatype: String::from(t),
+                            },
+                            iter.span,
+                        );
+                        None
+                    }
+                    _ => None,
+                };
+
+                self.scopes.push(HashMap::new());
+                self.declare(name, Binding::Value(item, true), span);
+                self.declare(
+                    &"index".to_string(),
+                    Binding::Value(Some(Type::Builtin(BuiltinType::Int)), false),
+                    span,
+                );
+                self.check_block(block);
+                self.scopes.pop();
+            }
+            StatementKind::While(cond, block) => {
+                self.check_condition(cond);
+                self.check_block(block);
+            }
+            StatementKind::Type(name, datatype) => {
+                self.resolve(datatype, span);
+                self.declare(name, Binding::Alias(datatype.clone()), span);
+            }
+            StatementKind::Struct(name, fields) => {
+                // declared first, so fields can refer to the struct itself
+                self.declare(name, Binding::Struct(fields.clone()), span);
+
+                let mut names: Vec<&String> = vec![];
+                for field in fields.iter() {
+                    if names.contains(&&field.name) {
+                        self.error(
+                            ErrorKind::DuplicateProperty {
+                                name: field.name.to_string(),
+                            },
+                            field.span,
+                        );
+                    }
+                    names.push(&field.name);
+                    self.resolve(&field.datatype, field.span);
+                }
+            }
+            StatementKind::Enum(name, variants) => {
+                self.declare(name, Binding::Enum(variants.clone()), span);
+
+                let mut names: Vec<&String> = vec![];
+                for variant in variants.iter() {
+                    if names.contains(&&variant.name) {
+                        self.error(
+                            ErrorKind::AlreadyDefined {
+                                name: variant.name.to_string(),
+                            },
+                            variant.span,
+                        );
+                    }
+                    names.push(&variant.name);
+
+                    for field in variant.fields.iter() {
+                        self.resolve(field, variant.span);
+                    }
+                }
+            }
+            StatementKind::Match(expr, arms) => {
+                self.check_match(expr, arms, span, false);
+            }
+            StatementKind::Trait(name, methods) => {
+                self.declare(name, Binding::Trait(methods.clone()), span);
+
+                let mut names: Vec<&String> = vec![];
+                for method in methods.iter() {
+                    if names.contains(&&method.name) {
+                        self.error(
+                            ErrorKind::AlreadyDefined {
+                                name: method.name.to_string(),
+                            },
+                            method.span,
+                        );
+                    }
+                    names.push(&method.name);
+
+                    let null = Type::Builtin(BuiltinType::Null);
+                    let ret_type = method.ret_type.as_ref().unwrap_or(&null);
+                    fn_type(self, &method.args, ret_type, method.span);
+                }
+            }
+            StatementKind::Impl(datatype, trait_name, methods) => {
+                let target = match self.resolve(&Type::from(datatype.to_string()), span) {
+                    Some(target) => target,
+                    None => return,
+                };
+                let prototype = target.prototype();
+                let builtins = Prototypes::exports();
+
+                // recorded before the bodies are checked, so `self` can be passed as the trait
+                let required = match trait_name {
+                    Some(trait_name) => self.check_impl(&prototype, trait_name, span),
+                    None => vec![],
+                };
+
+                // `list`, `tuple` and `function` say nothing about the item types
+                let this = match ["list", "tuple", "function"].contains(&datatype.as_str()) {
+                    true => None,
+                    false => Some(target),
+                };
+
+                // every method is declared before the bodies are checked, so they can call each other
+                for method in methods.iter() {
+                    let builtin = builtins
+                        .get(&prototype)
+                        .is_some_and(|proto| proto.contains_key(&method.name));
+                    let declared = self
+                        .methods
+                        .get(&prototype)
+                        .is_some_and(|methods| methods.contains_key(&method.name));
+
+                    if builtin || declared {
+                        self.error(
+                            ErrorKind::DuplicateMethod {
+                                name: method.name.to_string(),
+                                datatype: prototype.to_string(),
+                            },
+                            method.span,
+                        );
+                        continue;
+                    }
+
+                    let datatype = match &method.ret_type {
+                        Some(ret_type) => fn_type(self, &method.args, ret_type, method.span),
+                        None => None,
+                    };
+                    self.methods
+                        .entry(prototype.to_string())
+                        .or_default()
+                        .insert(method.name.to_string(), datatype);
+                }
+
+                for method in methods.iter_mut() {
+                    self.scopes.push(HashMap::new());
+                    self.declare(
+                        &"self".to_string(),
+                        Binding::Value(this.clone(), false),
+                        method.span,
+                    );
+                    let datatype = self.check_fn(
+                        &method.args,
+                        &mut method.ret_type,
+                        &mut method.block,
+                        method.span,
+                    );
+                    self.scopes.pop();
+
+                    if let Some(methods) = self.methods.get_mut(&prototype) {
+                        methods.insert(method.name.to_string(), datatype);
+                    }
+                }
+
+                // checked after the bodies, so inferred return types are known
+                for sig in required {
+                    let method = match methods.iter().find(|m| m.name == sig.name) {
+                        Some(method) => method,
+                        None => {
+                            self.error(
+                                ErrorKind::MissingMethod {
+                                    name: sig.name.to_string(),
+                                    datatype: trait_name.clone().unwrap_or_default(),
+                                },
+                                span,
+                            );
+                            continue;
+                        }
+                    };
+
+                    let null = Type::Builtin(BuiltinType::Null);
+                    let expected_ret = sig.ret_type.as_ref().unwrap_or(&null);
+                    let expected = fn_type(self, &sig.args, expected_ret, sig.span);
+                    let found = match &method.ret_type {
+                        Some(ret_type) => fn_type(self, &method.args, ret_type, method.span),
+                        None => None,
+                    };
+
+                    if let (Some(expected), Some(found)) = (expected, found) {
+                        if expected != found {
+                            self.mismatch(expected, found, method.span);
+                        }
+                    }
+                }
+            }
+            StatementKind::Export(statement) => self.check_statement(statement),
+            StatementKind::Break | StatementKind::Continue => {}
+        }
+    }
+
+    /// a value of type `found` written to `lvalue`, whose type is `expected`
+    fn check_store(
+        &mut self,
+        lvalue: &LValue,
+        stored: bool,
+        expected: Option<Type>,
+        found: Option<Type>,
+        found_span: Span,
+        span: Span,
+    ) {
+        match self.lookup(&lvalue.name) {
+            Some(Binding::Value(_, false)) if stored => self.error(
+                ErrorKind::ImmutableAssignment {
+                    name: lvalue.name.to_string(),
+                },
+                span,
+            ),
+            _ => {
+                if let (Some(expected), Some(found)) = (expected, found) {
+                    if !fits(self, &expected, &found) {
+                        self.mismatch(expected, found, found_span);
+                    }
+                }
+            }
+        }
+    }
+
+    /// the name and variants of `datatype` when it is an enum
+    fn enum_def(&self, datatype: &Type) -> Option<(String, Vec<Variant>)> {
+        match datatype {
+            Type::Alias(name) => match self.lookup_type(name) {
+                Some(Binding::Enum(variants)) => Some((name.to_string(), variants)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn check_variant(
+        &mut self,
+        name: &String,
+        variants: &[Variant],
+        expr: &mut Expr,
+    ) -> Option<Type> {
+        let span = expr.span;
+
+        let (variant_name, args) = match &mut expr.kind {
+            ExprKind::Identifier(variant_name, _) => (variant_name.clone(), vec![]),
+            ExprKind::Call(calle, args) => match &calle.kind {
+                ExprKind::Identifier(variant_name, _) => {
+                    let mut found = vec![];
+                    for arg in args.iter_mut() {
+                        found.push((self.check_expression(arg), arg.span));
+                    }
+                    (variant_name.clone(), found)
+                }
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        let variant = match variants.iter().find(|v| v.name == variant_name) {
+            Some(variant) => variant,
+            None => {
+                self.error(
+                    ErrorKind::UndefinedProperty {
+                        name: variant_name,
+                        prototype: name.to_string(),
+                    },
+                    span,
+                );
+                return None;
+            }
+        };
+
+        if variant.fields.len() != args.len() {
+            self.error(
+                ErrorKind::ArityMismatch {
+                    expected: variant.fields.len(),
+                    found: args.len(),
+                },
+                span,
+            );
+        } else {
+            for (i, (field, (arg, arg_span))) in variant.fields.iter().zip(args).enumerate() {
+                if let (Some(expected), Some(found)) = (self.resolve(field, arg_span), arg) {
+                    if !fits(self, &expected, &found) {
+                        self.error(
+                            ErrorKind::InvalidArgument {
+                                position: i + 1,
+                                expected,
+                                found,
+                            },
+                            arg_span,
+                        );
+                    }
+                }
+            }
+        }
+
+        Some(Type::Alias(name.to_string()))
+    }
+
+    /// checks the arms of a `match`. used as an expression, returns the type all arms agree on
+    fn check_match(
+        &mut self,
+        expr: &mut Expr,
+        arms: &mut [MatchArm],
+        span: Span,
+        is_expr: bool,
+    ) -> Option<Type> {
+        let datatype = self.check_expression(expr);
+        self.check_exhaustive(&datatype, arms, span);
+
+        // `return` inside an arm of a match expression gives the value of the arm
+        if is_expr {
+            self.functions.push(FnContext {
+                expected: None,
+                returns: vec![],
+            });
+        }
+
+        let mut types = vec![];
+        let mut complete = true;
+
+        for arm in arms.iter_mut() {
+            self.scopes.push(HashMap::new());
+            self.check_pattern(&mut arm.pattern, datatype.clone());
+
+            match &mut arm.body {
+                ArmBody::Expr(expr) => types.push(self.check_expression(expr)),
+                ArmBody::Block(block) => {
+                    self.check_block(block);
+                    complete = complete && always_returns(block);
+                }
+            }
+            self.scopes.pop();
+        }
+
+        if !is_expr {
+            return None;
+        }
+
+        let context = self.functions.pop().expect("match context should exist");
+        if !complete {
+            return None;
+        }
+
+        types.extend(context.returns.into_iter().map(|(t, _)| t));
+        agree(types)
+    }
+
+    /// declares the names bound by `pattern`
+    fn check_pattern(&mut self, pattern: &mut Pattern, expected: Option<Type>) {
+        let span = pattern.span;
+
+        match &mut pattern.kind {
+            PatternKind::Wildcard => {}
+            PatternKind::Literal(expr) => {
+                if let (Some(expected), Some(found)) = (expected, self.check_expression(expr)) {
+                    if !fits(self, &expected, &found) {
+                        self.mismatch(expected, found, span);
+                    }
+                }
+            }
+            PatternKind::Ident(name) => {
+                if let Some((_, variants)) = expected.as_ref().and_then(|t| self.enum_def(t)) {
+                    if let Some(variant) = variants.iter().find(|v| &v.name == name) {
+                        if !variant.fields.is_empty() {
+                            self.error(
+                                ErrorKind::ArityMismatch {
+                                    expected: variant.fields.len(),
+                                    found: 0,
+                                },
+                                span,
+                            );
+                        }
+                        return;
+                    }
+                }
+
+                self.declare(name, Binding::Value(expected, false), span);
+            }
+            PatternKind::Variant(enum_name, name, patterns) => {
+                let def = match enum_name {
+                    Some(enum_name) => match self.lookup_type(enum_name) {
+                        Some(Binding::Enum(variants)) => {
+                            let found = Type::Alias(enum_name.to_string());
+                            if let Some(expected) = expected {
+                                if !fits(self, &expected, &found) {
+                                    self.mismatch(expected, found, span);
+                                }
+                            }
+                            Some((enum_name.to_string(), variants))
+                        }
+                        Some(_) => {
+                            self.error(
+                                ErrorKind::NotAType {
+                                    name: enum_name.to_string(),
+                                },
+                                span,
+                            );
+                            None
+                        }
+                        None => {
+                            self.error(
+                                ErrorKind::UndefinedType {
+                                    name: enum_name.to_string(),
+                                },
+                                span,
+                            );
+                            None
+                        }
+                    },
+                    None => expected.as_ref().and_then(|t| self.enum_def(t)),
+                };
+
+                let fields = match def {
+                    Some((enum_name, variants)) => {
+                        match variants.iter().find(|v| &v.name == name) {
+                            Some(variant) if variant.fields.len() != patterns.len() => {
+                                self.error(
+                                    ErrorKind::ArityMismatch {
+                                        expected: variant.fields.len(),
+                                        found: patterns.len(),
+                                    },
+                                    span,
+                                );
+                                None
+                            }
+                            Some(variant) => Some(variant.fields.clone()),
+                            None => {
+                                self.error(
+                                    ErrorKind::UndefinedProperty {
+                                        name: name.to_string(),
+                                        prototype: enum_name,
+                                    },
+                                    span,
+                                );
+                                None
+                            }
+                        }
+                    }
+                    None => None,
+                };
+
+                for (i, pattern) in patterns.iter_mut().enumerate() {
+                    let datatype = match &fields {
+                        Some(fields) => self.resolve(&fields[i], pattern.span),
+                        None => None,
+                    };
+                    self.check_pattern(pattern, datatype);
+                }
+            }
+            PatternKind::Tuple(patterns) => {
+                let items = match expected {
+                    Some(Type::Builtin(BuiltinType::Tuple(items)))
+                        if items.len() == patterns.len() =>
+                    {
+                        Some(items)
+                    }
+                    _ => None,
+                };
+
+                for (i, pattern) in patterns.iter_mut().enumerate() {
+                    let datatype = items.as_ref().map(|items| items[i].clone());
+                    self.check_pattern(pattern, datatype);
+                }
+            }
+        }
+    }
+
+    /// reports the variants (or `_`) that no arm covers
+    fn check_exhaustive(&mut self, datatype: &Option<Type>, arms: &[MatchArm], span: Span) {
+        let datatype = match datatype {
+            Some(datatype) if !datatype.is_opaque() => datatype,
+            _ => return,
+        };
+        let def = self.enum_def(datatype);
+        let variants = def.as_ref().map(|(_, variants)| variants);
+
+        if arms.iter().any(|arm| irrefutable(&arm.pattern, variants)) {
+            return;
+        }
+
+        let missing: Vec<String> = match (&def, datatype) {
+            (Some((enum_name, variants)), _) => variants
+                .iter()
+                .filter(|variant| {
+                    !arms.iter().any(|arm| match &arm.pattern.kind {
+                        PatternKind::Ident(name) => name == &variant.name,
+                        PatternKind::Variant(_, name, patterns) => {
+                            name == &variant.name
+                                && patterns.iter().all(|pattern| irrefutable(pattern, None))
+                        }
+                        _ => false,
+                    })
+                })
+                .map(|variant| format!("{}::{}", enum_name, variant.name))
+                .collect(),
+            (None, Type::Builtin(BuiltinType::Bool)) => [true, false]
+                .iter()
+                .filter(|b| {
+                    !arms.iter().any(|arm| match &arm.pattern.kind {
+                        PatternKind::Literal(expr) => expr.kind == ExprKind::Bool(**b),
+                        _ => false,
+                    })
+                })
+                .map(|b| b.to_string())
+                .collect(),
+            (None, _) => vec!["_".to_string()],
+        };
+
+        for missing in missing {
+            self.error(ErrorKind::NonExhaustiveMatch { missing }, span);
+        }
+    }
+
+    fn check_struct(&mut self, name: &String, props: &mut [Prop], span: Span) -> Option<Type> {
+        let fields = match self.lookup_type(name) {
+            Some(Binding::Struct(fields)) => fields,
+            Some(_) => {
+                self.error(
+                    ErrorKind::NotAType {
+                        name: name.to_string(),
+                    },
+                    span,
+                );
+                return None;
+            }
+            None => {
+                self.error(
+                    ErrorKind::UndefinedType {
+                        name: name.to_string(),
+                    },
+                    span,
+                );
+                return None;
+            }
+        };
+
+        let mut keys: Vec<String> = vec![];
+
+        for prop in props.iter_mut() {
+            let found = self.check_expression(&mut prop.value);
+
+            if keys.contains(&prop.key) {
+                self.error(
+                    ErrorKind::DuplicateProperty {
+                        name: prop.key.to_string(),
+                    },
+                    prop.span,
+                );
+                continue;
+            }
+            keys.push(prop.key.to_string());
+
+            let field = match fields.iter().find(|field| field.name == prop.key) {
+                Some(field) => field,
+                None => {
+                    self.error(
+                        ErrorKind::UndefinedProperty {
+                            name: prop.key.to_string(),
+                            prototype: name.to_string(),
+                        },
+                        prop.span,
+                    );
+                    continue;
+                }
+            };
+
+            if let (Some(expected), Some(found)) = (self.resolve(&field.datatype, prop.span), found)
+            {
+                if !fits(self, &expected, &found) {
+                    self.mismatch(expected, found, prop.value.span);
+                }
+            }
+        }
+
+        for field in fields.iter() {
+            if !keys.contains(&field.name) {
+                self.error(
+                    ErrorKind::MissingField {
+                        name: field.name.to_string(),
+                        datatype: name.to_string(),
+                    },
+                    span,
+                );
+            }
+        }
+
+        Some(Type::Alias(name.to_string()))
+    }
+
+    /// records that `prototype` implements `trait_name`, returns the methods it requires
+    fn check_impl(&mut self, prototype: &str, trait_name: &String, span: Span) -> Vec<MethodSig> {
+        let required = match self.lookup_type(trait_name) {
+            Some(Binding::Trait(required)) => required,
+            Some(_) => {
+                self.error(
+                    ErrorKind::NotATrait {
+                        name: trait_name.to_string(),
+                    },
+                    span,
+                );
+                return vec![];
+            }
+            None => {
+                self.error(
+                    ErrorKind::UndefinedType {
+                        name: trait_name.to_string(),
+                    },
+                    span,
+                );
+                return vec![];
+            }
+        };
+
+        let traits = self.impls.entry(prototype.to_string()).or_default();
+        if traits.contains(trait_name) {
+            self.error(
+                ErrorKind::DuplicateImpl {
+                    name: trait_name.to_string(),
+                    datatype: prototype.to_string(),
+                },
+                span,
+            );
+        } else {
+            traits.push(trait_name.to_string());
+        }
+        required
+    }
+
+    /// whether `name` is a trait that `datatype` implements
+    fn implements(&self, datatype: &Type, name: &String) -> bool {
+        match self.lookup_type(name) {
+            Some(Binding::Trait(_)) => self
+                .impls
+                .get(&datatype.prototype())
+                .is_some_and(|traits| traits.contains(name)),
+            _ => false,
+        }
+    }
+
+    /// type of `field` when `datatype` is a struct, or of a method declared in an `impl` block
+    fn check_field(&mut self, datatype: &Type, field: &String, span: Span) -> Option<Type> {
+        if let Type::Alias(name) = datatype {
+            if let Some(Binding::Struct(fields)) = self.lookup_type(name) {
+                if let Some(field) = fields.iter().find(|f| &f.name == field) {
+                    return self.resolve(&field.datatype, field.span);
+                }
+            }
+        }
+
+        self.check_method(datatype, field, span)
+    }
+
+    /// type of the method `name` of `datatype`, structs and enums have no builtin methods
+    fn check_method(&mut self, datatype: &Type, name: &String, span: Span) -> Option<Type> {
+        let prototype = datatype.prototype();
+
+        if let Some(method) = self.methods.get(&prototype).and_then(|m| m.get(name)) {
+            return method.clone();
+        }
+
+        // values typed as a trait only have the methods the trait requires
+        if let Some(Binding::Trait(methods)) = self.lookup_type(&prototype) {
+            if let Some(method) = methods.iter().find(|m| &m.name == name) {
+                let null = Type::Builtin(BuiltinType::Null);
+                let ret_type = method.ret_type.as_ref().unwrap_or(&null);
+                return fn_type(self, &method.args, ret_type, method.span);
+            }
+        }
+
+        if let Some(Binding::Struct(_)) | Some(Binding::Enum(_)) | Some(Binding::Trait(_)) =
+            self.lookup_type(&prototype)
+        {
+            self.error(
+                ErrorKind::UndefinedProperty {
+                    name: name.to_string(),
+                    prototype,
+                },
+                span,
+            );
+        }
+        None
+    }
+
+    fn check_variable(
+        &mut self,
+        name: &String,
+        datatype: &Option<Type>,
+        expr: &mut Expr,
+        mutable: bool,
+        span: Span,
+    ) {
+        let found = self.check_expression(expr);
+
+        let datatype = match datatype {
+            Some(datatype) => {
+                let expected = self.resolve(datatype, span);
+
+                if let (Some(expected), Some(found)) = (expected.clone(), found) {
+                    if !fits(self, &expected, &found) {
+                        self.mismatch(expected, found, expr.span);
+                    }
+                }
+                expected
+            }
+            None => found,
+        };
+
+        self.declare(name, Binding::Value(datatype, mutable), span);
+    }
+
+    /// the type of the place `lvalue` writes to, and whether the variable itself is stored.
+    /// items of lists and properties of objects change in place, instances are values
+    fn check_lvalue(&mut self, lvalue: &mut LValue, span: Span) -> (Option<Type>, bool) {
+        let mut datatype = match self.lookup(&lvalue.name) {
+            Some(Binding::Value(datatype, _)) => datatype.clone(),
+            _ => None,
+        };
+        let mut stored = true;
+
+        for access in lvalue.path.iter_mut() {
+            datatype = match (access, datatype) {
+                (Access::Index(index), datatype) => {
+                    if let Some(found) = self.check_expression(index) {
+                        if found != Type::Builtin(BuiltinType::Int) {
+                            self.mismatch(Type::Builtin(BuiltinType::Int), found, index.span);
+                        }
+                    }
+
+                    match datatype {
+                        Some(Type::Builtin(BuiltinType::List(t))) => {
+                            stored = false;
+                            Some(*t)
+                        }
+                        Some(t @ Type::Builtin(BuiltinType::String))
+                        | Some(t @ Type::Builtin(BuiltinType::Tuple(_))) => {
+                            let datatype = String::from(t);
+                            self.error(ErrorKind::NotAssignable { datatype }, span);
+                            return (None, false);
+                        }
+                        Some(t) if !t.is_opaque() => {
+                            let datatype = String::from(t);
+                            self.error(ErrorKind::NotIndexable { datatype }, span);
+                            return (None, false);
+                        }
+                        _ => {
+                            stored = false;
+                            None
+                        }
+                    }
+                }
+                (Access::Property(name), Some(Type::Alias(datatype))) => {
+                    match self.lookup_type(&datatype) {
+                        Some(Binding::Struct(fields)) => {
+                            match fields.iter().find(|field| &field.name == name) {
+                                Some(field) => self.resolve(&field.datatype, field.span),
+                                None => {
+                                    let name = name.to_string();
+                                    let prototype = datatype;
+                                    self.error(
+                                        ErrorKind::UndefinedProperty { name, prototype },
+                                        span,
+                                    );
+                                    return (None, false);
+                                }
+                            }
+                        }
+                        _ => {
+                            stored = false;
+                            None
+                        }
+                    }
+                }
+                (Access::Property(_), Some(t)) => {
+                    let datatype = String::from(t);
+                    self.error(ErrorKind::NotAssignable { datatype }, span);
+                    return (None, false);
+                }
+                (Access::Property(_), None) => {
+                    stored = false;
+                    None
+                }
+            };
+        }
+
+        (datatype, stored)
+    }
+
+    fn check_condition(&mut self, cond: &mut Expr) {
+        if let Some(found) = self.check_expression(cond) {
+            if found != Type::Builtin(BuiltinType::Bool) {
+                self.mismatch(Type::Builtin(BuiltinType::Bool), found, cond.span);
+            }
+        }
+    }
+
+    /// checks the body against the declared return type, or infers it when there is none.
+    /// returns the type of the function itself
+    fn check_fn(
+        &mut self,
+        args: &Vec<Arg>,
+        ret_type: &mut Option<Type>,
+        block: &mut Block,
+        span: Span,
+    ) -> Option<Type> {
+        let expected = match ret_type {
+            Some(ret_type) => self.resolve(ret_type, span),
+            None => None,
+        };
+
+        self.scopes.push(HashMap::new());
+        for arg in args {
+            let datatype = self.resolve(&arg.datatype, arg.span);
+            self.declare(&arg.ident, Binding::Value(datatype, true), arg.span);
+        }
+
+        self.functions.push(FnContext {
+            expected: expected.clone(),
+            returns: vec![],
+        });
+        self.check_block(block);
+        let function = self.functions.pop().expect("function context should exist");
+        self.scopes.pop();
+
+        if ret_type.is_some() {
+            if let Some(expected) = expected {
+                let null = Type::Builtin(BuiltinType::Null);
+                if expected != null && !always_returns(block) {
+                    self.mismatch(expected, null, span);
+                }
+            }
+
+            return fn_type(self, args, ret_type.as_ref().unwrap(), span);
+        }
+
+        let mut inferred = Some(Type::Builtin(BuiltinType::Null));
+        let mut first: Option<Type> = None;
+
+        for (found, span) in function.returns {
+            match (found, &first) {
+                (None, _) => inferred = None,
+                (Some(found), None) => {
+                    if inferred.is_some() {
+                        inferred = Some(found.clone());
+                    }
+                    first = Some(found);
+                }
+                (Some(found), Some(expected)) => {
+                    if !fits(self, expected, &found) {
+                        self.mismatch(expected.clone(), found, span);
+                        inferred = None;
+                    }
+                }
+            }
+        }
+
+        if first.is_none() && inferred.is_none() {
+            return None;
+        }
+
+        let inferred = inferred?;
+        *ret_type = Some(inferred.clone());
+
+        fn_type(self, args, &inferred, span)
+    }
+
+    fn check_expression(&mut self, expr: &mut Expr) -> Option<Type> {
+        let span = expr.span;
+        // `?.` skips a `null` receiver, so there is nothing to check on it
+        let safe = matches!(
+            expr.kind,
+            ExprKind::SafeMethodCall(..) | ExprKind::SafeIndex(..)
+        );
+        let receiver = |datatype: Option<Type>| {
+            datatype.filter(|t| !(safe && *t == Type::Builtin(BuiltinType::Null)))
+        };
+
+        match &mut expr.kind {
+            ExprKind::Null => Some(Type::Builtin(BuiltinType::Null)),
+            ExprKind::Int(_) => Some(Type::Builtin(BuiltinType::Int)),
+            ExprKind::Float(_) => Some(Type::Builtin(BuiltinType::Float)),
+            ExprKind::String(_) => Some(Type::Builtin(BuiltinType::String)),
+            ExprKind::Bool(_) => Some(Type::Builtin(BuiltinType::Bool)),
+            ExprKind::List(items) => {
+                let mut first: Option<Type> = None;
+                let mut known = true;
+
+                for item in items.iter_mut() {
+                    match (self.check_expression(item), &first) {
+                        (Some(found), Some(expected)) => {
+                            if &found != expected {
+                                self.mismatch(expected.clone(), found, item.span);
+                                known = false;
+                            }
+                        }
+                        (Some(found), None) => first = Some(found),
+                        (None, _) => known = false,
+                    }
+                }
+
+                if !known {
+                    return None;
+                }
+
+                Some(Type::Builtin(BuiltinType::List(Box::new(first?))))
+            }
+            ExprKind::Object(props) => {
+                for prop in props {
+                    self.check_expression(&mut prop.value);
+                }
+                Some(Type::Alias("object".to_string()))
+            }
+            ExprKind::Struct(name, props) => self.check_struct(name, props, span),
+            ExprKind::Identifier(name, _) => match self.lookup(name) {
+                Some(Binding::Value(datatype, _)) => datatype.clone(),
+                Some(Binding::Module(_)) => Some(Type::Alias("module".to_string())),
+                _ => None,
+            },
+            ExprKind::Call(callee, args) => {
+                let callee = self.check_expression(callee);
+                self.check_call(callee, args, span)
+            }
+            ExprKind::MethodCall(object, calle) | ExprKind::SafeMethodCall(object, calle) => {
+                let datatype = receiver(self.check_expression(object));
+
+                match &mut calle.kind {
+                    ExprKind::Identifier(name, _) => self.check_field(&datatype?, name, calle.span),
+                    ExprKind::Call(_, args) => {
+                        for arg in args {
+                            self.check_expression(arg);
+                        }
+                        None
+                    }
+                    _ => None,
+                }
+            }
+            ExprKind::ModuleCall(paths, _, expr) => {
+                if let [name] = paths.as_slice() {
+                    if let Some(Binding::Enum(variants)) = self.lookup(name).cloned() {
+                        return self.check_variant(name, &variants, expr);
+                    }
+                }
+
+                // names after the path live in the module, only the arguments are checked here
+                if let ExprKind::Call(_, args) = &mut expr.kind {
+                    for arg in args {
+                        self.check_expression(arg);
+                    }
+                }
+                None
+            }
+            ExprKind::Index(expr, loc) | ExprKind::SafeIndex(expr, loc) => {
+                let datatype = receiver(self.check_expression(expr));
+                let index = self.check_expression(loc);
+
+                if let Some(index) = index {
+                    if index != Type::Builtin(BuiltinType::Int) {
+                        self.mismatch(Type::Builtin(BuiltinType::Int), index, loc.span);
+                        return None;
+                    }
+                }
+
+                match datatype {
+                    Some(Type::Builtin(BuiltinType::List(t))) => Some(*t),
+                    Some(Type::Builtin(BuiltinType::String)) => {
+                        Some(Type::Builtin(BuiltinType::String))
+                    }
+                    Some(Type::Builtin(BuiltinType::Tuple(items))) => match &loc.kind {
+                        ExprKind::Int(i) => items.get(*i as usize).cloned(),
+                        _ => None,
+                    },
+                    Some(t) if !t.is_opaque() => {
+                        self.error(
+                            ErrorKind::NotIndexable {
+                                datatype: String::from(t),
+                            },
+                            expr.span,
+                        );
+                        None
+                    }
+                    _ => None,
+                }
+            }
+            // the chain can end in `null` anywhere, so its type isn't known
+            ExprKind::SafeChain(chain) => {
+                self.check_expression(chain);
+                None
+            }
+            ExprKind::BinaryOp(lhs, op, rhs) => {
+                let lhs = self.check_expression(lhs);
+                let rhs = self.check_expression(rhs);
+
+                self.check_binary(lhs, op, rhs, span)
+            }
+            ExprKind::UnaryOp(op, expr) => {
+                let found = self.check_expression(expr);
+
+                match op {
+                    UnaryOpKind::Not => {
+                        if let Some(found) = found {
+                            if found != Type::Builtin(BuiltinType::Bool) {
+                                self.error(
+                                    ErrorKind::InvalidOperand {
+                                        op: "!".to_string(),
+                                        operand: String::from(found),
+                                    },
+                                    span,
+                                );
+                            }
+                        }
+                        Some(Type::Builtin(BuiltinType::Bool))
+                    }
+                    UnaryOpKind::Neg | UnaryOpKind::BitNot => {
+                        let found = found?;
+                        let (symbol, res) = match op {
+                            UnaryOpKind::Neg => ("-", numeric_type(&found, &found)),
+                            _ => ("~", int_type(&found, &found)),
+                        };
+
+                        if res.is_none() && !found.is_opaque() {
+                            self.error(
+                                ErrorKind::InvalidOperand {
+                                    op: symbol.to_string(),
+                                    operand: String::from(found),
+                                },
+                                span,
+                            );
+                        }
+                        res
+                    }
+                    UnaryOpKind::Typeof => Some(Type::Builtin(BuiltinType::String)),
+                }
+            }
+            ExprKind::Fn(args, ret_type, block) => self.check_fn(args, ret_type, block, span),
+            ExprKind::Module(block) => {
+                self.check_module(block);
+                Some(Type::Alias("module".to_string()))
+            }
+            ExprKind::If(branches, else_block) => {
+                // `return` inside the blocks of an if expression gives the value of the expression
+                self.functions.push(FnContext {
+                    expected: None,
+                    returns: vec![],
+                });
+
+                let mut complete = else_block.is_some();
+                for branch in branches {
+                    self.check_condition(&mut branch.condition);
+                    self.check_block(&mut branch.statements);
+                    complete = complete && always_returns(&branch.statements);
+                }
+
+                if let Some(block) = else_block {
+                    self.check_block(block);
+                    complete = complete && always_returns(block);
+                }
+
+                let context = self.functions.pop().expect("if context should exist");
+                if !complete {
+                    return None;
+                }
+
+                agree(context.returns.into_iter().map(|(t, _)| t).collect())
+            }
+            ExprKind::Match(expr, arms) => self.check_match(expr, arms, span, true),
+            ExprKind::Tuple(items) => {
+                let mut types = vec![];
+                for item in items {
+                    types.push(self.check_expression(item));
+                }
+                let types = types.into_iter().collect::<Option<Vec<Type>>>()?;
+                Some(Type::Builtin(BuiltinType::Tuple(types)))
+            }
+            ExprKind::Range(start, end) => {
+                for expr in [start, end] {
+                    if let Some(found) = self.check_expression(expr) {
+                        if found != Type::Builtin(BuiltinType::Int) {
+                            self.mismatch(Type::Builtin(BuiltinType::Int), found, expr.span);
+                        }
+                    }
+                }
+                Some(Type::Builtin(BuiltinType::List(Box::new(Type::Builtin(
+                    BuiltinType::Int,
+                )))))
+            }
+            ExprKind::Interpolation(fragments) => {
+                for fragment in fragments {
+                    if let Fragment::Value(expr, spec) = fragment {
+                        let found = self.check_expression(expr);
+
+                        // only ints can be written in another base
+                        let radix = spec.as_ref().and_then(|spec| spec.radix);
+                        if let (Some(radix), Some(found)) = (radix, found) {
+                            if found != Type::Builtin(BuiltinType::Int) && !found.is_opaque() {
+                                self.error(
+                                    ErrorKind::InvalidFormat {
+                                        format: radix.to_string(),
+                                        datatype: String::from(found),
+                                    },
+                                    expr.span,
+                                );
+                            }
+                        }
+                    }
+                }
+                Some(Type::Builtin(BuiltinType::String))
+            }
+        }
+    }
+
+    /// mirrors the operator implementations on `Value`
+    /// checks the arguments of a call against the type of the callee
+    fn check_call(&mut self, callee: Option<Type>, args: &mut [Expr], span: Span) -> Option<Type> {
+        let mut found = vec![];
+
+        for arg in args.iter_mut() {
+            found.push((self.check_expression(arg), arg.span));
+        }
+
+        match callee {
+            Some(Type::Builtin(BuiltinType::Fn(params, ret_type))) => {
+                if params.len() != found.len() {
+                    self.error(
+                        ErrorKind::ArityMismatch {
+                            expected: params.len(),
+                            found: found.len(),
+                        },
+                        span,
+                    );
+                    return Some(*ret_type);
+                }
+
+                for (i, (param, (arg, arg_span))) in params.iter().zip(found).enumerate() {
+                    let param = match self.resolve(param, arg_span) {
+                        Some(param) => param,
+                        None => continue,
+                    };
+
+                    if let Some(arg) = arg {
+                        if !fits(self, &param, &arg) {
+                            self.error(
+                                ErrorKind::InvalidArgument {
+                                    position: i + 1,
+                                    expected: param,
+                                    found: arg,
+                                },
+                                arg_span,
+                            );
+                        }
+                    }
+                }
+
+                Some(*ret_type)
+            }
+            Some(t) if !t.is_opaque() => {
+                self.error(
+                    ErrorKind::NotCallable {
+                        datatype: String::from(t),
+                    },
+                    span,
+                );
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn check_binary(
+        &mut self,
+        lhs: Option<Type>,
+        op: &BinaryOpKind,
+        rhs: Option<Type>,
+        span: Span,
+    ) -> Option<Type> {
+        let bool = Type::Builtin(BuiltinType::Bool);
+
+        match op {
+            BinaryOpKind::EQ
+            | BinaryOpKind::NE
+            | BinaryOpKind::GT
+            | BinaryOpKind::GTE
+            | BinaryOpKind::LT
+            | BinaryOpKind::LTE => return Some(bool),
+            BinaryOpKind::And | BinaryOpKind::Or => {
+                for found in [lhs, rhs].into_iter().flatten() {
+                    if found != bool {
+                        self.mismatch(bool.clone(), found, span);
+                    }
+                }
+                return Some(bool);
+            }
+            BinaryOpKind::Coalesce => {
+                return match (lhs?, rhs?) {
+                    (Type::Builtin(BuiltinType::Null), rhs) => Some(rhs),
+                    (lhs, rhs) if lhs == rhs => Some(lhs),
+                    _ => None,
+                };
+            }
+            _ => {}
+        }
+
+        let (lhs, rhs) = (lhs?, rhs?);
+
+        let (symbol, res) = match op {
+            BinaryOpKind::Add => ("+", add_type(&lhs, &rhs)),
+            BinaryOpKind::Sub => ("-", numeric_type(&lhs, &rhs)),
+            BinaryOpKind::Mul => ("*", numeric_type(&lhs, &rhs)),
+            BinaryOpKind::Div => ("/", numeric_type(&lhs, &rhs)),
+            BinaryOpKind::Rem => ("%", numeric_type(&lhs, &rhs)),
+            BinaryOpKind::Pow => ("**", numeric_type(&lhs, &rhs)),
+            BinaryOpKind::BitAnd => ("&", bitwise_type(&lhs, &rhs)),
+            BinaryOpKind::BitOr => ("|", bitwise_type(&lhs, &rhs)),
+            BinaryOpKind::BitXor => ("^", bitwise_type(&lhs, &rhs)),
+            BinaryOpKind::Shl => ("<<", int_type(&lhs, &rhs)),
+            BinaryOpKind::Shr => (">>", int_type(&lhs, &rhs)),
+            _ => unreachable!(),
+        };
+
+        if res.is_none() && !lhs.is_opaque() && !rhs.is_opaque() {
+            self.error(
+                ErrorKind::InvalidOperands {
+                    op: symbol.to_string(),
+                    lhs: String::from(lhs),
+                    rhs: String::from(rhs),
+                },
+                span,
+            );
+        }
+
+        res
+    }
+}
+
+fn fn_type(checker: &mut Checker, args: &Vec<Arg>, ret_type: &Type, span: Span) -> Option<Type> {
+    let mut types = vec![];
+    for arg in args {
+        types.push(checker.resolve(&arg.datatype, arg.span));
+    }
+    let ret_type = checker.resolve(ret_type, span);
+    let types = types.into_iter().collect::<Option<Vec<Type>>>()?;
+
+    Some(Type::Builtin(BuiltinType::Fn(types, Box::new(ret_type?))))
+}
+
+/// the module path and the name of a type named by its path, `a::b::T`
+fn split_path(name: &str) -> Option<(Vec<String>, &str)> {
+    let (module, name) = name.rsplit_once("::")?;
+    Some((module.split("::").map(String::from).collect(), name))
+}
+
+fn fits(checker: &Checker, expected: &Type, found: &Type) -> bool {
+    if expected == found {
+        return true;
+    }
+
+    match (expected, found) {
+        (Type::Alias(name), Type::Builtin(BuiltinType::Fn(..))) => name == "function",
+        (Type::Builtin(BuiltinType::List(e)), Type::Builtin(BuiltinType::List(f))) => {
+            fits(checker, e, f)
+        }
+        (Type::Builtin(BuiltinType::Tuple(e)), Type::Builtin(BuiltinType::Tuple(f))) => {
+            e.len() == f.len() && e.iter().zip(f).all(|(e, f)| fits(checker, e, f))
+        }
+        (Type::Builtin(BuiltinType::Fn(ea, er)), Type::Builtin(BuiltinType::Fn(fa, fr))) => {
+            ea == fa && fits(checker, er, fr)
+        }
+        (Type::Alias(name), found) => checker.implements(found, name),
+        _ => false,
+    }
+}
+
+fn add_type(lhs: &Type, rhs: &Type) -> Option<Type> {
+    use BuiltinType::*;
+
+    match (lhs, rhs) {
+        (Type::Builtin(String), Type::Builtin(Int | Float | String | List(_))) => {
+            Some(Type::Builtin(String))
+        }
+        (Type::Builtin(Int | Float), Type::Builtin(String)) => Some(Type::Builtin(String)),
+        _ => numeric_type(lhs, rhs),
+    }
+}
+
+fn numeric_type(lhs: &Type, rhs: &Type) -> Option<Type> {
+    use BuiltinType::*;
+
+    match (lhs, rhs) {
+        (Type::Builtin(Int), Type::Builtin(Int)) => Some(Type::Builtin(Int)),
+        (Type::Builtin(Int | Float), Type::Builtin(Int | Float)) => Some(Type::Builtin(Float)),
+        _ => None,
+    }
+}
+
+fn int_type(lhs: &Type, rhs: &Type) -> Option<Type> {
+    use BuiltinType::*;
+
+    match (lhs, rhs) {
+        (Type::Builtin(Int), Type::Builtin(Int)) => Some(Type::Builtin(Int)),
+        _ => None,
+    }
+}
+
+/// `&`, `|` and `^` work on the bits of ints and on bools
+fn bitwise_type(lhs: &Type, rhs: &Type) -> Option<Type> {
+    use BuiltinType::*;
+
+    match (lhs, rhs) {
+        (Type::Builtin(Bool), Type::Builtin(Bool)) => Some(Type::Builtin(Bool)),
+        _ => int_type(lhs, rhs),
+    }
+}
+
+/// whether every path through the block ends in a `return`
+fn always_returns(block: &Block) -> bool {
+    block.iter().any(|statement| match &statement.kind {
+        StatementKind::Return(_) => true,
+        StatementKind::If(branches, Some(else_block)) => {
+            branches
+                .iter()
+                .all(|branch| always_returns(&branch.statements))
+                && always_returns(else_block)
+        }
+        // exhaustiveness is checked separately
+        StatementKind::Match(_, arms) => {
+            !arms.is_empty()
+                && arms.iter().all(|arm| match &arm.body {
+                    ArmBody::Block(block) => always_returns(block),
+                    ArmBody::Expr(_) => false,
+                })
+        }
+        // `while true` is only left through a `return` or a `break`
+        StatementKind::While(condition, body) => {
+            matches!(condition.kind, ExprKind::Bool(true)) && !breaks(body)
+        }
+        _ => false,
+    })
+}
+
+/// whether `block`, the body of a loop, has a `break` that leaves that loop
+fn breaks(block: &Block) -> bool {
+    block.iter().any(|statement| match &statement.kind {
+        StatementKind::Break => true,
+        StatementKind::If(branches, else_block) => {
+            branches.iter().any(|branch| breaks(&branch.statements))
+                || else_block.as_ref().is_some_and(breaks)
+        }
+        StatementKind::Match(_, arms) => arms.iter().any(|arm| match &arm.body {
+            ArmBody::Block(block) => breaks(block),
+            ArmBody::Expr(_) => false,
+        }),
+        // a `break` inside an inner loop leaves the inner loop
+        _ => false,
+    })
+}
+
+/// the type shared by all `types`, if they are all known
+fn agree(types: Vec<Option<Type>>) -> Option<Type> {
+    let first = types.first()?.clone()?;
+
+    for datatype in types.iter() {
+        if datatype.as_ref() != Some(&first) {
+            return None;
+        }
+    }
+
+    Some(first)
+}
+
+/// whether `pattern` matches every value, `variants` are those of the matched enum
+fn irrefutable(pattern: &Pattern, variants: Option<&Vec<Variant>>) -> bool {
+    match &pattern.kind {
+        PatternKind::Wildcard => true,
+        PatternKind::Ident(name) => match variants {
+            Some(variants) => !variants.iter().any(|v| &v.name == name),
+            None => true,
+        },
+        PatternKind::Tuple(patterns) => patterns.iter().all(|p| irrefutable(p, None)),
+        PatternKind::Literal(_) | PatternKind::Variant(..) => false,
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt;
+
+use lalrpop_util::ParseError;
+
+use crate::literal;
+use crate::span::{Source, SourceId, Span};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Option<Span>,
+    pub backtrace: Vec<(String, Option<Span>)>,
+}
+
+impl Diagnostic {
+    pub fn new(message: String, span: Option<Span>) -> Self {
+        Self {
+            message,
+            span,
+            backtrace: vec![],
+        }
+    }
+
+    pub fn with_backtrace(mut self, backtrace: Vec<(String, Option<Span>)>) -> Self {
+        self.backtrace = backtrace;
+        self
+    }
+
+    pub fn from_parse_error<T: fmt::Display>(
+        source: SourceId,
+        error: ParseError<usize, T, Diagnostic>,
+    ) -> Self {
+        match error {
+            ParseError::InvalidToken { location } => Diagnostic::new(
+                "invalid token".to_string(),
+                Some(Span::new(source, location, location + 1)),
+            ),
+            ParseError::UnrecognizedEof { location, expected } => Diagnostic::new(
+                format!("unexpected end of file{}", expected_list(&expected)),
+                Some(Span::new(source, location, location)),
+            ),
+            // the digits of the smallest int only parse after a `-`
+            ParseError::UnrecognizedToken {
+                token: (start, token, end),
+                ..
+            } if token.to_string() == literal::MIN_INT => {
+                literal::int_out_of_range(Span::new(source, start, end))
+            }
+            ParseError::UnrecognizedToken {
+                token: (start, token, end),
+                expected,
+            } => Diagnostic::new(
+                format!("unexpected token `{}`{}", token, expected_list(&expected)),
+                Some(Span::new(source, start, end)),
+            ),
+            ParseError::ExtraToken {
+                token: (start, token, end),
+            } => Diagnostic::new(
+                format!("extra token `{}`", token),
+                Some(Span::new(source, start, end)),
+            ),
+            ParseError::User { error } => error,
+        }
+    }
+
+    /// renders the message, the source snippet and the stack backtrace if there is one
+    pub fn render(&self) -> String {
+        let mut res = format!("error: {}", self.message);
+
+        if let Some(span) = self.span {
+            res.push_str(&snippet(span));
+        }
+
+        if !self.backtrace.is_empty() {
+            res.push_str("\nstack backtrace:");
+
+            let mut i = 0;
+            while i < self.backtrace.len() {
+                let (name, span) = &self.backtrace[i];
+                res.push_str(&format!("\n{:>4}: {}", i, name));
+
+                if let Some((path, line, col)) = span.and_then(|span| span.position()) {
+                    res.push_str(&format!("\n        at {}:{}:{}", path, line, col));
+                }
+
+                // a recursion shows the same frame over and over, it is written once
+                let repeats = self.backtrace[i + 1..]
+                    .iter()
+                    .take_while(|frame| *frame == &self.backtrace[i])
+                    .count();
+                if repeats > 1 {
+                    res.push_str(&format!("\n      ... repeated {} more times", repeats));
+                    i += repeats;
+                }
+                i += 1;
+            }
+        }
+
+        res
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render())
+    }
+}
+
+/// the `--> file:line:col` header followed by the source line and a caret underline
+fn snippet(span: Span) -> String {
+    let mut res = String::new();
+
+    let source = match Source::get(span.source) {
+        Some(source) => source,
+        None => return res,
+    };
+
+    let (line, col) = source.line_col(span.start);
+    let (end_line, end_col) = source.line_col(span.end);
+    let text = source.line(line);
+
+    let width = if end_line == line && end_col > col {
+        end_col - col
+    } else if end_line > line {
+        (text.chars().count() + 1).saturating_sub(col).max(1)
+    } else {
+        1
+    };
+
+    let gutter = " ".repeat(line.to_string().len());
+
+    res.push_str(&format!("\n{}--> {}:{}:{}", gutter, source.path, line, col));
+    res.push_str(&format!("\n{} |", gutter));
+    res.push_str(&format!("\n{} | {}", line, text));
+    res.push_str(&format!(
+        "\n{} | {}{}",
+        gutter,
+        caret_padding(text, col),
+        "^".repeat(width)
+    ));
+
+    res
+}
+
+/// keeps tabs in the padding so the caret lines up with the source line
+fn caret_padding(text: &str, col: usize) -> String {
+    text.chars()
+        .take(col - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect()
+}
+
+fn expected_list(expected: &[String]) -> String {
+    if expected.is_empty() {
+        return String::new();
+    }
+
+    let mut names: Vec<String> = vec![];
+    for name in expected.iter().map(|token| token_name(token)) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    format!(", expected one of {}", names.join(", "))
+}
+
+/// lalrpop reports regex terminals by their pattern, which is not very helpful in a message
+fn token_name(token: &str) -> String {
+    if token.starts_with("r#") {
+        if token.contains("[a-zA-Z_]") {
+            return "identifier".to_string();
+        }
+        if token.contains(r"\.") {
+            return "float".to_string();
+        }
+        if token.contains("[0-9]+") {
+            return "integer".to_string();
+        }
+        return "string".to_string();
+    }
+
+    token.to_string()
+}
@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::ast::{Expr, Program, StatementKind};
+use crate::checker::Session;
+use crate::diagnostic::Diagnostic;
+use crate::grammar;
+use crate::resolver;
+use crate::runtime::error::{ErrorKind, RuntimeError};
+use crate::runtime::eval::expression::call_value;
+use crate::runtime::eval::run_program;
+use crate::runtime::value::{NativeFn, NativeMethod, Value};
+use crate::runtime::{Context, Engine, Prototypes, ScopeStack, StdLib, Type};
+use crate::span::{Source, Span};
+
+use lalrpop_util::ParseError;
+
+/// runs betadin code inside a host application.
+/// everything declared at the top level stays available to the code that runs after it
+pub struct Interpreter {
+    scopes: ScopeStack,
+    prototypes: HashMap<String, HashMap<String, Value>>,
+    session: Session,
+    // the code that ran, functions declared in it can still fail and point into it
+    sources: Vec<Arc<Source>>,
+}
+
+/// why a call into the interpreter failed. `diagnostics` are the errors found before the code ran,
+/// or the one it stopped with, then `runtime` has that error and its kind
+#[derive(Debug, Clone)]
+pub struct EvalError {
+    pub diagnostics: Vec<Diagnostic>,
+    pub runtime: Option<RuntimeError>,
+    // rendered while the sources the diagnostics point into are still there
+    rendered: String,
+}
+
+impl EvalError {
+    fn new(diagnostics: Vec<Diagnostic>) -> Self {
+        let rendered = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.render())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Self {
+            diagnostics,
+            runtime: None,
+            rendered,
+        }
+    }
+
+    /// the kind of the error the code stopped with, `None` when it didn't run
+    pub fn kind(&self) -> Option<&ErrorKind> {
+        self.runtime.as_ref().map(|e| e.kind.as_ref())
+    }
+}
+
+impl From<Diagnostic> for EvalError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        EvalError::new(vec![diagnostic])
+    }
+}
+
+impl From<RuntimeError> for EvalError {
+    fn from(error: RuntimeError) -> Self {
+        // an imported file with errors shows each of them where it is
+        let diagnostics = match error.kind.as_ref() {
+            ErrorKind::InvalidModule { diagnostics } => diagnostics.clone(),
+            _ => vec![Diagnostic::from(&error)],
+        };
+
+        let mut res = EvalError::new(diagnostics);
+        res.runtime = Some(error);
+        res
+    }
+}
+
+impl From<ErrorKind> for EvalError {
+    fn from(kind: ErrorKind) -> Self {
+        EvalError::from(RuntimeError::new(kind))
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rendered)
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        let builtins = Arc::new(Mutex::new(StdLib::exports()));
+        let globals = Arc::new(Mutex::new(HashMap::new()));
+
+        Self {
+            scopes: ScopeStack::new(vec![builtins, globals]),
+            prototypes: Prototypes::exports(),
+            session: Session::new(),
+            sources: vec![],
+        }
+    }
+
+    /// runs `code` and returns the value of its last statement if it is an expression, `null` otherwise.
+    /// a lone expression doesn't need the trailing `;`
+    pub fn eval_str(&mut self, code: &str) -> Result<Value, EvalError> {
+        self.eval_source("<string>", code)
+    }
+
+    /// runs the file at `path`, its statements need their `;` like the files it imports
+    pub fn eval_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Value, EvalError> {
+        // the directory of the first file is the project root, imports are also looked up there
+        if let Some(dir) = path.as_ref().parent() {
+            self.scopes.set_root(dir);
+        }
+        let path = path.as_ref().to_string_lossy();
+
+        let code = fs::read_to_string(path.as_ref())
+            .map_err(|e| Diagnostic::new(format!("unable to read `{}`: {}", path, e), None))?;
+
+        self.run(&path, &code, false)
+    }
+
+    /// same as `eval_str`, `path` is where diagnostics point to and imports are resolved from
+    pub fn eval_source(&mut self, path: &str, code: &str) -> Result<Value, EvalError> {
+        self.run(path, code, true)
+    }
+
+    fn run(&mut self, path: &str, code: &str, expression: bool) -> Result<Value, EvalError> {
+        let mut program = self.parse_source(path, code, expression)?;
+
+        let root = self.scopes.root();
+        let errors = self.session.check(&mut program, root.as_deref());
+        if !errors.is_empty() {
+            return Err(EvalError::new(errors));
+        }
+
+        let errors = resolver::resolve(&mut program, &self.scopes);
+        if !errors.is_empty() {
+            return Err(EvalError::new(errors));
+        }
+
+        let value = run_program(&mut self.scopes, &program, &self.prototypes)?;
+
+        Ok(value.unwrap_or(Value::Null))
+    }
+
+    /// the checked type of the expression `code`, `None` when it is only known by running it.
+    /// the expression isn't run
+    pub fn type_of(&mut self, code: &str) -> Result<Option<Type>, EvalError> {
+        let mut expr = self.parse_expr(code)?;
+
+        self.session.type_of(&mut expr).map_err(EvalError::new)
+    }
+
+    /// calls the global function `name`, the arguments are checked like in a call from a script
+    pub fn call_function(&mut self, name: &str, args: Vec<Value>) -> Result<Value, EvalError> {
+        let value = self
+            .get_global(name)
+            .ok_or_else(|| ErrorKind::UndefinedName {
+                name: name.to_string(),
+            })?;
+
+        let value = call_value(
+            &self.prototypes,
+            value,
+            args,
+            name.to_string(),
+            None,
+            host_span(),
+        )?;
+        Ok(value)
+    }
+
+    /// makes `f` callable from scripts at `path`, a name like `log` or a module path like
+    /// `host::db::query`. the modules on the way are created when they don't exist.
+    /// `f` can call the functions it is given through the `Context`. fails when a script
+    /// already declared a global with the first name of `path`, it would hide `f`
+    pub fn register_fn<F>(&mut self, path: &str, f: F) -> Result<(), EvalError>
+    where
+        F: Fn(&Context, Vec<Value>) -> Result<Value, RuntimeError> + Send + Sync + 'static,
+    {
+        let path: Vec<String> = path.split("::").map(|name| name.to_string()).collect();
+
+        self.scopes
+            .register(&path, Value::BuiltInFn(NativeFn::new(f)))?;
+        Ok(())
+    }
+
+    /// adds the method `name` to a builtin prototype such as `string` or `list`,
+    /// `f` gets the value the method is called on after the arguments
+    pub fn register_method<F>(&mut self, prototype: &str, name: &str, f: F) -> Result<(), EvalError>
+    where
+        F: Fn(&Context, Vec<Value>, Value) -> Result<Value, RuntimeError> + Send + Sync + 'static,
+    {
+        match self.prototypes.get_mut(prototype) {
+            Some(proto) => {
+                proto.insert(
+                    name.to_string(),
+                    Value::BuiltInMethod(NativeMethod::new(f), None),
+                );
+                Ok(())
+            }
+            None => Err(ErrorKind::UndefinedType {
+                name: prototype.to_string(),
+            }
+            .into()),
+        }
+    }
+
+    /// programs run on the vm unless the tree-walker is chosen here, imported files follow
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.scopes.set_engine(engine);
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.scopes.get(&name.to_string())
+    }
+
+    /// assigns the global `name`, it is declared as a mutable variable if it doesn't exist yet
+    pub fn set_global(&mut self, name: &str, value: Value) -> Result<(), EvalError> {
+        let datatype = Type::from(&value);
+
+        self.scopes.set(&name.to_string(), value)?;
+
+        self.session
+            .define(name, Some(datatype).filter(|t| !t.is_opaque()));
+        Ok(())
+    }
+
+    /// the syntax tree of the expression `code`, printed for debugging
+    pub fn syntax_tree(&mut self, code: &str) -> Result<String, EvalError> {
+        Ok(format!("{:#?}", self.parse_expr(code)?))
+    }
+
+    /// parses `code` that should be a single expression
+    pub(crate) fn parse_expr(&mut self, code: &str) -> Result<Expr, EvalError> {
+        let mut program = self.parse_source("<string>", code, true)?;
+
+        match program.statements.pop() {
+            Some(statement) if program.statements.is_empty() => match statement.kind {
+                StatementKind::Expression(expr) => Ok(expr),
+                _ => Err(Diagnostic::new(
+                    "expected an expression".to_string(),
+                    Some(statement.span),
+                )
+                .into()),
+            },
+            _ => Err(Diagnostic::new("expected an expression".to_string(), None).into()),
+        }
+    }
+
+    /// parses `code`, a lone `expression` doesn't need the trailing `;`.
+    /// diagnostics show `code` as it was written
+    fn parse_source(
+        &mut self,
+        path: &str,
+        code: &str,
+        expression: bool,
+    ) -> Result<Program, Diagnostic> {
+        let parser = grammar::programParser::new();
+        let source = self.source(path, code);
+
+        let e = match parser.parse(source.id, 0, code) {
+            Ok(program) => {
+                self.keep(source);
+                return Ok(program);
+            }
+            Err(e) => e,
+        };
+
+        let ended = matches!(e, ParseError::UnrecognizedEof { .. });
+        let mut diagnostic = Diagnostic::from_parse_error(source.id, e);
+
+        if expression {
+            // the spans of the retry point into `code`, the `;` is only there for the parser
+            match parser.parse(source.id, 0, &format!("{};", code)) {
+                Ok(program) => {
+                    self.keep(source);
+                    return Ok(program);
+                }
+                // `code` ended where the `;` goes, an error the retry finds before it is the real one
+                Err(e) if ended => {
+                    let retry = Diagnostic::from_parse_error(source.id, e);
+                    if retry.span.is_some_and(|span| span.start < code.len()) {
+                        diagnostic = retry;
+                    }
+                }
+                Err(_) => {}
+            }
+        }
+
+        self.keep(source);
+        Err(diagnostic)
+    }
+
+    /// the source of `code`, the same code at the same path shares it
+    fn source(&self, path: &str, code: &str) -> Arc<Source> {
+        let kept = self
+            .sources
+            .iter()
+            .find(|source| source.path == path && source.code == code);
+
+        match kept {
+            Some(source) => source.clone(),
+            None => Source::add(path, code),
+        }
+    }
+
+    fn keep(&mut self, source: Arc<Source>) {
+        if !self.sources.iter().any(|kept| Arc::ptr_eq(kept, &source)) {
+            self.sources.push(source);
+        }
+    }
+}
+
+// call site of the functions called by the host
+fn host_span() -> Span {
+    static HOST: OnceLock<Arc<Source>> = OnceLock::new();
+    let source = HOST.get_or_init(|| Source::add("<host>", ""));
+
+    Span::new(source.id, 0, 0)
+}
@@ -0,0 +1,20 @@
+#[macro_use]
+extern crate lalrpop_util;
+extern crate sys_info;
+
+lalrpop_util::lalrpop_mod!(#[allow(clippy::all)] grammar);
+
+mod ast;
+mod checker;
+mod diagnostic;
+mod interpreter;
+mod literal;
+mod resolver;
+mod runtime;
+mod span;
+
+pub use diagnostic::Diagnostic;
+pub use interpreter::{EvalError, Interpreter};
+pub use runtime::error::{ErrorKind, RuntimeError};
+pub use runtime::value::Value;
+pub use runtime::{Context, Engine, Type};
@@ -0,0 +1,357 @@
+use crate::ast::{Align, ExprKind, FormatSpec, Fragment, StatementKind};
+use crate::diagnostic::Diagnostic;
+use crate::grammar;
+use crate::span::{SourceId, Span};
+use lalrpop_util::ParseError;
+
+/// the digits of the smallest int, they only make an int after a `-`
+pub const MIN_INT: &str = "2147483648";
+
+/// the int literal `token`, which starts at `start` in `source`
+pub fn int(source: SourceId, start: usize, token: &str) -> Result<i32, Diagnostic> {
+    token
+        .parse()
+        .map_err(|_| int_out_of_range(Span::new(source, start, start + token.len())))
+}
+
+pub fn int_out_of_range(span: Span) -> Diagnostic {
+    Diagnostic::new("integer literal out of range".to_string(), Some(span))
+}
+
+/// the string literal `token`, which starts at `start` in `source`.
+/// `r"..."` is taken as it is written, `"""..."""` can go over several lines and loses
+/// the indentation its lines share. the other forms have escapes and values in braces,
+/// `"x={x}"` is an interpolation and `{{` and `}}` are the braces themselves
+pub fn string(source: SourceId, start: usize, token: &str) -> Result<ExprKind, Diagnostic> {
+    if let Some(raw) = token.strip_prefix('r') {
+        return Ok(ExprKind::String(raw[1..raw.len() - 1].to_string()));
+    }
+
+    let mut fragments = vec![];
+    if token.starts_with("\"\"\"") {
+        multiline(
+            source,
+            start + 3,
+            &token[3..token.len() - 3],
+            &mut fragments,
+        )?;
+    } else {
+        unescape(
+            source,
+            start + 1,
+            &token[1..token.len() - 1],
+            &mut fragments,
+        )?;
+    }
+
+    match fragments.as_slice() {
+        [] => Ok(ExprKind::String(String::new())),
+        [Fragment::Text(text)] => Ok(ExprKind::String(text.to_string())),
+        _ => Ok(ExprKind::Interpolation(fragments)),
+    }
+}
+
+fn multiline(
+    source: SourceId,
+    start: usize,
+    content: &str,
+    fragments: &mut Vec<Fragment>,
+) -> Result<(), Diagnostic> {
+    // every line with its offset in `content`
+    let mut lines = vec![];
+    let mut offset = 0;
+    for line in content.split('\n') {
+        lines.push((offset, line.strip_suffix('\r').unwrap_or(line)));
+        offset += line.len() + 1;
+    }
+
+    // the line break after the opening quotes and the line of the closing quotes aren't part of it
+    if lines.len() > 1 && lines[0].1.trim().is_empty() {
+        lines.remove(0);
+    }
+    if lines.len() > 1 && lines[lines.len() - 1].1.trim().is_empty() {
+        lines.pop();
+    }
+
+    let indent = lines
+        .iter()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(_, line)| line.len() - line.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+
+    for (i, (offset, line)) in lines.iter().enumerate() {
+        if i != 0 {
+            push_text(fragments, '\n');
+        }
+
+        if !line.trim().is_empty() {
+            unescape(source, start + offset + indent, &line[indent..], fragments)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn push_text(fragments: &mut Vec<Fragment>, c: char) {
+    match fragments.last_mut() {
+        Some(Fragment::Text(text)) => text.push(c),
+        _ => fragments.push(Fragment::Text(c.to_string())),
+    }
+}
+
+fn unescape(
+    source: SourceId,
+    start: usize,
+    content: &str,
+    fragments: &mut Vec<Fragment>,
+) -> Result<(), Diagnostic> {
+    let error = |message: String, from: usize, to: usize| {
+        Diagnostic::new(message, Some(Span::new(source, start + from, start + to)))
+    };
+
+    let mut chars = content.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '{' if content[i + 1..].starts_with('{') => {
+                chars.next();
+                push_text(fragments, '{');
+                continue;
+            }
+            '}' if content[i + 1..].starts_with('}') => {
+                chars.next();
+                push_text(fragments, '}');
+                continue;
+            }
+            '{' => {
+                let end = match closing_brace(&content[i + 1..]) {
+                    Some(end) => i + 1 + end,
+                    None => {
+                        let message = "unclosed `{` in string, write `{{` for a brace".to_string();
+                        return Err(error(message, i, i + 1));
+                    }
+                };
+
+                fragments.push(value(source, start + i + 1, &content[i + 1..end])?);
+                while chars.next_if(|(j, _)| *j <= end).is_some() {}
+                continue;
+            }
+            '}' => {
+                let message = "unmatched `}` in string, write `}}` for a brace".to_string();
+                return Err(error(message, i, i + 1));
+            }
+            '\\' => {}
+            c => {
+                push_text(fragments, c);
+                continue;
+            }
+        }
+
+        let c = match chars.next() {
+            Some((_, 'n')) => '\n',
+            Some((_, 't')) => '\t',
+            Some((_, 'r')) => '\r',
+            Some((_, '0')) => '\0',
+            Some((_, '"')) => '"',
+            Some((_, '\\')) => '\\',
+            Some((_, 'u')) => {
+                let rest = &content[i + 2..];
+                let end = match (rest.starts_with('{'), rest.find('}')) {
+                    (true, Some(end)) => end,
+                    _ => {
+                        let message = "expected `{` and `}` around the code of `\\u`".to_string();
+                        return Err(error(message, i, i + 2));
+                    }
+                };
+
+                let escape = &content[i..i + 3 + end];
+                let digits = &rest[1..end];
+                if digits.is_empty()
+                    || digits.len() > 6
+                    || !digits.chars().all(|c| c.is_ascii_hexdigit())
+                {
+                    let message = format!("invalid unicode escape `{}`", escape);
+                    return Err(error(message, i, i + escape.len()));
+                }
+
+                let code = u32::from_str_radix(digits, 16).expect("hex digits");
+                match char::from_u32(code) {
+                    Some(c) => {
+                        chars.nth(end);
+                        c
+                    }
+                    None => {
+                        let message = format!("`{}` is not a unicode character", escape);
+                        return Err(error(message, i, i + escape.len()));
+                    }
+                }
+            }
+            Some((j, c)) => {
+                let message = format!("unknown escape `\\{}` in string", c);
+                return Err(error(message, i, j + c.len_utf8()));
+            }
+            None => {
+                let message = "expected an escape after `\\`".to_string();
+                return Err(error(message, i, i + 1));
+            }
+        };
+
+        push_text(fragments, c);
+    }
+
+    Ok(())
+}
+
+/// where the `}` that closes a value is in `rest`, braces of the value itself are skipped
+fn closing_brace(rest: &str) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, c) in rest.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// the value between the braces of an interpolation, `code` starts at `start`
+fn value(source: SourceId, start: usize, code: &str) -> Result<Fragment, Diagnostic> {
+    let (code, spec) = match spec_colon(code) {
+        Some(colon) => (&code[..colon], Some(&code[colon + 1..])),
+        None => (code, None),
+    };
+
+    let error = |message: String| {
+        let span = Span::new(source, start, start + code.len());
+        Diagnostic::new(message, Some(span))
+    };
+
+    if code.trim().is_empty() {
+        return Err(error("expected a value between `{` and `}`".to_string()));
+    }
+
+    // the value is parsed as a statement, so the `;` added after it is where it ends
+    let input = format!("{};", code);
+    let end = start + code.len();
+    let mut program = grammar::programParser::new()
+        .parse(source, start, &input)
+        .map_err(|e| match e.map_location(|l| start + l) {
+            ParseError::UnrecognizedToken {
+                token: (from, _, _),
+                ..
+            } if from == end => Diagnostic::new(
+                "unexpected end of the value in braces".to_string(),
+                Some(Span::new(source, end, end + 1)),
+            ),
+            e => Diagnostic::from_parse_error(source, e),
+        })?;
+
+    let expr = match program.statements.pop() {
+        Some(statement) if program.statements.is_empty() => match statement.kind {
+            StatementKind::Expression(expr) => expr,
+            _ => return Err(error("expected a value, found a statement".to_string())),
+        },
+        _ => return Err(error("expected a single value".to_string())),
+    };
+
+    let spec = match spec {
+        Some(spec) => match format_spec(spec) {
+            Some(spec) => Some(spec),
+            None => {
+                let from = start + code.len() + 1;
+                let span = Span::new(source, from, from + spec.len());
+                let message = format!("invalid format `{}`", spec);
+                return Err(Diagnostic::new(message, Some(span)));
+            }
+        },
+        None => None,
+    };
+
+    Ok(Fragment::Value(expr, spec))
+}
+
+/// the `:` before the format spec, the `::` of paths and colons inside brackets don't count
+fn spec_colon(code: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut colon = None;
+    let mut chars = code.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => depth -= 1,
+            ':' if chars.next_if(|(_, c)| *c == ':').is_some() => {}
+            ':' if depth == 0 => colon = Some(i),
+            _ => {}
+        }
+    }
+
+    colon
+}
+
+fn format_spec(spec: &str) -> Option<FormatSpec> {
+    let align = |c: char| match c {
+        '<' => Some(Align::Left),
+        '^' => Some(Align::Center),
+        '>' => Some(Align::Right),
+        _ => None,
+    };
+
+    let mut res = FormatSpec {
+        fill: ' ',
+        align: None,
+        zero: false,
+        width: None,
+        precision: None,
+        radix: None,
+    };
+    let chars: Vec<char> = spec.chars().collect();
+    let mut i = 0;
+
+    if let Some(a) = chars.get(1).and_then(|c| align(*c)) {
+        res.fill = chars[0];
+        res.align = Some(a);
+        i = 2;
+    } else if let Some(a) = chars.first().and_then(|c| align(*c)) {
+        res.align = Some(a);
+        i = 1;
+    }
+
+    if chars.get(i) == Some(&'0') {
+        res.zero = true;
+        i += 1;
+    }
+
+    let number = |i: &mut usize| {
+        let from = *i;
+        while chars.get(*i).is_some_and(|c| c.is_ascii_digit()) {
+            *i += 1;
+        }
+        chars[from..*i]
+            .iter()
+            .collect::<String>()
+            .parse::<usize>()
+            .ok()
+    };
+
+    res.width = number(&mut i);
+    if chars.get(i) == Some(&'.') {
+        i += 1;
+        res.precision = Some(number(&mut i)?);
+    }
+
+    if let Some(c @ ('x' | 'X' | 'o' | 'b')) = chars.get(i) {
+        res.radix = Some(*c);
+        i += 1;
+    }
+
+    match i == chars.len() {
+        true => Some(res),
+        false => None,
+    }
+}
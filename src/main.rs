@@ -1,48 +1,39 @@
-use std::sync::{Arc, Mutex};
-use std::{env, fs};
+use std::env;
+use std::process;
 
-use lalrpop_util::ParseError;
-use runtime::eval::eval_program;
-use runtime::Prototypes;
-use runtime::ScopeStack;
-use runtime::StdLib;
+use betadin::{Engine, Interpreter};
 
-#[macro_use]
-extern crate lalrpop_util;
-extern crate sys_info;
+#[cfg(feature = "repl")]
+mod repl;
 
-lalrpop_util::lalrpop_mod!(pub grammar);
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
 
-mod ast;
-mod runtime;
-
-fn main() -> Result<(), String> {
-    let args: Vec<String> = env::args().collect();
+    // `--tree-walker` runs the program without compiling it, to compare with the vm
+    let engine = match args.iter().position(|arg| arg == "--tree-walker") {
+        Some(i) => {
+            args.remove(i);
+            Engine::TreeWalker
+        }
+        None => Engine::Vm,
+    };
 
-    match args.get(1) {
+    match args.first() {
         Some(path) => {
-            let mut scopes = ScopeStack::new(vec![Arc::new(Mutex::new(StdLib::exports()))]);
+            let mut interpreter = Interpreter::new();
+            interpreter.set_engine(engine);
 
-            
-            let code = fs::read_to_string(path).expect("unable to read the file");
-            let parser = grammar::programParser::new();
-            let ast = parser.parse(&code).map_err(|e| match e {
-                ParseError::InvalidToken { location } => format!("InvalidToken at {}", location),
-                ParseError::UnrecognizedEof {
-                    location,
-                    expected: _,
-                } => format!("UnrecognizedEof at {}", location),
-                ParseError::UnrecognizedToken { token, expected: _ } => {
-                    format!("UnrecognizedToken: {} -> {}:{}", token.1, token.0, token.2)
-                }
-                ParseError::ExtraToken { token } => format!("ExtraToken: {}", token.1),
-                ParseError::User { error } => format!("Error: {}", error),
-            })?;
-
-            // println!("{:#?}", ast);
-            eval_program(&mut scopes, ast, &Prototypes::exports())?;
-            Ok(())
+            if let Err(error) = interpreter.eval_file(path) {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        }
+        #[cfg(feature = "repl")]
+        None => repl::start(engine),
+        #[cfg(not(feature = "repl"))]
+        None => {
+            eprintln!("usage: betadin [--tree-walker] <file>");
+            process::exit(1);
         }
-        None => Err(format!("The file path is require")),
     }
 }
@@ -0,0 +1,160 @@
+use std::env;
+use std::path::PathBuf;
+
+use betadin::{Engine, EvalError, Interpreter, Value};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+const HELP: &str = "\
+commands:
+  :type <expr>   show the type of an expression
+  :ast <expr>    show the syntax tree of an expression
+  :load <file>   run a file, its declarations stay available
+  :help          show this message
+  :quit          exit the repl";
+
+/// starts an interactive session on stdin, history is kept in `~/.betadin_history`
+pub fn start(engine: Engine) {
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            eprintln!("error: unable to start the repl: {}", e);
+            return;
+        }
+    };
+
+    let history = env::var_os("HOME").map(|home| PathBuf::from(home).join(".betadin_history"));
+    if let Some(path) = &history {
+        let _ = editor.load_history(path);
+    }
+
+    println!(
+        "betadin {} repl, type :help for the commands",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_engine(engine);
+    let mut input = String::new();
+
+    loop {
+        let prompt = if input.is_empty() { ">> " } else { ".. " };
+
+        match editor.readline(prompt) {
+            Ok(line) => {
+                input.push_str(&line);
+                input.push('\n');
+
+                if is_incomplete(&input) {
+                    continue;
+                }
+
+                let entry = std::mem::take(&mut input);
+                let entry = entry.trim();
+                if entry.is_empty() {
+                    continue;
+                }
+
+                let _ = editor.add_history_entry(entry);
+                if !run(&mut interpreter, entry) {
+                    break;
+                }
+            }
+            Err(ReadlineError::Interrupted) => input.clear(),
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                break;
+            }
+        }
+    }
+
+    if let Some(path) = &history {
+        let _ = editor.save_history(path);
+    }
+}
+
+/// runs one input, returns `false` when the session should end
+fn run(interpreter: &mut Interpreter, entry: &str) -> bool {
+    let (command, rest) = entry.split_once(char::is_whitespace).unwrap_or((entry, ""));
+
+    match command {
+        ":quit" | ":q" => return false,
+        ":help" => println!("{}", HELP),
+        ":type" => match interpreter.type_of(rest.trim()) {
+            Ok(Some(datatype)) => println!("{}", datatype),
+            Ok(None) => println!("unknown"),
+            Err(error) => report(error),
+        },
+        ":ast" => match interpreter.syntax_tree(rest.trim()) {
+            Ok(tree) => println!("{}", tree),
+            Err(error) => report(error),
+        },
+        ":load" => match interpreter.eval_file(rest.trim()) {
+            Ok(_) => println!("loaded `{}`", rest.trim()),
+            Err(error) => report(error),
+        },
+        command if command.starts_with(':') => {
+            eprintln!(
+                "error: unknown command `{}`, type :help for the commands",
+                command
+            )
+        }
+        _ => match interpreter.eval_source("<repl>", entry) {
+            Ok(Value::Null) => {}
+            Ok(value) => println!("{}", value),
+            Err(error) => report(error),
+        },
+    }
+
+    true
+}
+
+fn report(error: EvalError) {
+    eprintln!("{}", error);
+}
+
+/// whether `input` has brackets or a `"""` string that are still open, the next lines continue it
+fn is_incomplete(input: &str) -> bool {
+    let mut depth = 0;
+    let mut prev = ' ';
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if chars.as_str().starts_with("\"\"") => {
+                let rest = &chars.as_str()[2..];
+                match rest.find("\"\"\"") {
+                    Some(end) => chars = rest[end + 3..].chars(),
+                    None => return true,
+                }
+            }
+            '"' => {
+                // raw strings have no escapes
+                let raw = prev == 'r';
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' if !raw => {
+                            chars.next();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            '#' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => depth -= 1,
+            _ => {}
+        }
+        prev = c;
+    }
+
+    depth > 0
+}
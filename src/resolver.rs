@@ -0,0 +1,432 @@
+use crate::ast::{
+    Access, Arg, ArmBody, Block, Expr, ExprKind, Fragment, MatchArm, Pattern, PatternKind, Program,
+    Slot, Statement, StatementKind,
+};
+use crate::diagnostic::Diagnostic;
+use crate::runtime::error::ErrorKind;
+use crate::runtime::ScopeStack;
+use crate::span::Span;
+
+/// binds every variable the program uses to the slot it is stored in, and reports the names
+/// that aren't defined and the ones declared twice before the program runs.
+/// globals and the declarations the tree-walker runs (imports, structs, modules...) are looked
+/// up by name, `scopes` is where the program runs and has the names defined before it
+pub fn resolve(program: &mut Program, scopes: &ScopeStack) -> Vec<Diagnostic> {
+    let mut resolver = Resolver {
+        functions: vec![Function::default()],
+        scopes,
+        names: 0,
+        modules: 0,
+        errors: vec![],
+    };
+
+    resolver.begin_block(&program.statements, true);
+    for statement in program.statements.iter_mut() {
+        resolver.statement(statement);
+    }
+
+    resolver.errors
+}
+
+/// a name declared by a block
+pub(crate) struct Declaration<'a> {
+    pub name: &'a String,
+    pub span: Span,
+    // functions, `let` and `const` are stored in slots, the tree-walker declares the others
+    pub slot: bool,
+    pub mutable: bool,
+}
+
+/// the names `block` declares, in the order their slots are given at the start of the block
+pub(crate) fn declarations(block: &Block) -> Vec<Declaration<'_>> {
+    let mut declarations = vec![];
+
+    for statement in block {
+        let span = statement.span;
+        let kind = match &statement.kind {
+            StatementKind::Export(inner) => &inner.kind,
+            kind => kind,
+        };
+
+        match kind {
+            StatementKind::Let(name, ..) | StatementKind::Const(name, ..) => {
+                declarations.push(Declaration {
+                    name,
+                    span,
+                    slot: true,
+                    mutable: matches!(kind, StatementKind::Let(..)),
+                });
+            }
+            StatementKind::Fn(name, ..) => declarations.push(Declaration {
+                name,
+                span,
+                slot: true,
+                mutable: false,
+            }),
+            kind => {
+                for name in env_names(kind) {
+                    declarations.push(Declaration {
+                        name,
+                        span,
+                        slot: false,
+                        mutable: false,
+                    });
+                }
+            }
+        }
+    }
+
+    declarations
+}
+
+/// the names a pattern can bind, a name can also turn out to be a unit variant
+pub(crate) fn pattern_names(pattern: &Pattern, names: &mut Vec<String>) {
+    match &pattern.kind {
+        PatternKind::Ident(name) => {
+            if !names.contains(name) {
+                names.push(name.to_string());
+            }
+        }
+        PatternKind::Variant(_, _, patterns) | PatternKind::Tuple(patterns) => {
+            for pattern in patterns {
+                pattern_names(pattern, names);
+            }
+        }
+        PatternKind::Wildcard | PatternKind::Literal(_) => {}
+    }
+}
+
+/// the names declared by a statement the tree-walker runs
+fn env_names(kind: &StatementKind) -> Vec<&String> {
+    match kind {
+        StatementKind::Import(_, Some(items)) => items.iter().collect(),
+        StatementKind::Import(paths, None) => paths.last().into_iter().collect(),
+        StatementKind::Let(..) | StatementKind::Const(..) | StatementKind::Fn(..) => vec![],
+        kind => kind.declared_name().into_iter().collect(),
+    }
+}
+
+struct Local {
+    name: String,
+    // `None` when the variable is looked up by name
+    slot: Option<u32>,
+    // nested functions can use a variable of the block before its declaration runs
+    declared: bool,
+}
+
+#[derive(Default)]
+struct Function {
+    scopes: Vec<Vec<Local>>,
+    slots: u32,
+}
+
+struct Resolver<'a> {
+    functions: Vec<Function>,
+    scopes: &'a ScopeStack,
+    // inside module blocks, the tree-walker runs them and looks everything up by name
+    names: usize,
+    // inside the path of a module call, names there can be items of the module
+    modules: usize,
+    errors: Vec<Diagnostic>,
+}
+
+impl<'a> Resolver<'a> {
+    fn error(&mut self, kind: ErrorKind, span: Span) {
+        self.errors
+            .push(Diagnostic::new(kind.to_string(), Some(span)));
+    }
+
+    fn function(&mut self) -> &mut Function {
+        self.functions.last_mut().expect("function")
+    }
+
+    fn allocate(&mut self) -> Option<u32> {
+        if self.names > 0 {
+            return None;
+        }
+
+        let function = self.function();
+        function.slots += 1;
+        Some(function.slots - 1)
+    }
+
+    fn add_local(&mut self, name: &str, slot: Option<u32>, declared: bool, span: Span) {
+        let scope = self.function().scopes.last_mut().expect("scope");
+
+        if scope.iter().any(|local| local.name == name) {
+            let name = name.to_string();
+            return self.error(ErrorKind::AlreadyDefined { name }, span);
+        }
+        scope.push(Local {
+            name: name.to_string(),
+            slot,
+            declared,
+        });
+    }
+
+    /// makes the names of `block` known before its statements, a global block declares them by name
+    fn begin_block(&mut self, block: &Block, global: bool) {
+        self.function().scopes.push(vec![]);
+
+        for declaration in declarations(block) {
+            let slot = match declaration.slot && !global {
+                true => self.allocate(),
+                false => None,
+            };
+            self.add_local(declaration.name, slot, false, declaration.span);
+        }
+    }
+
+    /// a variable that is visible as soon as it is declared, like a parameter
+    fn bind(&mut self, name: &str, span: Span) {
+        let slot = self.allocate();
+        self.add_local(name, slot, true, span);
+    }
+
+    /// the declaration of `name` in the current block has run
+    fn declare(&mut self, name: &str) {
+        let scope = self.function().scopes.last_mut().expect("scope");
+
+        if let Some(local) = scope
+            .iter_mut()
+            .find(|local| !local.declared && local.name == name)
+        {
+            local.declared = true;
+        }
+    }
+
+    /// `Some(None)` when the name is looked up at runtime
+    fn lookup(&self, name: &str) -> Option<Option<Slot>> {
+        for (depth, function) in self.functions.iter().rev().enumerate() {
+            for scope in function.scopes.iter().rev() {
+                let local = scope
+                    .iter()
+                    .rev()
+                    .find(|local| local.name == name && (local.declared || depth > 0));
+
+                if let Some(local) = local {
+                    return Some(local.slot.map(|index| Slot {
+                        depth: depth as u32,
+                        index,
+                    }));
+                }
+            }
+        }
+
+        self.scopes.get(&name.to_string()).map(|_| None)
+    }
+
+    fn use_name(&mut self, name: &str, span: Span) -> Option<Slot> {
+        match self.lookup(name) {
+            Some(slot) => slot,
+            None => {
+                if self.modules == 0 {
+                    let name = name.to_string();
+                    self.error(ErrorKind::UndefinedName { name }, span);
+                }
+                None
+            }
+        }
+    }
+
+    fn block(&mut self, block: &mut Block) {
+        self.begin_block(block, false);
+
+        for statement in block.iter_mut() {
+            self.statement(statement);
+        }
+
+        self.function().scopes.pop();
+    }
+
+    fn fn_body(&mut self, args: &Vec<Arg>, block: &mut Block, method: bool, span: Span) {
+        self.functions.push(Function {
+            scopes: vec![vec![]],
+            slots: 0,
+        });
+
+        if method {
+            self.bind("self", span);
+        }
+        for arg in args {
+            self.bind(&arg.ident, arg.span);
+        }
+        self.block(block);
+
+        self.functions.pop();
+    }
+
+    fn statement(&mut self, statement: &mut Statement) {
+        let span = statement.span;
+
+        match &mut statement.kind {
+            StatementKind::Let(name, _, expr) | StatementKind::Const(name, _, expr) => {
+                self.expr(expr);
+                self.declare(name);
+            }
+            StatementKind::Expression(expr) | StatementKind::Return(expr) => self.expr(expr),
+            StatementKind::Assignment(lvalue, expr)
+            | StatementKind::CompoundAssignment(lvalue, _, expr) => {
+                self.expr(expr);
+                lvalue.slot = self.use_name(&lvalue.name, span);
+
+                for access in lvalue.path.iter_mut() {
+                    if let Access::Index(index) = access {
+                        self.expr(index);
+                    }
+                }
+            }
+            StatementKind::If(branches, else_block) => {
+                for branch in branches {
+                    self.expr(&mut branch.condition);
+                    self.block(&mut branch.statements);
+                }
+                if let Some(block) = else_block {
+                    self.block(block);
+                }
+            }
+            StatementKind::Fn(name, args, _, block) => {
+                self.declare(name);
+                self.fn_body(args, block, false, span);
+            }
+            StatementKind::For(name, iter, block) => {
+                self.expr(iter);
+
+                self.function().scopes.push(vec![]);
+                self.bind(name, span);
+                self.bind("index", span);
+                self.block(block);
+                self.function().scopes.pop();
+            }
+            StatementKind::While(cond, block) => {
+                self.expr(cond);
+                self.block(block);
+            }
+            StatementKind::Match(expr, arms) => self.match_arms(expr, arms),
+            StatementKind::Impl(_, _, methods) => {
+                for method in methods {
+                    self.fn_body(&method.args, &mut method.block, true, method.span);
+                }
+            }
+            StatementKind::Module(name, block) => {
+                self.names += 1;
+                self.block(block);
+                self.names -= 1;
+                self.declare(name);
+            }
+            StatementKind::Export(statement) => self.statement(statement),
+            kind @ (StatementKind::Import(..)
+            | StatementKind::Type(..)
+            | StatementKind::Struct(..)
+            | StatementKind::Enum(..)
+            | StatementKind::Trait(..)) => {
+                let names: Vec<String> = env_names(kind).into_iter().cloned().collect();
+                for name in names {
+                    self.declare(&name);
+                }
+            }
+            StatementKind::Break | StatementKind::Continue => {}
+        }
+    }
+
+    fn match_arms(&mut self, expr: &mut Expr, arms: &mut Vec<MatchArm>) {
+        self.expr(expr);
+
+        for arm in arms {
+            self.function().scopes.push(vec![]);
+
+            let mut names = vec![];
+            pattern_names(&arm.pattern, &mut names);
+            for name in names {
+                self.bind(&name, arm.pattern.span);
+            }
+
+            match &mut arm.body {
+                ArmBody::Expr(expr) => self.expr(expr),
+                ArmBody::Block(block) => self.block(block),
+            }
+
+            self.function().scopes.pop();
+        }
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        let span = expr.span;
+
+        match &mut expr.kind {
+            ExprKind::Identifier(name, slot) => *slot = self.use_name(name, span),
+            ExprKind::List(items) | ExprKind::Tuple(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            ExprKind::Object(props) | ExprKind::Struct(_, props) => {
+                for prop in props {
+                    self.expr(&mut prop.value);
+                }
+            }
+            ExprKind::Call(calle, args) => {
+                self.expr(calle);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            // the names after the dot are members, other expressions there can't be called
+            ExprKind::MethodCall(object, calle) | ExprKind::SafeMethodCall(object, calle) => {
+                self.expr(object);
+
+                if let ExprKind::Call(method, args) = &mut calle.kind {
+                    if let ExprKind::Identifier(..) = method.kind {
+                        for arg in args {
+                            self.expr(arg);
+                        }
+                    }
+                }
+            }
+            ExprKind::ModuleCall(paths, slot, tail) => {
+                // the first name can also be a file module or a global module
+                *slot = self.lookup(&paths[0]).flatten();
+
+                self.modules += 1;
+                self.expr(tail);
+                self.modules -= 1;
+            }
+            ExprKind::Index(lhs, rhs)
+            | ExprKind::SafeIndex(lhs, rhs)
+            | ExprKind::BinaryOp(lhs, _, rhs)
+            | ExprKind::Range(lhs, rhs) => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            ExprKind::UnaryOp(_, expr) | ExprKind::SafeChain(expr) => self.expr(expr),
+            ExprKind::Interpolation(fragments) => {
+                for fragment in fragments {
+                    if let Fragment::Value(expr, _) = fragment {
+                        self.expr(expr);
+                    }
+                }
+            }
+            ExprKind::Fn(args, _, block) => self.fn_body(args, block, false, span),
+            ExprKind::Module(block) => {
+                self.names += 1;
+                self.block(block);
+                self.names -= 1;
+            }
+            ExprKind::If(branches, else_block) => {
+                for branch in branches {
+                    self.expr(&mut branch.condition);
+                    self.block(&mut branch.statements);
+                }
+                if let Some(block) = else_block {
+                    self.block(block);
+                }
+            }
+            ExprKind::Match(value, arms) => self.match_arms(value, arms),
+            ExprKind::Null
+            | ExprKind::Int(_)
+            | ExprKind::Float(_)
+            | ExprKind::String(_)
+            | ExprKind::Bool(_) => {}
+        }
+    }
+}
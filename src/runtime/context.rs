@@ -0,0 +1,39 @@
+use std::collections::HashMap;
+
+use crate::span::Span;
+
+use super::error::RuntimeError;
+use super::eval::expression::call_value;
+use super::value::Value;
+
+/// given to native functions so they can call back into the script
+pub struct Context<'a> {
+    prototypes: &'a HashMap<String, HashMap<String, Value>>,
+    // where the native function was called
+    span: Span,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(prototypes: &'a HashMap<String, HashMap<String, Value>>, span: Span) -> Self {
+        Self { prototypes, span }
+    }
+
+    pub(crate) fn prototypes(&self) -> &HashMap<String, HashMap<String, Value>> {
+        self.prototypes
+    }
+
+    /// calls `f`, a script function or a native one, with arguments that are already evaluated.
+    /// the call is in the stack trace under the name `f` was declared with
+    pub fn call(&self, f: &Value, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let name = match f {
+            Value::Func(.., Some(name)) => name.to_string(),
+            Value::Closure(closure) => match &closure.function.name {
+                Some(name) => name.to_string(),
+                None => String::from("<anonymous>"),
+            },
+            _ => String::from("<anonymous>"),
+        };
+
+        call_value(self.prototypes, f.clone(), args, name, None, self.span)
+    }
+}
@@ -0,0 +1,346 @@
+use std::fmt;
+
+use crate::diagnostic::Diagnostic;
+use crate::span::Span;
+
+use super::trace::{self, Frame};
+use super::Type;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    // boxed so results that carry the error stay small
+    pub kind: Box<ErrorKind>,
+    pub span: Option<Span>,
+    pub trace: Vec<Frame>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    TypeMismatch {
+        expected: Type,
+        found: Type,
+    },
+    InvalidArgument {
+        position: usize,
+        expected: Type,
+        found: Type,
+    },
+    ArityMismatch {
+        expected: usize,
+        found: usize,
+    },
+    UndefinedName {
+        name: String,
+    },
+    UndefinedType {
+        name: String,
+    },
+    NotAType {
+        name: String,
+    },
+    UndefinedProperty {
+        name: String,
+        prototype: String,
+    },
+    DuplicateProperty {
+        name: String,
+    },
+    MissingField {
+        name: String,
+        datatype: String,
+    },
+    MissingMethod {
+        name: String,
+        datatype: String,
+    },
+    DuplicateMethod {
+        name: String,
+        datatype: String,
+    },
+    DuplicateImpl {
+        name: String,
+        datatype: String,
+    },
+    NotATrait {
+        name: String,
+    },
+    NonExhaustiveMatch {
+        missing: String,
+    },
+    ReservedProperty {
+        name: String,
+    },
+    AlreadyDefined {
+        name: String,
+    },
+    ImmutableAssignment {
+        name: String,
+    },
+    IndexOutOfBounds {
+        index: i32,
+        len: usize,
+    },
+    NotIndexable {
+        datatype: String,
+    },
+    NotAssignable {
+        datatype: String,
+    },
+    NotIterable {
+        datatype: String,
+    },
+    NotCallable {
+        datatype: String,
+    },
+    InvalidOperands {
+        op: String,
+        lhs: String,
+        rhs: String,
+    },
+    InvalidOperand {
+        op: String,
+        operand: String,
+    },
+    DivisionByZero {
+        op: String,
+    },
+    Overflow {
+        op: String,
+    },
+    InvalidConversion {
+        value: String,
+        to: Type,
+    },
+    InvalidFormat {
+        format: String,
+        datatype: String,
+    },
+    InvalidControlFlow {
+        keyword: String,
+    },
+    NotSupportedInModule {
+        statement: String,
+    },
+    PrivateItem {
+        name: String,
+    },
+    ModuleNotFound {
+        path: String,
+    },
+    // the parse or type errors of an imported file
+    InvalidModule {
+        diagnostics: Vec<Diagnostic>,
+    },
+    CircularImport {
+        chain: Vec<String>,
+    },
+    IoError {
+        message: String,
+    },
+    Panic {
+        message: String,
+    },
+    StackOverflow {
+        depth: usize,
+    },
+}
+
+impl RuntimeError {
+    pub fn new(kind: ErrorKind) -> Self {
+        Self {
+            kind: Box::new(kind),
+            span: None,
+            trace: vec![],
+        }
+    }
+
+    /// attaches `span` unless a more precise one was already recorded
+    pub fn with_span(mut self, span: Span) -> Self {
+        if self.span.is_none() {
+            self.span = Some(span);
+        }
+        self
+    }
+
+    /// records the call stack the first time the error leaves a function
+    pub fn with_trace(mut self, trace: Vec<Frame>) -> Self {
+        if self.trace.is_empty() {
+            self.trace = trace;
+        }
+        self
+    }
+}
+
+impl From<ErrorKind> for RuntimeError {
+    fn from(kind: ErrorKind) -> Self {
+        RuntimeError::new(kind)
+    }
+}
+
+impl From<std::io::Error> for RuntimeError {
+    fn from(error: std::io::Error) -> Self {
+        RuntimeError::new(ErrorKind::IoError {
+            message: error.to_string(),
+        })
+    }
+}
+
+// parse and type errors of an imported file
+impl From<Vec<Diagnostic>> for RuntimeError {
+    fn from(diagnostics: Vec<Diagnostic>) -> Self {
+        let span = diagnostics.first().and_then(|diagnostic| diagnostic.span);
+        let error = RuntimeError::new(ErrorKind::InvalidModule { diagnostics });
+
+        match span {
+            Some(span) => error.with_span(span),
+            None => error,
+        }
+    }
+}
+
+impl From<Diagnostic> for RuntimeError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        RuntimeError::from(vec![diagnostic])
+    }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+    fn from(error: &RuntimeError) -> Self {
+        let diagnostic = Diagnostic::new(error.to_string(), error.span);
+        let backtrace = trace::resolve(&error.trace, error.span);
+
+        // a lone `<main>` frame would only repeat the location above
+        if backtrace.len() < 2 {
+            return diagnostic;
+        }
+
+        diagnostic.with_backtrace(backtrace)
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::TypeMismatch { expected, found } => {
+                write!(f, "expected `{}`, found `{}`", expected, found)
+            }
+            ErrorKind::InvalidArgument {
+                position,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected `{}` for argument {}, found `{}`",
+                expected, position, found
+            ),
+            ErrorKind::ArityMismatch { expected, found } => write!(
+                f,
+                "expected {} argument{}, found {}",
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                found
+            ),
+            ErrorKind::UndefinedName { name } => write!(f, "`{}` is not defined", name),
+            ErrorKind::UndefinedType { name } => write!(f, "type `{}` is not defined", name),
+            ErrorKind::NotAType { name } => write!(f, "expected a type, `{}` is a value", name),
+            ErrorKind::UndefinedProperty { name, prototype } => write!(
+                f,
+                "`{}` does not exist in the `{}` prototype",
+                name, prototype
+            ),
+            ErrorKind::DuplicateProperty { name } => write!(f, "duplicate property `{}`", name),
+            ErrorKind::MissingField { name, datatype } => {
+                write!(f, "missing field `{}` in `{}`", name, datatype)
+            }
+            ErrorKind::MissingMethod { name, datatype } => {
+                write!(f, "missing method `{}` of trait `{}`", name, datatype)
+            }
+            ErrorKind::DuplicateMethod { name, datatype } => {
+                write!(f, "method `{}` is already defined for `{}`", name, datatype)
+            }
+            ErrorKind::DuplicateImpl { name, datatype } => {
+                write!(f, "`{}` already implements `{}`", datatype, name)
+            }
+            ErrorKind::NotATrait { name } => write!(f, "expected a trait, found `{}`", name),
+            ErrorKind::NonExhaustiveMatch { missing } => {
+                write!(f, "non-exhaustive match, `{}` is not covered", missing)
+            }
+            ErrorKind::ReservedProperty { name } => {
+                write!(f, "property `{}` is reserved in the object prototype", name)
+            }
+            ErrorKind::AlreadyDefined { name } => {
+                write!(f, "`{}` is already defined in this scope", name)
+            }
+            ErrorKind::ImmutableAssignment { name } => {
+                write!(f, "cannot mutate the immutable item `{}`", name)
+            }
+            ErrorKind::IndexOutOfBounds { index, len } => {
+                write!(f, "index {} is out of bounds for length {}", index, len)
+            }
+            ErrorKind::NotIndexable { datatype } => {
+                write!(f, "cannot index into a value of type `{}`", datatype)
+            }
+            ErrorKind::NotAssignable { datatype } => {
+                write!(f, "cannot assign into a value of type `{}`", datatype)
+            }
+            ErrorKind::NotIterable { datatype } => write!(
+                f,
+                "value of type `{}` is not iterable, expected a list or tuple",
+                datatype
+            ),
+            ErrorKind::NotCallable { datatype } => {
+                write!(f, "value of type `{}` is not callable", datatype)
+            }
+            ErrorKind::InvalidOperands { op, lhs, rhs } => {
+                write!(f, "cannot apply `{}` to `{}` and `{}`", op, lhs, rhs)
+            }
+            ErrorKind::InvalidOperand { op, operand } => {
+                write!(f, "cannot apply unary operator `{}` to `{}`", op, operand)
+            }
+            ErrorKind::DivisionByZero { op } => write!(f, "cannot divide by zero with `{}`", op),
+            ErrorKind::Overflow { op } => {
+                write!(f, "the result of `{}` doesn't fit in an `int`", op)
+            }
+            ErrorKind::InvalidConversion { value, to } => {
+                write!(f, "cannot convert `{}` to `{}`", value, to)
+            }
+            ErrorKind::InvalidFormat { format, datatype } => {
+                write!(
+                    f,
+                    "cannot format a value of type `{}` with `{}`",
+                    datatype, format
+                )
+            }
+            ErrorKind::InvalidControlFlow { keyword } => match keyword.as_str() {
+                "return" => write!(f, "`return` outside of function"),
+                keyword => write!(f, "`{}` outside of loop", keyword),
+            },
+            ErrorKind::NotSupportedInModule { statement } => {
+                write!(f, "{} is not supported in modules", statement)
+            }
+            ErrorKind::PrivateItem { name } => write!(f, "item `{}` is private", name),
+            ErrorKind::ModuleNotFound { path } => write!(f, "module `{}` not found", path),
+            ErrorKind::InvalidModule { diagnostics } => {
+                let messages: Vec<&str> = diagnostics
+                    .iter()
+                    .map(|diagnostic| diagnostic.message.as_str())
+                    .collect();
+                write!(f, "{}", messages.join("\n"))
+            }
+            ErrorKind::CircularImport { chain } => {
+                write!(f, "circular import: {}", chain.join(" -> "))
+            }
+            ErrorKind::IoError { message } => write!(f, "{}", message),
+            ErrorKind::Panic { message } => write!(f, "panic: {}", message),
+            ErrorKind::StackOverflow { depth } => {
+                write!(f, "stack overflow, more than {} calls are running", depth)
+            }
+        }
+    }
+}
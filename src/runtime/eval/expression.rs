@@ -1,59 +1,83 @@
 use std::collections::{BTreeMap, HashMap};
-use std::fs;
 
-use crate::ast::{Arg, BinaryOpKind, Branch, Expr, Prop, Statement, UnaryOpKind};
-use crate::grammar;
-use crate::runtime::value::{KeyValue, Value};
-use crate::runtime::{DeclType, Prototypes, ScopeStack, Simple, Type};
-
-use super::program::eval_program_and_push_scope;
+use crate::ast::{
+    Arg, ArmBody, BinaryOpKind, Branch, Expr, ExprKind, Fragment, MatchArm, Pattern, PatternKind,
+    Prop, Statement, UnaryOpKind, Variant,
+};
+use crate::runtime::error::{ErrorKind, RuntimeError};
+use crate::runtime::trace::{self, Frame};
+use crate::runtime::value::{interpolate, BuiltinType, KeyValue, Value};
+use crate::runtime::vm;
+use crate::runtime::{Context, DeclType, ScopeStack, Simple, Type};
+use crate::span::Span;
+
+use super::module::{load_module, resolve_module};
 use super::statement::{eval_module, eval_statements, Escape};
 
 pub fn eval_expression(
     scopes: &mut ScopeStack,
     expression: &Expr,
     prototypes: &HashMap<String, HashMap<String, Value>>,
-) -> Result<Value, String> {
-    match expression {
-        Expr::Null => eval_null_expr(),
-        Expr::Int(n) => eval_int_expr(n),
-        Expr::Float(n) => eval_float_expr(n),
-        Expr::String(s) => eval_stirng_expr(s),
-        Expr::Bool(b) => eval_bool_expr(b),
-        Expr::List(list) => eval_list_expr(scopes, prototypes, list),
-        Expr::Call(expr, args) => eval_call_expr(scopes, prototypes, expr, args),
-        Expr::Identifier(name) => eval_ident_expr(scopes, name),
-        Expr::MethodCall(object, calle) => eval_method_call_expr(scopes, prototypes, object, calle),
-        Expr::Index(expr, loc) => eval_index_expr(scopes, prototypes, expr, loc),
-        Expr::BinaryOp(lhs, op, rhs) => eval_binary_expr(scopes, prototypes, lhs, op, rhs),
-        Expr::UnaryOp(op, expr) => eval_unary_expr(scopes, prototypes, op, expr),
-        Expr::Object(props) => eval_object_expr(scopes, prototypes, props),
-        Expr::Fn(args, ret_type, block) => eval_fn_expr(args, ret_type, block),
-        Expr::ModuleCall(paths, expr) => eval_module_call_expr(scopes, prototypes, paths, expr),
-        Expr::Module(statements) => eval_module_expr(scopes, prototypes, statements),
-        Expr::If(branchs, else_block) => eval_if_expr(scopes, prototypes, branchs, else_block),
-        Expr::Tuple(exprs) => eval_tuple_expr(scopes, prototypes, exprs),
-        Expr::Range(start, end) => eval_range_expr(scopes, prototypes, start, end),
-    }
-}
-
-pub fn eval_null_expr() -> Result<Value, String> {
+) -> Result<Value, RuntimeError> {
+    let res = match &expression.kind {
+        ExprKind::Null => eval_null_expr(),
+        ExprKind::Int(n) => eval_int_expr(n),
+        ExprKind::Float(n) => eval_float_expr(n),
+        ExprKind::String(s) => eval_stirng_expr(s),
+        ExprKind::Bool(b) => eval_bool_expr(b),
+        ExprKind::List(list) => eval_list_expr(scopes, prototypes, list),
+        ExprKind::Call(expr, args) => {
+            eval_call_expr(scopes, prototypes, expr, args, expression.span, None)
+        }
+        ExprKind::Identifier(name, _) => eval_ident_expr(scopes, name),
+        ExprKind::MethodCall(object, calle) => {
+            eval_method_call_expr(scopes, prototypes, object, calle)
+        }
+        ExprKind::Index(expr, loc) => eval_index_expr(scopes, prototypes, expr, loc),
+        ExprKind::SafeChain(chain) => {
+            eval_chain(scopes, prototypes, chain).map(|value| value.unwrap_or(Value::Null))
+        }
+        ExprKind::SafeMethodCall(..) | ExprKind::SafeIndex(..) => {
+            eval_chain(scopes, prototypes, expression).map(|value| value.unwrap_or(Value::Null))
+        }
+        ExprKind::BinaryOp(lhs, op, rhs) => eval_binary_expr(scopes, prototypes, lhs, op, rhs),
+        ExprKind::UnaryOp(op, expr) => eval_unary_expr(scopes, prototypes, op, expr),
+        ExprKind::Object(props) => eval_object_expr(scopes, prototypes, props),
+        ExprKind::Struct(name, props) => eval_struct_expr(scopes, prototypes, name, props),
+        ExprKind::Fn(args, ret_type, block) => eval_fn_expr(scopes, args, ret_type, block),
+        ExprKind::ModuleCall(paths, _, expr) => {
+            eval_module_call_expr(scopes, prototypes, paths, expr, expression.span)
+        }
+        ExprKind::Module(statements) => eval_module_expr(scopes, prototypes, statements),
+        ExprKind::If(branchs, else_block) => eval_if_expr(scopes, prototypes, branchs, else_block),
+        ExprKind::Match(expr, arms) => eval_match_expr(scopes, prototypes, expr, arms),
+        ExprKind::Tuple(exprs) => eval_tuple_expr(scopes, prototypes, exprs),
+        ExprKind::Range(start, end) => eval_range_expr(scopes, prototypes, start, end),
+        ExprKind::Interpolation(fragments) => {
+            eval_interpolation_expr(scopes, prototypes, fragments)
+        }
+    };
+
+    res.map_err(|e| e.with_span(expression.span))
+}
+
+pub fn eval_null_expr() -> Result<Value, RuntimeError> {
     Ok(Value::Null)
 }
 
-pub fn eval_stirng_expr(s: &String) -> Result<Value, String> {
+pub fn eval_stirng_expr(s: &String) -> Result<Value, RuntimeError> {
     Ok(Value::String(s.to_string()))
 }
 
-pub fn eval_int_expr(n: &i32) -> Result<Value, String> {
+pub fn eval_int_expr(n: &i32) -> Result<Value, RuntimeError> {
     Ok(Value::Int(*n))
 }
 
-pub fn eval_float_expr(n: &f32) -> Result<Value, String> {
+pub fn eval_float_expr(n: &f32) -> Result<Value, RuntimeError> {
     Ok(Value::Float(*n))
 }
 
-pub fn eval_bool_expr(b: &bool) -> Result<Value, String> {
+pub fn eval_bool_expr(b: &bool) -> Result<Value, RuntimeError> {
     Ok(Value::Bool(*b))
 }
 
@@ -61,282 +85,484 @@ pub fn eval_list_expr(
     scopes: &mut ScopeStack,
     prototypes: &HashMap<String, HashMap<String, Value>>,
     list: &Vec<Expr>,
-) -> Result<Value, String> {
+) -> Result<Value, RuntimeError> {
     let mut values: Vec<Value> = Vec::new();
 
     for expr in list {
-        let value = eval_expression(scopes, expr, &prototypes)?;
+        let value = eval_expression(scopes, expr, prototypes)?;
 
         values.push(value);
     }
 
-    Ok(Value::List(values))
+    Ok(Value::list(values))
 }
 
 pub fn eval_call_expr(
     scopes: &mut ScopeStack,
     prototypes: &HashMap<String, HashMap<String, Value>>,
-    expr: &Box<Expr>,
+    expr: &Expr,
     args: &Vec<Expr>,
-) -> Result<Value, String> {
-    let value = eval_expression(scopes, &expr, &prototypes)?;
+    span: Span,
+    module: Option<String>,
+) -> Result<Value, RuntimeError> {
+    let value = eval_expression(scopes, expr, prototypes)?;
+
+    let mut values = vec![];
+    for arg in args {
+        values.push(eval_expression(scopes, arg, prototypes)?);
+    }
 
-    match value {
-        Value::BuiltInFn(f) => {
-            let mut values = vec![];
+    call_value(prototypes, value, values, call_name(expr), module, span)
+}
 
-            for arg in args {
-                let val = eval_expression(scopes, arg, &prototypes)?;
-                values.push(val);
+/// the name a call is shown with in the stack trace
+pub fn call_name(calle: &Expr) -> String {
+    match &calle.kind {
+        ExprKind::Identifier(name, _) => name.to_string(),
+        ExprKind::MethodCall(_, calle) | ExprKind::SafeMethodCall(_, calle) => match &calle.kind {
+            ExprKind::Identifier(name, _) => name.to_string(),
+            _ => String::from("<anonymous>"),
+        },
+        _ => String::from("<anonymous>"),
+    }
+}
+
+/// calls `value` with arguments that are already evaluated,
+/// `name`, `module` and `span` describe the call in the stack trace
+pub fn call_value(
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    value: Value,
+    values: Vec<Value>,
+    name: String,
+    module: Option<String>,
+    span: Span,
+) -> Result<Value, RuntimeError> {
+    // the tree-walker recurses through here, so the stack grows as calls nest
+    trace::grow(|| {
+        match value {
+            Value::BuiltInFn(f) => {
+                let _frame = trace::enter(Frame::new(name, module, span, true))?;
+                let ctx = Context::new(prototypes, span);
+                let value = f
+                    .call(&ctx, values)
+                    .map_err(|e| e.with_trace(trace::capture()))?;
+                Ok(value)
             }
+            Value::Func(params, _, block, env, _) => {
+                if params.len() != values.len() {
+                    return Err(ErrorKind::ArityMismatch {
+                        expected: params.len(),
+                        found: values.len(),
+                    }
+                    .into());
+                }
 
-            let value = f(values)?;
-            return Ok(value);
-        }
-        Value::Func(params, _, block) => {
-            if params.len() != args.len() {
-                return Err(format!(
-                    "expected {} arguments but found {}",
-                    params.len(),
-                    args.len()
-                ));
-            }
-
-            let mut inner_scope = scopes.new_from_push(HashMap::new());
-            for (i, param) in params.iter().enumerate() {
-                match args.get(i) {
-                    Some(expr) => {
-                        let value = eval_expression(&mut inner_scope, expr, &prototypes)?;
-
-                        inner_scope.declare(
-                            &param.ident,
-                            value,
-                            &Some(param.datatype.clone()),
-                            DeclType::Mutable,
-                        )?;
+                // the body runs in the captured environment
+                let mut inner_scope = env.new_from_push(HashMap::new());
+                for (param, value) in params.iter().zip(values) {
+                    inner_scope.declare(
+                        &param.ident,
+                        value,
+                        &Some(param.datatype.clone()),
+                        DeclType::Mutable,
+                    )?;
+                }
+
+                let _frame = trace::enter(Frame::new(name, module, span, false))?;
+                let ret = eval_statements(&mut inner_scope, &block, prototypes)
+                    .map_err(|e| e.with_trace(trace::capture()))?;
+                match ret {
+                    Escape::None => Ok(Value::Null),
+                    Escape::Return(value) => Ok(value),
+                    Escape::Break => Err(ErrorKind::InvalidControlFlow {
+                        keyword: "break".to_string(),
                     }
-                    None => {
-                        return Err(format!(
-                            "expected {} arguments but found {}",
-                            params.len(),
-                            args.len()
-                        ))
+                    .into()),
+                    Escape::Continue => Err(ErrorKind::InvalidControlFlow {
+                        keyword: "continue".to_string(),
                     }
+                    .into()),
                 }
             }
-
-            let ret = eval_statements(&mut inner_scope, &block, prototypes)?;
-            match ret {
-                Escape::None => Ok(Value::Null),
-                Escape::Return(value) => Ok(value),
-                Escape::Break => Err(format!("break outside of loop (2)")),
-                Escape::Continue => Err(format!("continue out side of loop (2)")),
+            Value::Closure(closure) => vm::call(prototypes, &closure, values, name, module, span),
+            Value::BuiltInMethod(f, this) => {
+                if let Some(this) = this {
+                    let _frame = trace::enter(Frame::new(name, module, span, true))?;
+                    let res = f
+                        .call(&Context::new(prototypes, span), values, *this)
+                        .map_err(|e| e.with_trace(trace::capture()))?;
+                    Ok(res)
+                } else {
+                    Err(ErrorKind::NotCallable {
+                        datatype: "function".to_string(),
+                    }
+                    .into())
+                }
             }
-        }
-        Value::BuiltInMethod(f, this) => {
-            let mut values = vec![];
-
-            for arg in args {
-                let val = eval_expression(scopes, arg, &prototypes)?;
-                values.push(val);
+            _ => Err(ErrorKind::NotCallable {
+                datatype: Type::simple(&value),
             }
-            if let Some(this) = this {
-                let res = f(values, *this)?;
-                return Ok(res);
-            } else {
-                return Err("dev error".to_string());
-            }
-        }
-        _ => {
-            return Err(format!(
-                "value of type '{:?}' is not callable (5)",
-                String::from(Type::from(&value))
-            ));
+            .into()),
         }
-    }
+    })
 }
 
-pub fn eval_ident_expr(scopes: &mut ScopeStack, name: &String) -> Result<Value, String> {
-    match scopes.get(&name) {
+pub fn eval_ident_expr(scopes: &mut ScopeStack, name: &String) -> Result<Value, RuntimeError> {
+    match scopes.get(name) {
         Some(v) => Ok(v),
-        None => Err(format!("{} is not defied (8)", name)),
+        None => Err(ErrorKind::UndefinedName {
+            name: name.to_string(),
+        }
+        .into()),
     }
 }
 
 pub fn eval_method_call_expr(
     scopes: &mut ScopeStack,
     prototypes: &HashMap<String, HashMap<String, Value>>,
-    object: &Box<Expr>,
-    calle: &Box<Expr>,
-) -> Result<Value, String> {
-    let obj_value = eval_expression(scopes, &*object, &prototypes)?;
-
-    match *calle.clone() {
-        Expr::Identifier(name) => match prototypes.get(&Type::simple(&obj_value.clone())) {
-            Some(proto) => match proto.get(&name) {
-                Some(value) => {
-                    if let Value::BuiltInMethod(f, _) = value {
-                        return Ok(Value::BuiltInMethod(*f, Some(Box::new(obj_value))));
-                    }
-                    return Ok(value.to_owned());
-                }
-                None => {
-                    if let Value::Object(props) = &obj_value {
-                        let prop = props.into_iter().find(|kv| kv.key == name);
+    object: &Expr,
+    calle: &Expr,
+) -> Result<Value, RuntimeError> {
+    let obj_value = eval_expression(scopes, object, prototypes)?;
 
-                        if let Some(kv) = prop {
-                            return Ok(kv.value.clone());
-                        }
-                    }
-                    return Err(format!(
-                        "'{}' dose not exist in '{:?}' prototype (6)",
-                        name,
-                        String::from(Type::from(&obj_value))
-                    ));
+    member_value(scopes, prototypes, obj_value, calle, false)
+}
+
+/// the member `calle` of `obj_value`, `safe` when it is looked up through `?.`
+fn member_value(
+    scopes: &mut ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    obj_value: Value,
+    calle: &Expr,
+    safe: bool,
+) -> Result<Value, RuntimeError> {
+    match &calle.kind {
+        ExprKind::Identifier(name, _) if safe => {
+            get_safe_member(scopes, prototypes, obj_value, name)
+        }
+        ExprKind::Int(n) if safe => get_safe_member(scopes, prototypes, obj_value, &n.to_string()),
+        ExprKind::Identifier(name, _) => get_member(scopes, prototypes, obj_value, name),
+        ExprKind::Call(expr, args) => match &expr.kind {
+            ExprKind::Identifier(name, _) => {
+                let mut values = vec![];
+                for arg in args {
+                    values.push(eval_expression(scopes, arg, prototypes)?);
                 }
-            },
-            None => {
-                return Err(format!(
-                    "the prototype for type {:?} is not implemented (8)",
-                    String::from(Type::from(&obj_value))
-                ));
+
+                call_proto_method(prototypes, obj_value, name, values, calle.span)
+            }
+            _ => Err(ErrorKind::NotCallable {
+                datatype: Type::simple(&obj_value),
             }
+            .into()),
         },
-        Expr::Call(expr, args) => match *expr {
-            Expr::Identifier(name) => match prototypes.get(&Type::simple(&obj_value.clone())) {
-                Some(proto) => match proto.get(&name) {
-                    Some(value) => match value {
-                        Value::BuiltInMethod(f, _) => {
-                            let mut values = vec![];
-
-                            for arg in args {
-                                let val = eval_expression(scopes, &arg, &prototypes)?;
-                                values.push(val);
-                            }
-
-                            let res = f(values, obj_value.to_owned())?;
-                            return Ok(res);
-                        }
-                        _ => todo!(),
-                    },
-                    None => {
-                        if let Value::Object(props) = &obj_value {
-                            let prop = props.into_iter().find(|kv| kv.key == name);
-                            if let Some(kv) = prop {
-                                return Ok(kv.value.to_owned());
-                            }
-                        }
-                        return Err(format!(
-                            "'{}' dose not exist in '{:?}' prototype (3)",
-                            name,
-                            String::from(Type::from(&obj_value))
-                        ));
+        ExprKind::Int(n) => get_member(scopes, prototypes, obj_value, &n.to_string()),
+        _ => Err(ErrorKind::NotCallable {
+            datatype: Type::simple(&obj_value),
+        }
+        .into()),
+    }
+}
+
+/// `object.name`: a field of an instance, a method from an `impl` block or a prototype,
+/// or a property of an object
+pub fn get_member(
+    scopes: &ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    obj_value: Value,
+    name: &String,
+) -> Result<Value, RuntimeError> {
+    if let Value::Instance(_, fields) = &obj_value {
+        if let Some(kv) = fields.iter().find(|kv| &kv.key == name) {
+            return Ok(kv.value.clone());
+        }
+    }
+
+    // methods declared in `impl` blocks
+    if let Some(method) = scopes.get_method(&obj_value, name)? {
+        return Ok(method);
+    }
+
+    match prototypes.get(&Type::simple(&obj_value.clone())) {
+        Some(proto) => match proto.get(name) {
+            Some(value) => {
+                if let Value::BuiltInMethod(f, _) = value {
+                    return Ok(Value::BuiltInMethod(f.clone(), Some(Box::new(obj_value))));
+                }
+                Ok(value.to_owned())
+            }
+            None => {
+                if let Value::Object(props) = &obj_value {
+                    let props = props.lock();
+                    let prop = props.iter().find(|kv| &kv.key == name);
+
+                    if let Some(kv) = prop {
+                        return Ok(kv.value.clone());
                     }
-                },
-                None => {
-                    return Err(format!(
-                        "the prototype for type {:?} is not implemented",
-                        String::from(Type::from(&obj_value))
-                    ))
                 }
-            },
-            _ => {
-                return Err(format!(
-                    "value of type {:?} not callable (2)",
-                    String::from(Type::from(&obj_value))
-                ));
+                Err(ErrorKind::UndefinedProperty {
+                    name: name.to_string(),
+                    prototype: Type::simple(&obj_value),
+                }
+                .into())
             }
         },
-        Expr::Int(n) => {
-            return Err(format!(
-                "{} is not found in {} prototype",
-                n,
-                String::from(Type::from(&obj_value))
-            ))
+        None => Err(ErrorKind::UndefinedProperty {
+            name: name.to_string(),
+            prototype: Type::simple(&obj_value),
         }
+        .into()),
+    }
+}
 
-        _ => {
-            return Err(format!(
-                "value of type {:?} not callable (1)",
-                String::from(Type::from(&obj_value))
-            ));
+/// `object?.name`, a key the object doesn't have is `null` instead of an error
+pub fn get_safe_member(
+    scopes: &ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    obj_value: Value,
+    name: &String,
+) -> Result<Value, RuntimeError> {
+    let object = matches!(obj_value, Value::Object(_));
+
+    match get_member(scopes, prototypes, obj_value, name) {
+        Err(e) if object && matches!(*e.kind, ErrorKind::UndefinedProperty { .. }) => {
+            Ok(Value::Null)
         }
+        res => res,
     }
 }
 
-pub fn eval_index_expr(
+/// the links of a chain with a `?.` in it, `None` when a `?.` found `null` and the rest was skipped
+pub fn eval_chain(
     scopes: &mut ScopeStack,
     prototypes: &HashMap<String, HashMap<String, Value>>,
-    expr: &Box<Expr>,
-    loc: &Box<Expr>,
-) -> Result<Value, String> {
-    let expr_value = eval_expression(scopes, &*expr, &prototypes)?;
-
-    match &expr_value {
-        Value::String(s) => {
-            let loc_value = eval_expression(scopes, &*loc, &prototypes)?;
-
-            match loc_value {
-                Value::Int(index) => {
-                    if let Some(res) = s.chars().nth(index as usize) {
-                        return Ok(Value::String(res.to_string()));
-                    } else {
-                        return Err(format!("index out of bounds"));
+    expr: &Expr,
+) -> Result<Option<Value>, RuntimeError> {
+    let safe = matches!(
+        expr.kind,
+        ExprKind::SafeMethodCall(..) | ExprKind::SafeIndex(..)
+    );
+
+    let res = match &expr.kind {
+        ExprKind::MethodCall(object, calle) | ExprKind::SafeMethodCall(object, calle) => {
+            let obj_value = match eval_chain(scopes, prototypes, object)? {
+                Some(Value::Null) if safe => return Ok(None),
+                Some(value) => value,
+                None => return Ok(None),
+            };
+
+            member_value(scopes, prototypes, obj_value, calle, safe)
+        }
+        ExprKind::Index(target, index) | ExprKind::SafeIndex(target, index) => {
+            let value = match eval_chain(scopes, prototypes, target)? {
+                Some(Value::Null) if safe => return Ok(None),
+                Some(value) => value,
+                None => return Ok(None),
+            };
+            check_indexable(&value).map_err(|e| e.with_span(expr.span))?;
+
+            let index = eval_expression(scopes, index, prototypes)?;
+            match safe {
+                true => safe_index_value(&value, index),
+                false => index_value(&value, index),
+            }
+        }
+        ExprKind::Call(calle, args) => {
+            let value = match eval_chain(scopes, prototypes, calle)? {
+                Some(value) => value,
+                None => return Ok(None),
+            };
+
+            let mut values = vec![];
+            for arg in args {
+                values.push(eval_expression(scopes, arg, prototypes)?);
+            }
+
+            call_value(prototypes, value, values, call_name(calle), None, expr.span)
+        }
+        _ => eval_expression(scopes, expr, prototypes),
+    };
+
+    res.map(Some).map_err(|e| e.with_span(expr.span))
+}
+
+/// `object.(name(args))`, calls a method of the prototype of `obj_value` directly
+pub fn call_proto_method(
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    obj_value: Value,
+    name: &String,
+    values: Vec<Value>,
+    span: Span,
+) -> Result<Value, RuntimeError> {
+    match prototypes.get(&Type::simple(&obj_value.clone())) {
+        Some(proto) => match proto.get(name) {
+            Some(value) => match value {
+                Value::BuiltInMethod(f, _) => {
+                    let ctx = Context::new(prototypes, span);
+                    let res = f.call(&ctx, values, obj_value.to_owned())?;
+                    Ok(res)
+                }
+                _ => todo!(),
+            },
+            None => {
+                if let Value::Object(props) = &obj_value {
+                    let props = props.lock();
+                    let prop = props.iter().find(|kv| &kv.key == name);
+                    if let Some(kv) = prop {
+                        return Ok(kv.value.to_owned());
                     }
                 }
-                _ => {
-                    return Err(format!(
-                        "the type {:?} cannot be indexed by {:?}",
-                        String::from(Type::from(&expr_value)),
-                        String::from(Type::from(&loc_value))
-                    ))
+                Err(ErrorKind::UndefinedProperty {
+                    name: name.to_string(),
+                    prototype: Type::simple(&obj_value),
                 }
+                .into())
             }
+        },
+        None => Err(ErrorKind::UndefinedProperty {
+            name: name.to_string(),
+            prototype: Type::simple(&obj_value),
+        }
+        .into()),
+    }
+}
+
+pub fn eval_index_expr(
+    scopes: &mut ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    expr: &Expr,
+    loc: &Expr,
+) -> Result<Value, RuntimeError> {
+    let expr_value = eval_expression(scopes, expr, prototypes)?;
+    check_indexable(&expr_value)?;
+
+    let loc_value = eval_expression(scopes, loc, prototypes)?;
+    index_value(&expr_value, loc_value)
+}
+
+/// only strings, lists and tuples can be indexed
+pub fn check_indexable(value: &Value) -> Result<(), RuntimeError> {
+    match value {
+        Value::String(_) | Value::List(..) | Value::Tuple(_) => Ok(()),
+        _ => Err(ErrorKind::NotIndexable {
+            datatype: Type::simple(value),
         }
-        Value::List(l) | Value::Tuple(l) => {
-            let loc_value = eval_expression(scopes, &*loc, &prototypes)?;
+        .into()),
+    }
+}
 
-            match loc_value {
-                Value::Int(index) => {
-                    if let Some(res) = l.get(index as usize) {
-                        return Ok(Value::from(res));
-                    } else {
-                        return Err(format!("index out of bounds"));
+/// `value?.[index]`, an index past the end is `null` instead of an error
+pub fn safe_index_value(value: &Value, index: Value) -> Result<Value, RuntimeError> {
+    match index_value(value, index) {
+        Err(e) if matches!(*e.kind, ErrorKind::IndexOutOfBounds { .. }) => Ok(Value::Null),
+        res => res,
+    }
+}
+
+pub fn index_value(expr_value: &Value, loc_value: Value) -> Result<Value, RuntimeError> {
+    match expr_value {
+        Value::String(s) => match loc_value {
+            Value::Int(index) => {
+                if let Some(res) = s.chars().nth(index as usize) {
+                    Ok(Value::String(res.to_string()))
+                } else {
+                    Err(ErrorKind::IndexOutOfBounds {
+                        index,
+                        len: s.chars().count(),
                     }
+                    .into())
                 }
-                _ => {
-                    return Err(format!(
-                        "the type {:?} cannot be indexed by {:?}",
-                        String::from(Type::from(&expr_value)),
-                        String::from(Type::from(&loc_value))
-                    ))
+            }
+            _ => Err(ErrorKind::TypeMismatch {
+                expected: Type::Builtin(BuiltinType::Int),
+                found: Type::from(&loc_value),
+            }
+            .into()),
+        },
+        Value::List(l, _) => index_items(&l.lock(), loc_value),
+        Value::Tuple(l) => index_items(l, loc_value),
+        _ => Err(ErrorKind::NotIndexable {
+            datatype: Type::simple(expr_value),
+        }
+        .into()),
+    }
+}
+
+fn index_items(items: &[Value], loc_value: Value) -> Result<Value, RuntimeError> {
+    match loc_value {
+        Value::Int(index) => {
+            if let Some(res) = items.get(index as usize) {
+                Ok(Value::from(res))
+            } else {
+                Err(ErrorKind::IndexOutOfBounds {
+                    index,
+                    len: items.len(),
                 }
+                .into())
             }
         }
-        _ => {
-            return Err(format!(
-                "cannot index into a value of type {:?}",
-                String::from(Type::from(&expr_value)),
-            ));
+        _ => Err(ErrorKind::TypeMismatch {
+            expected: Type::Builtin(BuiltinType::Int),
+            found: Type::from(&loc_value),
         }
+        .into()),
     }
 }
 
 pub fn eval_binary_expr(
     scopes: &mut ScopeStack,
     prototypes: &HashMap<String, HashMap<String, Value>>,
-    lhs: &Box<Expr>,
+    lhs: &Expr,
     op: &BinaryOpKind,
-    rhs: &Box<Expr>,
-) -> Result<Value, String> {
-    let lhs = eval_expression(scopes, &*lhs, &prototypes)?;
-    let rhs = eval_expression(scopes, &*rhs, &prototypes)?;
+    rhs: &Expr,
+) -> Result<Value, RuntimeError> {
+    let lhs = eval_expression(scopes, lhs, prototypes)?;
+    if let Some(value) = short_circuit(op, &lhs)? {
+        return Ok(value);
+    }
+    let rhs = eval_expression(scopes, rhs, prototypes)?;
+
+    binary_value(op, lhs, rhs)
+}
+
+/// the value of `&&`, `||` or `??` when `lhs` decides it, the right side isn't evaluated then
+pub fn short_circuit(op: &BinaryOpKind, lhs: &Value) -> Result<Option<Value>, RuntimeError> {
+    let stop = match op {
+        BinaryOpKind::And => false,
+        BinaryOpKind::Or => true,
+        BinaryOpKind::Coalesce if *lhs == Value::Null => return Ok(None),
+        BinaryOpKind::Coalesce => return Ok(Some(lhs.clone())),
+        _ => return Ok(None),
+    };
+
+    match lhs {
+        Value::Bool(b) if *b == stop => Ok(Some(Value::Bool(stop))),
+        Value::Bool(_) => Ok(None),
+        _ => Err(ErrorKind::TypeMismatch {
+            expected: Type::Builtin(BuiltinType::Bool),
+            found: Type::from(lhs),
+        }
+        .into()),
+    }
+}
 
-    let res = match op {
+pub fn binary_value(op: &BinaryOpKind, lhs: Value, rhs: Value) -> Result<Value, RuntimeError> {
+    match op {
         BinaryOpKind::Add => &lhs + &rhs,
         BinaryOpKind::Sub => &lhs - &rhs,
         BinaryOpKind::Mul => &lhs * &rhs,
         BinaryOpKind::Div => &lhs / &rhs,
+        BinaryOpKind::Coalesce => match lhs {
+            Value::Null => Ok(rhs),
+            lhs => Ok(lhs),
+        },
+        BinaryOpKind::Rem => &lhs % &rhs,
+        BinaryOpKind::Pow => lhs.pow(&rhs),
+        BinaryOpKind::BitAnd => &lhs & &rhs,
+        BinaryOpKind::BitOr => &lhs | &rhs,
+        BinaryOpKind::BitXor => &lhs ^ &rhs,
+        BinaryOpKind::Shl => &lhs << &rhs,
+        BinaryOpKind::Shr => &lhs >> &rhs,
         BinaryOpKind::EQ => Ok(Value::Bool(lhs == rhs)),
         BinaryOpKind::NE => Ok(Value::Bool(lhs != rhs)),
         BinaryOpKind::GT => Ok(Value::Bool(lhs > rhs)),
@@ -346,52 +572,60 @@ pub fn eval_binary_expr(
         BinaryOpKind::And => {
             if let Value::Bool(v1) = lhs {
                 if let Value::Bool(v2) = rhs {
-                    return Ok(Value::Bool(v1 && v2));
+                    Ok(Value::Bool(v1 && v2))
                 } else {
-                    return Err(format!(
-                        "expected bool found {:?}",
-                        String::from(Type::from(&rhs))
-                    ));
+                    Err(ErrorKind::TypeMismatch {
+                        expected: Type::Builtin(BuiltinType::Bool),
+                        found: Type::from(&rhs),
+                    }
+                    .into())
                 }
             } else {
-                return Err(format!(
-                    "expected bool found {:?}",
-                    String::from(Type::from(&lhs))
-                ));
+                Err(ErrorKind::TypeMismatch {
+                    expected: Type::Builtin(BuiltinType::Bool),
+                    found: Type::from(&lhs),
+                }
+                .into())
             }
         }
         BinaryOpKind::Or => {
             if let Value::Bool(v1) = lhs {
                 if let Value::Bool(v2) = rhs {
-                    return Ok(Value::Bool(v1 || v2));
+                    Ok(Value::Bool(v1 || v2))
                 } else {
-                    return Err(format!(
-                        "expected bool found {:?}",
-                        String::from(Type::from(&rhs))
-                    ));
+                    Err(ErrorKind::TypeMismatch {
+                        expected: Type::Builtin(BuiltinType::Bool),
+                        found: Type::from(&rhs),
+                    }
+                    .into())
                 }
             } else {
-                return Err(format!(
-                    "expected bool found {:?}",
-                    String::from(Type::from(&lhs))
-                ));
+                Err(ErrorKind::TypeMismatch {
+                    expected: Type::Builtin(BuiltinType::Bool),
+                    found: Type::from(&lhs),
+                }
+                .into())
             }
         }
-    };
-
-    res
+    }
 }
 
 pub fn eval_unary_expr(
     scopes: &mut ScopeStack,
     prototypes: &HashMap<String, HashMap<String, Value>>,
     op: &UnaryOpKind,
-    expr: &Box<Expr>,
-) -> Result<Value, String> {
-    let value = eval_expression(scopes, &*expr, prototypes)?;
+    expr: &Expr,
+) -> Result<Value, RuntimeError> {
+    let value = eval_expression(scopes, expr, prototypes)?;
 
+    unary_value(op, value)
+}
+
+pub fn unary_value(op: &UnaryOpKind, value: Value) -> Result<Value, RuntimeError> {
     match op {
         UnaryOpKind::Not => !value,
+        UnaryOpKind::Neg => -value,
+        UnaryOpKind::BitNot => value.complement(),
         UnaryOpKind::Typeof => Ok(Value::String(Type::simple(&value))),
     }
 }
@@ -400,11 +634,11 @@ pub fn eval_object_expr(
     scopes: &mut ScopeStack,
     prototypes: &HashMap<String, HashMap<String, Value>>,
     props: &Vec<Prop>,
-) -> Result<Value, String> {
+) -> Result<Value, RuntimeError> {
     let mut values: Vec<KeyValue> = Vec::new();
 
     for prop in props {
-        let value = eval_expression(scopes, &prop.value, &prototypes)?;
+        let value = eval_expression(scopes, &prop.value, prototypes)?;
 
         values.push(KeyValue {
             key: prop.key.to_string(),
@@ -412,47 +646,250 @@ pub fn eval_object_expr(
         });
     }
 
-    Ok(Value::Object(values))
+    Ok(Value::object(values))
+}
+
+pub fn eval_struct_expr(
+    scopes: &mut ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    name: &String,
+    props: &Vec<Prop>,
+) -> Result<Value, RuntimeError> {
+    let mut values = vec![];
+    for prop in props {
+        values.push(eval_expression(scopes, &prop.value, prototypes)?);
+    }
+
+    instance_value(scopes, name, props, values)
+}
+
+/// an instance of the struct `name`, `values` are the values of `props`
+pub fn instance_value(
+    scopes: &ScopeStack,
+    name: &String,
+    props: &[Prop],
+    values: Vec<Value>,
+) -> Result<Value, RuntimeError> {
+    let fields = match scopes.get(name) {
+        Some(Value::Struct(_, fields)) => fields,
+        Some(_) => {
+            return Err(ErrorKind::NotAType {
+                name: name.to_string(),
+            }
+            .into())
+        }
+        None => {
+            return Err(ErrorKind::UndefinedType {
+                name: name.to_string(),
+            }
+            .into())
+        }
+    };
+
+    let mut checked: Vec<KeyValue> = Vec::new();
+
+    for (prop, value) in props.iter().zip(values) {
+        if checked.iter().any(|kv| kv.key == prop.key) {
+            return Err(RuntimeError::new(ErrorKind::DuplicateProperty {
+                name: prop.key.to_string(),
+            })
+            .with_span(prop.span));
+        }
+
+        let field = match fields.iter().find(|field| field.name == prop.key) {
+            Some(field) => field,
+            None => {
+                return Err(RuntimeError::new(ErrorKind::UndefinedProperty {
+                    name: prop.key.to_string(),
+                    prototype: name.to_string(),
+                })
+                .with_span(prop.span))
+            }
+        };
+
+        let expected = scopes.get_type_alias(&field.datatype)?;
+        let found = scopes.type_of(&value)?;
+        if expected != found {
+            return Err(
+                RuntimeError::new(ErrorKind::TypeMismatch { expected, found })
+                    .with_span(prop.value.span),
+            );
+        }
+
+        checked.push(KeyValue {
+            key: prop.key.to_string(),
+            value,
+        });
+    }
+
+    // fields are stored in declaration order
+    let mut instance = Vec::new();
+    for field in &fields {
+        match checked.iter().position(|kv| kv.key == field.name) {
+            Some(i) => instance.push(checked.remove(i)),
+            None => {
+                return Err(ErrorKind::MissingField {
+                    name: field.name.to_string(),
+                    datatype: name.to_string(),
+                }
+                .into())
+            }
+        }
+    }
+
+    Ok(Value::Instance(name.to_string(), instance))
 }
 
 pub fn eval_fn_expr(
-    args: &Vec<Arg>,
+    scopes: &mut ScopeStack,
+    args: &[Arg],
     ret_type: &Option<Type>,
-    block: &Vec<Statement>,
-) -> Result<Value, String> {
-    Ok(Value::Func(args.to_vec(), ret_type.clone(), block.to_vec()))
+    block: &[Statement],
+) -> Result<Value, RuntimeError> {
+    Ok(Value::Func(
+        args.to_vec(),
+        ret_type.clone(),
+        block.to_vec(),
+        scopes.clone(),
+        None,
+    ))
 }
 
 pub fn eval_module_call_expr(
     scopes: &mut ScopeStack,
     prototypes: &HashMap<String, HashMap<String, Value>>,
-    paths: &Vec<String>,
-    expr: &Box<Expr>,
-) -> Result<Value, String> {
-    let module = get_module(scopes, paths)?;
+    paths: &[String],
+    expr: &Expr,
+    span: Span,
+) -> Result<Value, RuntimeError> {
+    // `Shape::Circle(1.0)` looks like a module call but constructs an enum variant
+    if let Some(Value::Enum(name, variants)) = get_path(scopes, paths) {
+        return eval_variant_expr(scopes, prototypes, &name, &variants, expr);
+    }
+
+    let (module, private) = get_module(scopes, prototypes, paths, span)?;
+
+    let item = match &expr.kind {
+        ExprKind::Call(calle, _) => match &calle.kind {
+            ExprKind::Identifier(name, _) => Some(name),
+            _ => None,
+        },
+        ExprKind::Identifier(name, _) | ExprKind::Struct(name, _) => Some(name),
+        _ => None,
+    };
+    if let Some(name) = item.filter(|name| private.contains(name)) {
+        return Err(ErrorKind::PrivateItem {
+            name: name.to_string(),
+        }
+        .into());
+    }
 
     let mut inner_scopes = scopes.new_from_push(HashMap::new());
 
     for (key, value) in module {
-        inner_scopes.declare(
-            &key,
-            value.clone(),
-            &Some(Type::from(&value)),
-            DeclType::Immutable,
-        )?;
+        inner_scopes.declare(&key, value.clone(), &None, DeclType::Immutable)?;
     }
 
-    let value = eval_expression(&mut inner_scopes, &*expr, &prototypes)?;
+    // calls through a module path are recorded with the path in the backtrace
+    if let ExprKind::Call(calle, args) = &expr.kind {
+        let module = Some(paths.join("::"));
+        return eval_call_expr(&mut inner_scopes, prototypes, calle, args, span, module);
+    }
+
+    let value = eval_expression(&mut inner_scopes, expr, prototypes)?;
     Ok(value)
 }
 
+pub fn eval_variant_expr(
+    scopes: &mut ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    name: &String,
+    variants: &[Variant],
+    expr: &Expr,
+) -> Result<Value, RuntimeError> {
+    let (variant_name, args) = match &expr.kind {
+        ExprKind::Identifier(variant_name, _) => (variant_name, vec![]),
+        ExprKind::Call(calle, args) => match &calle.kind {
+            ExprKind::Identifier(variant_name, _) => (variant_name, args.to_vec()),
+            _ => {
+                return Err(ErrorKind::NotCallable {
+                    datatype: name.to_string(),
+                }
+                .into())
+            }
+        },
+        _ => {
+            return Err(ErrorKind::NotCallable {
+                datatype: name.to_string(),
+            }
+            .into())
+        }
+    };
+
+    let mut values = vec![];
+    for arg in &args {
+        values.push(eval_expression(scopes, arg, prototypes)?);
+    }
+
+    let spans: Vec<Span> = args.iter().map(|arg| arg.span).collect();
+    variant_value(scopes, name, variants, variant_name, values, &spans)
+}
+
+/// the variant `variant_name` of the enum `name` holding `values`, `spans` point to the arguments
+pub fn variant_value(
+    scopes: &ScopeStack,
+    name: &String,
+    variants: &[Variant],
+    variant_name: &String,
+    values: Vec<Value>,
+    spans: &[Span],
+) -> Result<Value, RuntimeError> {
+    let variant = match variants.iter().find(|v| &v.name == variant_name) {
+        Some(variant) => variant,
+        None => {
+            return Err(ErrorKind::UndefinedProperty {
+                name: variant_name.to_string(),
+                prototype: name.to_string(),
+            }
+            .into())
+        }
+    };
+
+    if variant.fields.len() != values.len() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: variant.fields.len(),
+            found: values.len(),
+        }
+        .into());
+    }
+
+    for (i, (datatype, value)) in variant.fields.iter().zip(&values).enumerate() {
+        let expected = scopes.get_type_alias(datatype)?;
+        let found = scopes.type_of(value)?;
+        if expected != found {
+            return Err(RuntimeError::new(ErrorKind::InvalidArgument {
+                position: i + 1,
+                expected,
+                found,
+            })
+            .with_span(spans[i]));
+        }
+    }
+
+    Ok(Value::Variant(
+        name.to_string(),
+        variant_name.to_string(),
+        values,
+    ))
+}
+
 pub fn eval_module_expr(
     scopes: &mut ScopeStack,
     prototypes: &HashMap<String, HashMap<String, Value>>,
     statements: &Vec<Statement>,
-) -> Result<Value, String> {
+) -> Result<Value, RuntimeError> {
     let module = eval_module(scopes, prototypes, &String::from("test"), statements)?;
-    Ok(Value::Module(module))
+    Ok(module)
 }
 
 pub fn eval_if_expr(
@@ -460,14 +897,14 @@ pub fn eval_if_expr(
     prototypes: &HashMap<String, HashMap<String, Value>>,
     branchs: &Vec<Branch>,
     else_block: &Option<Vec<Statement>>,
-) -> Result<Value, String> {
+) -> Result<Value, RuntimeError> {
     for branch in branchs {
-        let value = eval_expression(scopes, &branch.condition, &prototypes)?;
+        let value = eval_expression(scopes, &branch.condition, prototypes)?;
 
         match value {
             Value::Bool(b) => {
                 if b {
-                    let ret = eval_statements(scopes, &branch.statements, &prototypes)?;
+                    let ret = eval_statements(scopes, &branch.statements, prototypes)?;
 
                     if let Escape::Return(value) = ret {
                         return Ok(value);
@@ -476,12 +913,18 @@ pub fn eval_if_expr(
                     return Ok(Value::Null);
                 }
             }
-            _ => return Err(format!("condition most be a boolean")),
+            other => {
+                return Err(RuntimeError::new(ErrorKind::TypeMismatch {
+                    expected: Type::Builtin(BuiltinType::Bool),
+                    found: Type::from(&other),
+                })
+                .with_span(branch.condition.span))
+            }
         }
     }
 
     if let Some(stmts) = else_block {
-        let e = eval_statements(scopes, stmts, &prototypes)?;
+        let e = eval_statements(scopes, stmts, prototypes)?;
 
         if let Escape::Return(value) = e {
             return Ok(value);
@@ -491,15 +934,124 @@ pub fn eval_if_expr(
     Ok(Value::Null)
 }
 
+pub fn eval_match_expr(
+    scopes: &mut ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    expr: &Expr,
+    arms: &Vec<MatchArm>,
+) -> Result<Value, RuntimeError> {
+    let value = eval_expression(scopes, expr, prototypes)?;
+    let (arm, mut inner_scopes) = find_arm(scopes, prototypes, &value, arms)?;
+
+    match &arm.body {
+        ArmBody::Expr(expr) => eval_expression(&mut inner_scopes, expr, prototypes),
+        ArmBody::Block(block) => {
+            let ret = eval_statements(&mut inner_scopes, block, prototypes)?;
+
+            if let Escape::Return(value) = ret {
+                return Ok(value);
+            }
+
+            Ok(Value::Null)
+        }
+    }
+}
+
+/// finds the first arm matching `value`, and a scope holding the names its pattern binds
+pub fn find_arm<'a>(
+    scopes: &mut ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    value: &Value,
+    arms: &'a Vec<MatchArm>,
+) -> Result<(&'a MatchArm, ScopeStack), RuntimeError> {
+    for arm in arms {
+        let mut bindings = vec![];
+
+        if match_pattern(scopes, prototypes, &arm.pattern, value, &mut bindings)? {
+            let mut inner_scopes = scopes.new_from_push(HashMap::new());
+
+            for (name, value) in bindings {
+                inner_scopes.declare(&name, value, &None, DeclType::Immutable)?;
+            }
+
+            return Ok((arm, inner_scopes));
+        }
+    }
+
+    Err(ErrorKind::NonExhaustiveMatch {
+        missing: value.to_string(),
+    }
+    .into())
+}
+
+pub fn match_pattern(
+    scopes: &mut ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    pattern: &Pattern,
+    value: &Value,
+    bindings: &mut Vec<(String, Value)>,
+) -> Result<bool, RuntimeError> {
+    match &pattern.kind {
+        PatternKind::Wildcard => Ok(true),
+        PatternKind::Literal(expr) => Ok(&eval_expression(scopes, expr, prototypes)? == value),
+        PatternKind::Ident(name) => {
+            if let Value::Variant(enum_name, variant, _) = value {
+                if let Some(Value::Enum(_, variants)) = scopes.get(enum_name) {
+                    if variants.iter().any(|v| &v.name == name) {
+                        return Ok(variant == name);
+                    }
+                }
+            }
+
+            bindings.push((name.to_string(), value.clone()));
+            Ok(true)
+        }
+        PatternKind::Variant(enum_name, name, patterns) => match value {
+            Value::Variant(value_enum, variant, values) => {
+                if let Some(enum_name) = enum_name {
+                    if enum_name != value_enum {
+                        return Ok(false);
+                    }
+                }
+
+                if variant != name || values.len() != patterns.len() {
+                    return Ok(false);
+                }
+
+                for (pattern, value) in patterns.iter().zip(values) {
+                    if !match_pattern(scopes, prototypes, pattern, value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            }
+            _ => Ok(false),
+        },
+        PatternKind::Tuple(patterns) => match value {
+            Value::Tuple(values) if values.len() == patterns.len() => {
+                for (pattern, value) in patterns.iter().zip(values) {
+                    if !match_pattern(scopes, prototypes, pattern, value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            }
+            _ => Ok(false),
+        },
+    }
+}
+
 pub fn eval_tuple_expr(
     scopes: &mut ScopeStack,
     prototypes: &HashMap<String, HashMap<String, Value>>,
     exprs: &Vec<Expr>,
-) -> Result<Value, String> {
+) -> Result<Value, RuntimeError> {
     let mut values = Vec::new();
 
     for expr in exprs {
-        let value = eval_expression(scopes, expr, &prototypes)?;
+        let value = eval_expression(scopes, expr, prototypes)?;
         values.push(value);
     }
 
@@ -509,12 +1061,17 @@ pub fn eval_tuple_expr(
 pub fn eval_range_expr(
     scopes: &mut ScopeStack,
     prototypes: &HashMap<String, HashMap<String, Value>>,
-    start: &Box<Expr>,
-    end: &Box<Expr>,
-) -> Result<Value, String> {
-    let start = eval_expression(scopes, &start, prototypes)?;
-    let end = eval_expression(scopes, &end, prototypes)?;
+    start: &Expr,
+    end: &Expr,
+) -> Result<Value, RuntimeError> {
+    let start = eval_expression(scopes, start, prototypes)?;
+    let end = eval_expression(scopes, end, prototypes)?;
 
+    range_value(start, end)
+}
+
+/// the list of the ints from `start` to `end`, both included
+pub fn range_value(start: Value, end: Value) -> Result<Value, RuntimeError> {
     match start {
         Value::Int(s) => match end {
             Value::Int(e) => {
@@ -524,68 +1081,121 @@ pub fn eval_range_expr(
                     list.push(Value::Int(num));
                 }
 
-                return Ok(Value::List(list));
+                Ok(Value::list(list))
             }
-            other => {
-                return Err(format!(
-                    "extected integer, found {}",
-                    String::from(Type::from(&other))
-                ))
+            other => Err(ErrorKind::TypeMismatch {
+                expected: Type::Builtin(BuiltinType::Int),
+                found: Type::from(&other),
             }
+            .into()),
         },
-        other => {
-            return Err(format!(
-                "expected integer, found {}",
-                String::from(Type::from(&other))
-            ))
+        other => Err(ErrorKind::TypeMismatch {
+            expected: Type::Builtin(BuiltinType::Int),
+            found: Type::from(&other),
+        }
+        .into()),
+    }
+}
+
+pub fn eval_interpolation_expr(
+    scopes: &mut ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    fragments: &Vec<Fragment>,
+) -> Result<Value, RuntimeError> {
+    let mut values = vec![];
+    for fragment in fragments {
+        if let Fragment::Value(expr, _) = fragment {
+            values.push(eval_expression(scopes, expr, prototypes)?);
         }
     }
+
+    interpolate(fragments, values)
+}
+
+/// looks up `a::b::c` through modules already in scope, without loading files
+fn get_path(scopes: &mut ScopeStack, paths: &[String]) -> Option<Value> {
+    let (first, rest) = paths.split_first()?;
+    get_path_from(scopes.get(first), rest)
+}
+
+/// looks up the rest of a path from the value its first name has
+pub fn get_path_from(first: Option<Value>, rest: &[String]) -> Option<Value> {
+    let mut value = first?;
+
+    for path in rest {
+        value = match value {
+            Value::Module(items, _) => items.get(path)?.clone(),
+            _ => return None,
+        };
+    }
+
+    Some(value)
 }
 
+/// the exported items of the module at `paths` and the names of its private items.
+/// a module that isn't in scope is loaded from a file, `span` is where it is imported
 pub fn get_module(
     scopes: &mut ScopeStack,
-    paths: &Vec<String>,
-) -> Result<BTreeMap<String, Value>, String> {
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    paths: &[String],
+    span: Span,
+) -> Result<(BTreeMap<String, Value>, Vec<String>), RuntimeError> {
+    let first = scopes.get(&paths[0]);
+    get_module_from(scopes, prototypes, first, paths, span)
+}
+
+/// same as `get_module`, `first` is the value of the first name of the path
+pub fn get_module_from(
+    scopes: &mut ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    first: Option<Value>,
+    paths: &[String],
+    span: Span,
+) -> Result<(BTreeMap<String, Value>, Vec<String>), RuntimeError> {
     let mut exports: BTreeMap<String, Value> = BTreeMap::new();
+    let mut private: Vec<String> = vec![];
+    let mut first = first;
+
+    for (i, path) in paths.iter().enumerate() {
+        let value = match i {
+            0 => first.take(),
+            _ => exports.get(path).cloned(),
+        };
 
-    for path in paths {
-        match exports.get(path) {
-            Some(value) => match value.to_owned() {
-                Value::Module(items) => {
-                    exports = items;
+        match value {
+            Some(Value::Module(items, names)) => {
+                exports = items;
+                private = names;
+            }
+            Some(_) => {
+                return Err(ErrorKind::ModuleNotFound {
+                    path: path.to_string(),
                 }
-                _ => return Err(format!("module {} not found (1)", path)),
-            },
-            None => match scopes.get(path) {
-                Some(value) => match value {
-                    Value::Module(items) => {
-                        exports = items;
-                    }
-                    _ => return Err(format!("module {} not found (2)", path)),
-                },
+                .into())
+            }
+            None if private.contains(path) => {
+                return Err(ErrorKind::PrivateItem {
+                    name: path.to_string(),
+                }
+                .into())
+            }
+            None if i > 0 => {
+                return Err(ErrorKind::ModuleNotFound {
+                    path: path.to_string(),
+                }
+                .into())
+            }
+            None => match resolve_module(scopes, paths, span) {
+                Some(file) => return load_module(scopes, prototypes, &file),
                 None => {
-                    let mut path = String::new();
-                    path.push_str("./examples/");
-                    path.push_str(&paths.join("/"));
-                    path.push_str(".ak");
-
-                    let file_result = fs::read_to_string(path);
-
-                    if let Ok(file) = file_result {
-                        // scopes.push();
-                        let program = grammar::programParser::new().parse(&file).expect(&format!(
-                            "unable to compile module {}",
-                            paths.last().unwrap()
-                        ));
-                        eval_program_and_push_scope(scopes, program, &Prototypes::exports())?;
-                        break;
-                    } else {
-                        return Err(format!("module {} not found (3)", paths.last().unwrap()));
+                    return Err(ErrorKind::ModuleNotFound {
+                        path: paths.join("::"),
                     }
+                    .into())
                 }
             },
         }
     }
 
-    Ok(exports)
+    Ok((exports, private))
 }
@@ -1,5 +1,6 @@
 pub mod expression;
+pub mod module;
 pub mod program;
 pub mod statement;
 
-pub use program::eval_program;
+pub use program::run_program;
@@ -0,0 +1,175 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::ast::StatementKind;
+use crate::checker;
+use crate::diagnostic::Diagnostic;
+use crate::grammar;
+use crate::resolver;
+use crate::runtime::error::{ErrorKind, RuntimeError};
+use crate::runtime::value::Value;
+use crate::runtime::{Engine, ScopeStack};
+use crate::span::{Source, Span};
+
+use super::program::run_program;
+
+/// finds the file of the module `a::b` (`a/b.ak`) imported at `from`. it is looked up next to
+/// the importing file, then in every directory of `BETADIN_PATH`, then in the project root,
+/// the directory of the main script
+pub fn resolve_module(scopes: &ScopeStack, paths: &[String], from: Span) -> Option<PathBuf> {
+    let root = scopes.modules().root.clone();
+    find_module(root.as_deref(), paths, from)
+}
+
+/// same as `resolve_module`, with the project root given
+pub(crate) fn find_module(root: Option<&Path>, paths: &[String], from: Span) -> Option<PathBuf> {
+    let mut file = PathBuf::from_iter(paths);
+    file.set_extension("ak");
+
+    let mut dirs = vec![];
+
+    if let Some(source) = Source::get(from.source) {
+        dirs.push(parent_dir(&source.path));
+    }
+
+    if let Some(paths) = env::var_os("BETADIN_PATH") {
+        dirs.extend(env::split_paths(&paths));
+    }
+
+    if let Some(root) = root {
+        dirs.push(root.to_path_buf());
+    }
+
+    dirs.into_iter()
+        .map(|dir| dir.join(&file))
+        .find(|path| path.is_file())
+}
+
+/// file modules loaded by an interpreter, keyed by their canonical path, and the rest of
+/// the state every file of the interpreter shares
+#[derive(Default)]
+pub struct ModuleRegistry {
+    loaded: HashMap<PathBuf, (BTreeMap<String, Value>, Vec<String>)>,
+    // files being evaluated, outermost first, with the path they were found at
+    loading: Vec<(PathBuf, String)>,
+    // the imported files run with the engine of the importer
+    pub engine: Engine,
+    // the code of the imported files, their functions can fail after the import
+    sources: Vec<Arc<Source>>,
+    // the directory of the main script
+    pub root: Option<PathBuf>,
+    // methods of `impl` blocks by the prototype of their type, with where they were declared.
+    // they belong to the type, so they are found from every file its values go to
+    pub methods: HashMap<String, HashMap<String, (Value, Span)>>,
+    // the traits each prototype implements, with where the `impl` block is
+    pub impls: HashMap<String, HashMap<String, Span>>,
+}
+
+/// the exported items and the names of the private ones of the file at `path`.
+/// a file is evaluated once, later imports share its items
+pub fn load_module(
+    scopes: &ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    path: &Path,
+) -> Result<(BTreeMap<String, Value>, Vec<String>), RuntimeError> {
+    let key = fs::canonicalize(path)?;
+    let name = path.to_string_lossy().to_string();
+
+    {
+        let mut registry = scopes.modules();
+
+        if let Some(module) = registry.loaded.get(&key) {
+            return Ok(module.clone());
+        }
+
+        if let Some(start) = registry.loading.iter().position(|(p, _)| p == &key) {
+            let mut chain: Vec<String> = registry.loading[start..]
+                .iter()
+                .map(|(_, name)| name.to_string())
+                .collect();
+            chain.push(name);
+
+            return Err(ErrorKind::CircularImport { chain }.into());
+        }
+
+        registry.loading.push((key.clone(), name));
+    }
+
+    // the registry is unlocked while the file runs, it can import other files
+    let res = eval_file(scopes, prototypes, path);
+
+    let mut registry = scopes.modules();
+    registry.loading.pop();
+
+    let module = res?;
+    registry.loaded.insert(key, module.clone());
+    Ok(module)
+}
+
+/// parses, checks, resolves and runs the file at `path` in a scope of its own
+fn eval_file(
+    scopes: &ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    path: &Path,
+) -> Result<(BTreeMap<String, Value>, Vec<String>), RuntimeError> {
+    let code = fs::read_to_string(path)?;
+    let source = Source::add(&path.to_string_lossy(), &code);
+    scopes.modules().sources.push(source.clone());
+
+    let mut program = grammar::programParser::new()
+        .parse(source.id, 0, &code)
+        .map_err(|e| Diagnostic::from_parse_error(source.id, e))?;
+
+    let root = scopes.modules().root.clone();
+    let errors = checker::check(&mut program, root.as_deref());
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    // only the builtins are shared with the importer
+    let mut module_scopes = scopes.global();
+    module_scopes.push(HashMap::new());
+
+    let errors = resolver::resolve(&mut program, &module_scopes);
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    let mut items: Vec<(String, bool)> = vec![];
+    for statement in &program.statements {
+        let (kind, exported) = match &statement.kind {
+            StatementKind::Export(inner) => (&inner.kind, true),
+            kind => (kind, false),
+        };
+
+        if let Some(name) = kind.declared_name() {
+            items.push((name.to_string(), exported));
+        }
+    }
+
+    run_program(&mut module_scopes, &program, prototypes)?;
+
+    let mut exports: BTreeMap<String, Value> = BTreeMap::new();
+    let mut private: Vec<String> = vec![];
+
+    for (name, exported) in items {
+        match module_scopes.get(&name) {
+            Some(value) if exported => {
+                exports.insert(name, value);
+            }
+            _ => private.push(name),
+        }
+    }
+
+    Ok((exports, private))
+}
+
+fn parent_dir(path: &str) -> PathBuf {
+    Path::new(path)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
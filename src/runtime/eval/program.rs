@@ -1,51 +1,53 @@
 use std::collections::HashMap;
 
-use crate::ast::Program;
+use crate::ast::{Program, StatementKind};
+use crate::runtime::error::{ErrorKind, RuntimeError};
 use crate::runtime::value::Value;
-use crate::runtime::ScopeStack;
+use crate::runtime::{vm, Engine, ScopeStack};
 
-use super::statement::{eval_statements, eval_statements_and_push_scope, Escape};
+use super::expression::eval_expression;
+use super::statement::{eval_statement, Escape};
 
-pub fn eval_program(
+/// evaluates `program` in the innermost scope of `scopes`, so its declarations outlive it.
+/// returns the value of the last statement when it is an expression
+pub fn eval_program_in_scope(
     scopes: &mut ScopeStack,
-    program: Program,
+    program: &Program,
     prototypes: &HashMap<String, HashMap<String, Value>>,
-) -> Result<Escape, String> {
-    let e = eval_statements(scopes, &program.statements, prototypes)?;
-
-    if let Escape::Return(_) = e {
-        return Err(format!("return outside of function"));
-    }
-
-    if let Escape::Break = e {
-        return Err(format!("break outside of loop"));
+) -> Result<Option<Value>, RuntimeError> {
+    let mut value = None;
+
+    for statement in &program.statements {
+        if let StatementKind::Expression(expr) = &statement.kind {
+            value = Some(eval_expression(scopes, expr, prototypes)?);
+            continue;
+        }
+        value = None;
+
+        let keyword = match eval_statement(scopes, statement, prototypes)? {
+            Escape::None => continue,
+            Escape::Return(_) => "return",
+            Escape::Break => "break",
+            Escape::Continue => "continue",
+        };
+
+        return Err(RuntimeError::new(ErrorKind::InvalidControlFlow {
+            keyword: keyword.to_string(),
+        })
+        .with_span(statement.span));
     }
 
-    if let Escape::Continue = e {
-        return Err(format!("continue outside of loop"));
-    }
-
-    Ok(e)
+    Ok(value)
 }
 
-pub fn eval_program_and_push_scope(
+/// runs `program` in the innermost scope of `scopes` with the engine of the interpreter
+pub fn run_program(
     scopes: &mut ScopeStack,
-    program: Program,
+    program: &Program,
     prototypes: &HashMap<String, HashMap<String, Value>>,
-) -> Result<Escape, String> {
-    let e = eval_statements_and_push_scope(scopes, &program.statements, prototypes)?;
-
-    if let Escape::Return(_) = e {
-        return Err(format!("return outside of function"));
+) -> Result<Option<Value>, RuntimeError> {
+    match scopes.engine() {
+        Engine::Vm => vm::run(scopes, program, prototypes),
+        Engine::TreeWalker => eval_program_in_scope(scopes, program, prototypes),
     }
-
-    if let Escape::Break = e {
-        return Err(format!("break outside of loop"));
-    }
-
-    if let Escape::Continue = e {
-        return Err(format!("continue outside of loop"));
-    }
-
-    Ok(e)
 }
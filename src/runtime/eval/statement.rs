@@ -1,10 +1,14 @@
 use std::collections::{BTreeMap, HashMap};
 
-use crate::ast::Statement;
-use crate::runtime::value::{BuiltinType, Value};
+use crate::ast::{Access, ArmBody, LValue, Statement, StatementKind};
+use crate::runtime::error::{ErrorKind, RuntimeError};
+use crate::runtime::prototypes::object::check_key;
+use crate::runtime::value::{check_list_items, BuiltinType, KeyValue, Value};
 use crate::runtime::{DeclType, ScopeStack, Simple, Type};
 
-use super::expression::{eval_expression, get_module};
+use super::expression::{
+    binary_value, eval_expression, eval_ident_expr, find_arm, get_member, get_module, index_value,
+};
 
 #[derive(Debug, Clone)]
 pub enum Escape {
@@ -18,12 +22,22 @@ pub fn eval_statement(
     scopes: &mut ScopeStack,
     statement: &Statement,
     prototypes: &HashMap<String, HashMap<String, Value>>,
-) -> Result<Escape, String> {
-    match statement {
-        Statement::Expression(expr) => {
+) -> Result<Escape, RuntimeError> {
+    let res = eval_statement_kind(scopes, statement, prototypes);
+
+    res.map_err(|e| e.with_span(statement.span))
+}
+
+fn eval_statement_kind(
+    scopes: &mut ScopeStack,
+    statement: &Statement,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+) -> Result<Escape, RuntimeError> {
+    match &statement.kind {
+        StatementKind::Expression(expr) => {
             eval_expression(scopes, expr, prototypes)?;
         }
-        Statement::Let(name, datatype, rhs) => {
+        StatementKind::Let(name, datatype, rhs) => {
             let value = eval_expression(scopes, rhs, prototypes)?;
 
             if let Some(datatype) = datatype {
@@ -32,7 +46,7 @@ pub fn eval_statement(
                 scopes.declare_variable(name, &Type::from(&value), &value, DeclType::Mutable)?;
             }
         }
-        Statement::Const(name, datatype, rhs) => {
+        StatementKind::Const(name, datatype, rhs) => {
             let value = eval_expression(scopes, rhs, prototypes)?;
 
             if let Some(datatype) = datatype {
@@ -41,39 +55,61 @@ pub fn eval_statement(
                 scopes.declare_variable(name, &Type::from(&value), &value, DeclType::Immutable)?;
             }
         }
-        Statement::Import(args, items) => {
-            let module = get_module(scopes, args)?;
+        StatementKind::Import(args, items) => {
+            let (module, private) = get_module(scopes, prototypes, args, statement.span)?;
 
             match items {
                 Some(list) => {
+                    if let Some(name) = list.iter().find(|name| private.contains(name)) {
+                        return Err(ErrorKind::PrivateItem {
+                            name: name.to_string(),
+                        }
+                        .into());
+                    }
+
                     for (key, value) in module {
                         if list.contains(&key) {
-                            scopes.declare(
-                                &key,
-                                value.clone(),
-                                &Some(Type::from(&value)),
-                                DeclType::Immutable,
-                            )?;
+                            scopes.declare(&key, value.clone(), &None, DeclType::Immutable)?;
                         }
                     }
                 }
                 None => {
                     if let Some(m) = args.last() {
-                        scopes.declare(
-                            m,
-                            Value::Module(module),
-                            &Some(Type::Alias("todo".to_string())),
-                            DeclType::Immutable,
-                        )?;
+                        let module = Value::Module(module, private);
+                        scopes.declare(m, module, &None, DeclType::Immutable)?;
                     }
                 }
             }
         }
-        Statement::Assignment(name, rhs) => {
+        StatementKind::Assignment(lvalue, rhs) => {
             let value = eval_expression(scopes, rhs, prototypes)?;
-            scopes.assgin(name.to_string(), value)?;
+
+            if lvalue.path.is_empty() {
+                scopes.assgin(lvalue.name.to_string(), value)?;
+                return Ok(Escape::None);
+            }
+
+            let target = eval_ident_expr(scopes, &lvalue.name)?;
+            let path = eval_path(scopes, prototypes, lvalue)?;
+
+            if let Some(target) = assign_path(scopes, prototypes, target, &path, value)? {
+                scopes.assgin(lvalue.name.to_string(), target)?;
+            }
+        }
+        StatementKind::CompoundAssignment(lvalue, op, rhs) => {
+            // the indexes are evaluated once, before the right side
+            let target = eval_ident_expr(scopes, &lvalue.name)?;
+            let path = eval_path(scopes, prototypes, lvalue)?;
+            let current = read_path(scopes, prototypes, target.clone(), &path)?;
+
+            let rhs = eval_expression(scopes, rhs, prototypes)?;
+            let value = binary_value(op, current, rhs)?;
+
+            if let Some(target) = assign_path(scopes, prototypes, target, &path, value)? {
+                scopes.assgin(lvalue.name.to_string(), target)?;
+            }
         }
-        Statement::If(branchs, else_block) => {
+        StatementKind::If(branchs, else_block) => {
             for branch in branchs {
                 let value = eval_expression(scopes, &branch.condition, prototypes)?;
 
@@ -84,7 +120,13 @@ pub fn eval_statement(
                             return Ok(ret);
                         }
                     }
-                    _ => return Err(format!("condition most be a boolean")),
+                    other => {
+                        return Err(RuntimeError::new(ErrorKind::TypeMismatch {
+                            expected: Type::Builtin(BuiltinType::Bool),
+                            found: Type::from(&other),
+                        })
+                        .with_span(branch.condition.span))
+                    }
                 }
             }
 
@@ -93,65 +135,58 @@ pub fn eval_statement(
                 return Ok(e);
             }
         }
-        Statement::Return(expr) => {
+        StatementKind::Return(expr) => {
             let value = eval_expression(scopes, expr, prototypes)?;
             return Ok(Escape::Return(value));
         }
-        Statement::Fn(name, args, ret_type, block) => {
-            if let Some(ret_type) = ret_type {
-                scopes.declare_fn_statement(name, args, ret_type, block)?;
-            } else {
-                scopes.declare_fn_statement(
-                    name,
-                    args,
-                    &Type::Builtin(BuiltinType::Null),
-                    block,
-                )?;
-            }
+        StatementKind::Fn(name, args, ret_type, block) => {
+            scopes.declare_fn_statement(name, args, ret_type, block)?;
         }
-        Statement::For(lhs, iter, block) => {
+        StatementKind::For(lhs, iter, block) => {
             let iter_val = eval_expression(scopes, iter, prototypes)?;
 
-            match &iter_val {
-                Value::List(values) | Value::Tuple(values) => {
-                    for (i, value) in values.iter().enumerate() {
-                        let mut inner_scopes = scopes.new_from_push(HashMap::new());
-
-                        inner_scopes.declare(
-                            lhs,
-                            value.clone(),
-                            &Some(Type::from(value)),
-                            DeclType::Mutable,
-                        )?;
-
-                        inner_scopes.declare(
-                            &"index".to_string(),
-                            Value::Int(i as i32),
-                            &Some(Type::Builtin(BuiltinType::Int)),
-                            DeclType::Immutable,
-                        )?;
-
-                        let ret = eval_statements(&mut inner_scopes, block, prototypes)?;
-
-                        match ret {
-                            Escape::None => {}
-                            Escape::Continue => {}
-                            Escape::Return(v) => return Ok(Escape::Return(v)),
-                            Escape::Break => return Ok(Escape::None),
-                        }
-                    }
-                }
+            let items = match &iter_val {
+                // the loop goes over the items the list had when it started
+                Value::List(values, _) => values.get(),
+                Value::Tuple(values) => values.to_vec(),
                 _ => {
-                    return Err(format!(
-                        "iterator most be a list, found {}",
-                        Type::simple(&iter_val)
-                    ))
+                    return Err(RuntimeError::new(ErrorKind::NotIterable {
+                        datatype: Type::simple(&iter_val),
+                    })
+                    .with_span(iter.span))
+                }
+            };
+
+            for (i, value) in items.iter().enumerate() {
+                let mut inner_scopes = scopes.new_from_push(HashMap::new());
+
+                inner_scopes.declare(
+                    lhs,
+                    value.clone(),
+                    &Some(Type::from(value)),
+                    DeclType::Mutable,
+                )?;
+
+                inner_scopes.declare(
+                    &"index".to_string(),
+                    Value::Int(i as i32),
+                    &Some(Type::Builtin(BuiltinType::Int)),
+                    DeclType::Immutable,
+                )?;
+
+                let ret = eval_statements(&mut inner_scopes, block, prototypes)?;
+
+                match ret {
+                    Escape::None => {}
+                    Escape::Continue => {}
+                    Escape::Return(v) => return Ok(Escape::Return(v)),
+                    Escape::Break => return Ok(Escape::None),
                 }
             }
         }
-        Statement::Break => return Ok(Escape::Break),
-        Statement::Continue => return Ok(Escape::Continue),
-        Statement::While(cond, block) => loop {
+        StatementKind::Break => return Ok(Escape::Break),
+        StatementKind::Continue => return Ok(Escape::Continue),
+        StatementKind::While(cond, block) => loop {
             let value = eval_expression(scopes, cond, prototypes)?;
 
             match value {
@@ -169,62 +204,255 @@ pub fn eval_statement(
                         Escape::Break => return Ok(Escape::None),
                     }
                 }
-                _ => return Err(format!("condition most be a boolean")),
+                other => {
+                    return Err(RuntimeError::new(ErrorKind::TypeMismatch {
+                        expected: Type::Builtin(BuiltinType::Bool),
+                        found: Type::from(&other),
+                    })
+                    .with_span(cond.span))
+                }
             }
         },
-        Statement::Module(name, statements) => {
+        StatementKind::Module(name, statements) => {
             let module = eval_module(scopes, prototypes, name, statements)?;
 
-            scopes.declare(
-                name,
-                Value::Module(module),
-                &Some(Type::Alias("todo".to_string())),
-                DeclType::Immutable,
-            )?;
+            scopes.declare(name, module, &None, DeclType::Immutable)?;
         }
-        Statement::Type(name, datatype) => {
+        StatementKind::Export(statement) => {
+            return eval_statement(scopes, statement, prototypes);
+        }
+        StatementKind::Type(name, datatype) => {
             scopes.declare_type_alias(name, datatype)?;
         }
+        StatementKind::Struct(name, fields) => {
+            scopes.declare_struct(name, fields)?;
+        }
+        StatementKind::Enum(name, variants) => {
+            scopes.declare_enum(name, variants)?;
+        }
+        StatementKind::Trait(name, methods) => {
+            scopes.declare_trait(name, methods)?;
+        }
+        StatementKind::Impl(datatype, trait_name, methods) => {
+            let prototype = scopes
+                .get_type_alias(&Type::from(datatype.to_string()))?
+                .prototype();
+
+            if let Some(trait_name) = trait_name {
+                scopes.declare_impl(&prototype, trait_name, methods, statement.span)?;
+            }
+
+            for method in methods {
+                let value = Value::Func(
+                    method.args.clone(),
+                    method.ret_type.clone(),
+                    method.block.clone(),
+                    scopes.clone(),
+                    Some(method.name.to_string()),
+                );
+                scopes.declare_method(prototypes, &prototype, method, value)?;
+            }
+        }
+        StatementKind::Match(expr, arms) => {
+            let value = eval_expression(scopes, expr, prototypes)?;
+            let (arm, mut inner_scopes) = find_arm(scopes, prototypes, &value, arms)?;
+
+            match &arm.body {
+                ArmBody::Expr(expr) => {
+                    eval_expression(&mut inner_scopes, expr, prototypes)?;
+                }
+                ArmBody::Block(block) => {
+                    return eval_statements(&mut inner_scopes, block, prototypes);
+                }
+            }
+        }
     };
 
     Ok(Escape::None)
 }
 
-pub fn eval_statements(
+/// an item or a property an assignment writes through, indexes are already evaluated
+#[derive(Debug, Clone)]
+pub enum Step {
+    Index(Value),
+    Property(String),
+}
+
+/// the steps of the path of `lvalue`, its indexes evaluated
+fn eval_path(
     scopes: &mut ScopeStack,
-    statements: &Vec<Statement>,
     prototypes: &HashMap<String, HashMap<String, Value>>,
-) -> Result<Escape, String> {
-    let mut inner_scopes = scopes.new_from_push(HashMap::new());
+    lvalue: &LValue,
+) -> Result<Vec<Step>, RuntimeError> {
+    let mut path = vec![];
+    for access in &lvalue.path {
+        path.push(match access {
+            Access::Index(expr) => Step::Index(eval_expression(scopes, expr, prototypes)?),
+            Access::Property(name) => Step::Property(name.to_string()),
+        });
+    }
+    Ok(path)
+}
 
-    for statement in statements {
-        let e = eval_statement(&mut inner_scopes, statement, prototypes)?;
+/// the value `path` leads to from `target`
+pub fn read_path(
+    scopes: &ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    target: Value,
+    path: &[Step],
+) -> Result<Value, RuntimeError> {
+    let mut value = target;
+    for step in path {
+        value = match step {
+            Step::Index(index) => index_value(&value, index.clone())?,
+            Step::Property(name) => get_member(scopes, prototypes, value, name)?,
+        };
+    }
+    Ok(value)
+}
 
-        if let Statement::Fn(..) = statement {
-            continue;
-        }
+/// writes `value` through `path` into `target`. lists and objects are changed in place,
+/// instances are values so the updated `target` is returned when it has to be stored back
+pub fn assign_path(
+    scopes: &ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    target: Value,
+    path: &[Step],
+    value: Value,
+) -> Result<Option<Value>, RuntimeError> {
+    let (step, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return Ok(Some(value)),
+    };
 
-        if let Escape::None = e {
-            continue;
-        }
+    if rest.is_empty() {
+        return write_step(scopes, prototypes, target, step, value);
+    }
 
-        return Ok(e);
+    let item = match step {
+        Step::Index(index) => index_value(&target, index.clone())?,
+        Step::Property(name) => get_member(scopes, prototypes, target.clone(), name)?,
+    };
+
+    match assign_path(scopes, prototypes, item, rest, value)? {
+        Some(item) => write_step(scopes, prototypes, target, step, item),
+        None => Ok(None),
     }
+}
 
-    Ok(Escape::None)
+fn write_step(
+    scopes: &ScopeStack,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    target: Value,
+    step: &Step,
+    value: Value,
+) -> Result<Option<Value>, RuntimeError> {
+    match (target, step) {
+        (Value::List(list, item_type), Step::Index(index)) => {
+            let index = match index {
+                Value::Int(index) => *index,
+                other => {
+                    return Err(ErrorKind::TypeMismatch {
+                        expected: Type::Builtin(BuiltinType::Int),
+                        found: Type::from(other),
+                    }
+                    .into())
+                }
+            };
+
+            if let Value::List(items, _) = &value {
+                check_list_items(&items.lock())?;
+            }
+
+            let mut items = list.lock();
+            let len = items.len();
+            if index < 0 || index as usize >= len {
+                return Err(ErrorKind::IndexOutOfBounds { index, len }.into());
+            }
+
+            // the item keeps the type of the list
+            if let Some(expected) = item_type.get() {
+                if expected != &Type::from(&value) {
+                    return Err(ErrorKind::TypeMismatch {
+                        expected: expected.clone(),
+                        found: Type::from(&value),
+                    }
+                    .into());
+                }
+            }
+
+            items[index as usize] = value;
+            Ok(None)
+        }
+        (Value::Object(props), Step::Property(name)) => {
+            check_key(prototypes, name)?;
+
+            let mut props = props.lock();
+            match props.iter_mut().find(|prop| &prop.key == name) {
+                Some(prop) => prop.value = value,
+                // new keys come first, like `object.set`
+                None => props.insert(
+                    0,
+                    KeyValue {
+                        key: name.to_string(),
+                        value,
+                    },
+                ),
+            }
+            Ok(None)
+        }
+        (Value::Instance(datatype, mut fields), Step::Property(name)) => {
+            let field = match fields.iter_mut().find(|field| &field.key == name) {
+                Some(field) => field,
+                None => {
+                    return Err(ErrorKind::UndefinedProperty {
+                        name: name.to_string(),
+                        prototype: datatype,
+                    }
+                    .into())
+                }
+            };
+
+            // the declared type of the field, or the type of its value when the struct isn't in scope
+            let expected = match scopes.get(&datatype) {
+                Some(Value::Struct(_, declared)) => match declared.iter().find(|f| &f.name == name)
+                {
+                    Some(declared) => scopes.get_type_alias(&declared.datatype)?,
+                    None => Type::from(&field.value),
+                },
+                _ => Type::from(&field.value),
+            };
+            let found = scopes.type_of(&value)?;
+            if expected != found {
+                return Err(ErrorKind::TypeMismatch { expected, found }.into());
+            }
+
+            field.value = value;
+            Ok(Some(Value::Instance(datatype, fields)))
+        }
+        (target @ (Value::String(_) | Value::Tuple(_)), Step::Index(_))
+        | (target, Step::Property(_)) => Err(ErrorKind::NotAssignable {
+            datatype: Type::simple(&target),
+        }
+        .into()),
+        (target, Step::Index(_)) => Err(ErrorKind::NotIndexable {
+            datatype: Type::simple(&target),
+        }
+        .into()),
+    }
 }
 
-pub fn eval_statements_and_push_scope(
+pub fn eval_statements(
     scopes: &mut ScopeStack,
     statements: &Vec<Statement>,
     prototypes: &HashMap<String, HashMap<String, Value>>,
-) -> Result<Escape, String> {
-    scopes.push(HashMap::new());
+) -> Result<Escape, RuntimeError> {
+    let mut inner_scopes = scopes.new_from_push(HashMap::new());
 
     for statement in statements {
-        let e = eval_statement(scopes, statement, prototypes)?;
+        let e = eval_statement(&mut inner_scopes, statement, prototypes)?;
 
-        if let Statement::Fn(..) = statement {
+        if let StatementKind::Fn(..) = statement.kind {
             continue;
         }
 
@@ -238,66 +466,100 @@ pub fn eval_statements_and_push_scope(
     Ok(Escape::None)
 }
 
+/// evaluates a `module` block into a `Value::Module`. every item is visible by name inside
+/// the module, only the ones marked with `export` can be reached from outside
 pub fn eval_module(
     scopes: &mut ScopeStack,
     prototypes: &HashMap<String, HashMap<String, Value>>,
     name: &String,
     statements: &Vec<Statement>,
-) -> Result<BTreeMap<String, Value>, String> {
+) -> Result<Value, RuntimeError> {
     let mut exports: BTreeMap<String, Value> = BTreeMap::new();
+    let mut items: BTreeMap<String, Value> = BTreeMap::new();
+    let mut private: Vec<String> = vec![];
 
     let mut inner_scope = scopes.new_from_push(HashMap::new());
     for statement in statements {
-        match statement {
-            Statement::Const(name, datatype, expr) => {
+        let (statement, exported) = match &statement.kind {
+            StatementKind::Export(inner) => (inner.as_ref(), true),
+            _ => (statement, false),
+        };
+
+        let item = match &statement.kind {
+            StatementKind::Const(name, datatype, expr) => {
                 let value = eval_expression(&mut inner_scope, expr, prototypes)?;
+                let datatype = datatype.clone().unwrap_or(Type::from(&value));
 
-                // type checking
-                if let Some(datatype) = datatype {
-                    if &Type::from(&value) != datatype {
-                        return Err(format!(
-                            "expected {} found {}",
-                            datatype,
-                            Type::from(&value),
-                        ));
-                    }
-                }
-                exports.insert(name.to_string(), value);
+                inner_scope
+                    .declare_variable(name, &datatype, &value, DeclType::Immutable)
+                    .map_err(|e| e.with_span(expr.span))?;
+                name
             }
-            Statement::Let(name, datatype, expr) => {
+            StatementKind::Let(name, datatype, expr) => {
                 let value = eval_expression(&mut inner_scope, expr, prototypes)?;
+                let datatype = datatype.clone().unwrap_or(Type::from(&value));
 
-                // type checking
-                if let Some(datatype) = datatype {
-                    if &Type::from(&value) != datatype {
-                        return Err(format!(
-                            "expected {} found {}",
-                            datatype,
-                            Type::from(&value),
-                        ));
-                    }
-                }
-                exports.insert(name.to_string(), value);
+                inner_scope
+                    .declare_variable(name, &datatype, &value, DeclType::Mutable)
+                    .map_err(|e| e.with_span(expr.span))?;
+                name
             }
-            Statement::Fn(name, args, ret_type, block) => {
-                exports.insert(
-                    name.to_string(),
-                    Value::Func(args.to_vec(), ret_type.clone(), block.to_vec()),
-                );
+            StatementKind::Fn(name, args, ret_type, block) => {
+                inner_scope.declare_fn_statement(name, args, ret_type, block)?;
+                name
+            }
+            StatementKind::Module(name, statements) => {
+                let module = eval_module(&mut inner_scope, prototypes, name, statements)?;
+                inner_scope.declare(name, module, &None, DeclType::Immutable)?;
+                name
             }
-            Statement::Module(name2, statements2) => {
-                let exports2 = eval_module(&mut inner_scope, prototypes, name2, statements2)?;
-                exports.insert(name2.to_string(), Value::Module(exports2));
+            StatementKind::Enum(name, variants) => {
+                inner_scope.declare_enum(name, variants)?;
+                name
             }
-            other => return Err(format!("'{:?}' is not supported in modules", other)),
+            StatementKind::Struct(name, fields) => {
+                inner_scope.declare_struct(name, fields)?;
+                name
+            }
+            StatementKind::Trait(name, methods) => {
+                inner_scope.declare_trait(name, methods)?;
+                name
+            }
+            StatementKind::Type(name, datatype) => {
+                inner_scope.declare_type_alias(name, datatype)?;
+                name
+            }
+            // the methods go to the type, the block itself is no item of the module
+            StatementKind::Impl(..) => {
+                eval_statement(&mut inner_scope, statement, prototypes)?;
+                continue;
+            }
+            other => {
+                return Err(RuntimeError::new(ErrorKind::NotSupportedInModule {
+                    statement: other.describe().to_string(),
+                })
+                .with_span(statement.span))
+            }
+        };
+
+        let value = inner_scope
+            .get(item)
+            .expect("module item should be declared in the module scope");
+
+        if exported {
+            exports.insert(item.to_string(), value.clone());
+        } else {
+            private.push(item.to_string());
         }
+        items.insert(item.to_string(), value);
     }
 
+    // inside the module its own path reaches the private items too
     inner_scope.declare(
         name,
-        Value::Module(exports.clone()),
-        &Some(Type::Alias("module".to_string())),
+        Value::Module(items, vec![]),
+        &None,
         DeclType::Immutable,
     )?;
-    Ok(exports)
+    Ok(Value::Module(exports, private))
 }
@@ -8,6 +8,12 @@ pub mod std;
 
 pub struct StdLib(HashMap<String, (Value, DeclType, Type)>);
 
+impl Default for StdLib {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl StdLib {
     pub fn new() -> Self {
         Self(HashMap::new())
@@ -19,28 +25,28 @@ impl StdLib {
         // builtin modules
         lib.declare(
             "std",
-            Value::Module(Std::exports()),
+            Value::Module(Std::exports(), vec![]),
             Type::Alias("module".to_string()),
         );
 
         // builtin functions
         lib.declare(
             "print",
-            Value::BuiltInFn(ak_lib::print),
+            Value::native(ak_lib::print),
             Type::Alias("function".to_string()),
         );
         lib.declare(
             "println",
-            Value::BuiltInFn(ak_lib::println),
+            Value::native(ak_lib::println),
             Type::Alias("function".to_string()),
         );
         lib.declare(
             "panic",
-            Value::BuiltInFn(ak_lib::panic),
+            Value::native(ak_lib::panic),
             Type::Alias("function".to_string()),
         );
 
-        return lib.items();
+        lib.items()
     }
 
     pub fn declare(&mut self, name: &str, value: Value, datatype: Type) {
@@ -49,51 +55,77 @@ impl StdLib {
     }
 
     fn items(self) -> HashMap<String, (Value, DeclType, Type)> {
-        return self.0;
+        self.0
     }
 }
 
 mod ak_lib {
+    use crate::runtime::error::{ErrorKind, RuntimeError};
     use crate::runtime::value::Value;
 
-    pub fn print(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 1 || vs.len() < 1 {
-            return Err(format!("expected 1 argument, but found {}", vs.len()));
+    pub fn print(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if vs.len() > 1 || vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into());
         }
 
-        match vs.get(0) {
+        match vs.first() {
             Some(value) => {
                 print!("{}", value);
-                return Ok(Value::Null);
+                Ok(Value::Null)
+            }
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
             }
-            None => return Err(format!("expected 1 argument, but found {}", vs.len())),
+            .into()),
         }
     }
 
-    pub fn println(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 1 || vs.len() < 1 {
-            return Err(format!("expected 1 argument, but found {}", vs.len()));
+    pub fn println(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if vs.len() > 1 || vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into());
         }
 
-        match vs.get(0) {
+        match vs.first() {
             Some(value) => {
                 println!("{}", value);
-                return Ok(Value::Null);
+                Ok(Value::Null)
+            }
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
             }
-            None => return Err(format!("expected 1 argument, but found {}", vs.len())),
+            .into()),
         }
     }
 
-    pub fn panic(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 1 || vs.len() < 1 {
-            return Err(format!("expected 1 argument, but found {}", vs.len()));
+    pub fn panic(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if vs.len() > 1 || vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into());
         }
 
-        match vs.get(0) {
-            Some(value) => {
-                return Err(format!("{}", value));
+        match vs.first() {
+            Some(value) => Err(ErrorKind::Panic {
+                message: value.to_string(),
+            }
+            .into()),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
             }
-            None => return Err(format!("expected 1 argument, but found {}", vs.len())),
+            .into()),
         }
     }
 }
@@ -4,6 +4,12 @@ use crate::runtime::value::Value;
 
 pub struct Collections(BTreeMap<String, Value>);
 
+impl Default for Collections {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Collections {
     pub fn new() -> Self {
         Self(BTreeMap::new())
@@ -13,9 +19,9 @@ impl Collections {
         let mut std = Collections::new();
 
         // collections functions
-        std.declare("set", Value::BuiltInFn(ak_collections::set));
+        std.declare("set", Value::native(ak_collections::set));
 
-        return std.items();
+        std.items()
     }
 
     pub fn declare(&mut self, name: &str, value: Value) {
@@ -23,31 +29,42 @@ impl Collections {
     }
 
     fn items(self) -> BTreeMap<String, Value> {
-        return self.0;
+        self.0
     }
 }
 
 mod ak_collections {
+    use crate::runtime::error::{ErrorKind, RuntimeError};
     use crate::runtime::value::Value;
+    use crate::runtime::Type;
 
-    pub fn set(vs: Vec<Value>) -> Result<Value, String> {
-        match vs.get(0) {
+    pub fn set(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        match vs.first() {
             Some(value) => {
-                if let Value::List(list) = value {
+                if let Value::List(list, _) = value {
                     let mut set = Vec::new();
-                    let list = list.to_vec();
+                    let list = list.get();
                     for val in list {
                         if !set.contains(&val) {
                             set.push(val);
                         }
                     }
 
-                    return Ok(Value::List(set));
+                    Ok(Value::list(set))
                 } else {
-                    return Err(format!("the first argument most be a list"));
+                    Err(ErrorKind::InvalidArgument {
+                        position: 1,
+                        expected: Type::Alias("list".to_string()),
+                        found: Type::from(value),
+                    }
+                    .into())
                 }
             }
-            None => Err(format!("the first argument is required")),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: 0,
+            }
+            .into()),
         }
     }
 }
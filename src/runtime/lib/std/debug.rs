@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+
+use crate::runtime::value::Value;
+
+pub struct Debug(BTreeMap<String, Value>);
+
+impl Default for Debug {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug {
+    pub fn new() -> Self {
+        Self(BTreeMap::new())
+    }
+
+    pub fn exports() -> BTreeMap<String, Value> {
+        let mut debug = Debug::new();
+
+        // debug functions
+        debug.declare("backtrace", Value::native(ak_debug::backtrace));
+
+        debug.items()
+    }
+
+    pub fn declare(&mut self, name: &str, value: Value) {
+        self.0.insert(String::from(name), value);
+    }
+
+    fn items(self) -> BTreeMap<String, Value> {
+        self.0
+    }
+}
+
+mod ak_debug {
+    use crate::runtime::error::{ErrorKind, RuntimeError};
+    use crate::runtime::trace;
+    use crate::runtime::value::{KeyValue, Value};
+
+    /// returns the current call stack as a list of objects, innermost frame first
+    pub fn backtrace(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if !vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 0,
+                found: vs.len(),
+            }
+            .into());
+        }
+
+        let mut frames = vec![];
+
+        for (name, span) in trace::resolve(&trace::capture(), None) {
+            let mut frame = vec![KeyValue {
+                key: String::from("function"),
+                value: Value::String(name),
+            }];
+
+            if let Some((path, line, col)) = span.and_then(|span| span.position()) {
+                frame.push(KeyValue {
+                    key: String::from("file"),
+                    value: Value::String(path),
+                });
+                frame.push(KeyValue {
+                    key: String::from("line"),
+                    value: Value::Int(line as i32),
+                });
+                frame.push(KeyValue {
+                    key: String::from("column"),
+                    value: Value::Int(col as i32),
+                });
+            }
+
+            frames.push(Value::object(frame));
+        }
+
+        Ok(Value::list(frames))
+    }
+}
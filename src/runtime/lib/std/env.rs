@@ -4,6 +4,12 @@ use crate::runtime::value::Value;
 
 pub struct Env(BTreeMap<String, Value>);
 
+impl Default for Env {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Env {
     pub fn new() -> Self {
         Self(BTreeMap::new())
@@ -13,13 +19,13 @@ impl Env {
         let mut env = Env::new();
 
         // env functions
-        env.declare("args", Value::BuiltInFn(ak_env::args));
-        env.declare("var", Value::BuiltInFn(ak_env::var));
-        env.declare("vars", Value::BuiltInFn(ak_env::vars));
-        env.declare("remove_var", Value::BuiltInFn(ak_env::remove_var));
-        env.declare("set_var", Value::BuiltInFn(ak_env::set_var));
+        env.declare("args", Value::native(ak_env::args));
+        env.declare("var", Value::native(ak_env::var));
+        env.declare("vars", Value::native(ak_env::vars));
+        env.declare("remove_var", Value::native(ak_env::remove_var));
+        env.declare("set_var", Value::native(ak_env::set_var));
 
-        return env.items();
+        env.items()
     }
 
     pub fn declare(&mut self, name: &str, value: Value) {
@@ -27,29 +33,40 @@ impl Env {
     }
 
     fn items(self) -> BTreeMap<String, Value> {
-        return self.0;
+        self.0
     }
 }
 
 mod ak_env {
+    use crate::runtime::error::{ErrorKind, RuntimeError};
+    use crate::runtime::value::BuiltinType;
     use crate::runtime::value::{KeyValue, Value};
+    use crate::runtime::Type;
     use std::env;
 
-    pub fn args(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 0 {
-            return Err(format!("expected 0 arguments, but found {}", vs.len()));
+    pub fn args(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if !vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 0,
+                found: vs.len(),
+            }
+            .into());
         }
 
         let mut args = Vec::new();
         for arg in env::args() {
             args.push(Value::String(arg))
         }
-        Ok(Value::List(args))
+        Ok(Value::list(args))
     }
 
-    pub fn vars(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 0 {
-            return Err(format!("expected 0 arguments, but found {}", vs.len()));
+    pub fn vars(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if !vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 0,
+                found: vs.len(),
+            }
+            .into());
         }
 
         let mut vars = Vec::new();
@@ -60,63 +77,114 @@ mod ak_env {
             });
         }
 
-        Ok(Value::Object(vars))
+        Ok(Value::object(vars))
     }
 
-    pub fn var(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 1 && vs.len() < 1 {
-            return Err(format!("expected 1 arguments, but found {}", vs.len()));
+    pub fn var(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if vs.len() > 1 && vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into());
         }
 
-        match vs.get(0) {
+        match vs.first() {
             Some(value) => match value {
                 Value::String(arg1) => match env::var(arg1) {
-                    Ok(v) => return Ok(Value::String(v)),
-                    Err(e) => return Err(e.to_string()),
+                    Ok(v) => Ok(Value::String(v)),
+                    Err(e) => Err(ErrorKind::IoError {
+                        message: e.to_string(),
+                    }
+                    .into()),
                 },
-                _ => return Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(value),
+                }
+                .into()),
             },
-            None => return Err(format!("expected 1 arguments, but found {}", vs.len())),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into()),
         }
     }
 
-    pub fn remove_var(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 1 && vs.len() < 1 {
-            return Err(format!("expected 1 arguments, but found {}", vs.len()));
+    pub fn remove_var(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if vs.len() > 1 && vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into());
         }
 
-        match vs.get(0) {
+        match vs.first() {
             Some(value) => match value {
                 Value::String(key) => {
                     env::remove_var(key);
-                    return Ok(Value::Null);
+                    Ok(Value::Null)
                 }
-                _ => return Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(value),
+                }
+                .into()),
             },
-            None => return Err(format!("expected 1 arguments, but found {}", vs.len())),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into()),
         }
     }
 
-    pub fn set_var(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 1 && vs.len() < 1 {
-            return Err(format!("expected 1 arguments, but found {}", vs.len()));
+    pub fn set_var(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if vs.len() > 1 && vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into());
         }
 
-        match vs.get(0) {
+        match vs.first() {
             Some(value) => match value {
                 Value::String(arg1) => match vs.get(1) {
                     Some(value2) => match value2 {
                         Value::String(arg2) => {
                             env::set_var(arg1, arg2);
-                            return Ok(Value::Null);
+                            Ok(Value::Null)
+                        }
+                        _ => Err(ErrorKind::InvalidArgument {
+                            position: 2,
+                            expected: Type::Builtin(BuiltinType::String),
+                            found: Type::from(value2),
                         }
-                        _ => return Err(format!("the second argument most be a string")),
+                        .into()),
                     },
-                    None => return Err(format!("expected 1 arguments, but found {}", vs.len())),
+                    None => Err(ErrorKind::ArityMismatch {
+                        expected: 1,
+                        found: vs.len(),
+                    }
+                    .into()),
                 },
-                _ => return Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(value),
+                }
+                .into()),
             },
-            None => return Err(format!("expected 1 arguments, but found {}", vs.len())),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into()),
         }
     }
 }
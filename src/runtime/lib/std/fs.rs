@@ -4,6 +4,12 @@ use crate::runtime::value::Value;
 
 pub struct Fs(BTreeMap<String, Value>);
 
+impl Default for Fs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Fs {
     pub fn new() -> Self {
         Self(BTreeMap::new())
@@ -13,14 +19,14 @@ impl Fs {
         let mut fs = Fs::new();
 
         // fs functions
-        fs.declare("read_file", Value::BuiltInFn(ak_fs::read_file));
-        fs.declare("read_dir", Value::BuiltInFn(ak_fs::read_dir));
-        fs.declare("remove_file", Value::BuiltInFn(ak_fs::remove_file));
-        fs.declare("remove_dir", Value::BuiltInFn(ak_fs::remove_dir));
-        fs.declare("rename_file", Value::BuiltInFn(ak_fs::rename_file));
-        fs.declare("write_file", Value::BuiltInFn(ak_fs::write_file));
-
-        return fs.items();
+        fs.declare("read_file", Value::native(ak_fs::read_file));
+        fs.declare("read_dir", Value::native(ak_fs::read_dir));
+        fs.declare("remove_file", Value::native(ak_fs::remove_file));
+        fs.declare("remove_dir", Value::native(ak_fs::remove_dir));
+        fs.declare("rename_file", Value::native(ak_fs::rename_file));
+        fs.declare("write_file", Value::native(ak_fs::write_file));
+
+        fs.items()
     }
 
     pub fn declare(&mut self, name: &str, value: Value) {
@@ -28,122 +34,191 @@ impl Fs {
     }
 
     fn items(self) -> BTreeMap<String, Value> {
-        return self.0;
+        self.0
     }
 }
 
 mod ak_fs {
+    use crate::runtime::error::{ErrorKind, RuntimeError};
+    use crate::runtime::value::BuiltinType;
     use crate::runtime::value::Value;
+    use crate::runtime::Type;
     use std::fs;
 
-    pub fn read_file(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 1 || vs.len() < 1 {
-            return Err(format!("expected 1 argument, but found {}", vs.len()));
+    pub fn read_file(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if vs.len() > 1 || vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into());
         }
 
-        match vs.get(0) {
+        match vs.first() {
             Some(value) => match value {
                 Value::String(s) => {
                     let file_result = fs::read_to_string(s);
 
                     match file_result {
-                        Ok(content) => return Ok(Value::String(content)),
-                        Err(e) => return Err(e.to_string()),
+                        Ok(content) => Ok(Value::String(content)),
+                        Err(e) => Err(e.into()),
                     }
                 }
-                _ => return Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(value),
+                }
+                .into()),
             },
-            None => {
-                return Err(format!("expected 1 argument, but found {}", vs.len()));
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
             }
+            .into()),
         }
     }
 
-    pub fn write_file(vs: Vec<Value>) -> Result<Value, String> {
+    pub fn write_file(vs: Vec<Value>) -> Result<Value, RuntimeError> {
         if vs.len() > 2 || vs.len() < 2 {
-            return Err(format!("expected 2 argument, but found {}", vs.len()));
+            return Err(ErrorKind::ArityMismatch {
+                expected: 2,
+                found: vs.len(),
+            }
+            .into());
         }
 
-        match vs.get(0) {
+        match vs.first() {
             Some(v1) => match v1 {
                 Value::String(path) => match vs.get(1) {
                     Some(v2) => match v2 {
                         Value::String(content) => {
                             let res = fs::write(path, content);
                             match res {
-                                Ok(_) => return Ok(Value::Null),
-                                Err(e) => return Err(e.to_string()),
+                                Ok(_) => Ok(Value::Null),
+                                Err(e) => Err(e.into()),
                             }
                         }
-                        _ => return Err(format!("the seconde argument most be a string")),
+                        _ => Err(ErrorKind::InvalidArgument {
+                            position: 2,
+                            expected: Type::Builtin(BuiltinType::String),
+                            found: Type::from(v2),
+                        }
+                        .into()),
                     },
-                    None => return Err(format!("expected 2 argument, but found {}", vs.len())),
+                    None => Err(ErrorKind::ArityMismatch {
+                        expected: 2,
+                        found: vs.len(),
+                    }
+                    .into()),
                 },
-                _ => return Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(v1),
+                }
+                .into()),
             },
-            None => {
-                return Err(format!("expected 2 argument, but found {}", vs.len()));
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 2,
+                found: vs.len(),
             }
+            .into()),
         }
     }
 
-    pub fn rename_file(vs: Vec<Value>) -> Result<Value, String> {
+    pub fn rename_file(vs: Vec<Value>) -> Result<Value, RuntimeError> {
         if vs.len() > 2 || vs.len() < 2 {
-            return Err(format!("expected 2 argument, but found {}", vs.len()));
+            return Err(ErrorKind::ArityMismatch {
+                expected: 2,
+                found: vs.len(),
+            }
+            .into());
         }
 
-        match vs.get(0) {
+        match vs.first() {
             Some(v1) => match v1 {
                 Value::String(from) => match vs.get(1) {
                     Some(v2) => match v2 {
                         Value::String(to) => {
                             let res = fs::rename(from, to);
                             match res {
-                                Ok(_) => return Ok(Value::Null),
-                                Err(e) => return Err(e.to_string()),
+                                Ok(_) => Ok(Value::Null),
+                                Err(e) => Err(e.into()),
                             }
                         }
-                        _ => return Err(format!("the first argument most be a string")),
+                        _ => Err(ErrorKind::InvalidArgument {
+                            position: 1,
+                            expected: Type::Builtin(BuiltinType::String),
+                            found: Type::from(v2),
+                        }
+                        .into()),
                     },
-                    None => return Err(format!("expected 2 argument, but found {}", vs.len())),
+                    None => Err(ErrorKind::ArityMismatch {
+                        expected: 2,
+                        found: vs.len(),
+                    }
+                    .into()),
                 },
-                _ => return Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(v1),
+                }
+                .into()),
             },
-            None => {
-                return Err(format!("expected 2 argument, but found {}", vs.len()));
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 2,
+                found: vs.len(),
             }
+            .into()),
         }
     }
 
-    pub fn remove_file(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 1 || vs.len() < 1 {
-            return Err(format!("expected 1 argument, but found {}", vs.len()));
+    pub fn remove_file(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if vs.len() > 1 || vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into());
         }
 
-        match vs.get(0) {
+        match vs.first() {
             Some(value) => match value {
                 Value::String(s) => {
                     let file_result = fs::remove_file(s);
 
                     match file_result {
-                        Ok(_) => return Ok(Value::Null),
-                        Err(e) => return Err(e.to_string()),
+                        Ok(_) => Ok(Value::Null),
+                        Err(e) => Err(e.into()),
                     }
                 }
-                _ => return Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(value),
+                }
+                .into()),
             },
-            None => {
-                return Err(format!("expected 1 argument, but found {}", vs.len()));
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
             }
+            .into()),
         }
     }
 
-    pub fn read_dir(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 1 || vs.len() < 1 {
-            return Err(format!("expected 1 argument, but found {}", vs.len()));
+    pub fn read_dir(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if vs.len() > 1 || vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into());
         }
 
-        match vs.get(0) {
+        match vs.first() {
             Some(value) => match value {
                 Value::String(s) => {
                     let file_result = fs::read_dir(s);
@@ -157,39 +232,57 @@ mod ak_fs {
                                 items.push(Value::String(t));
                             }
 
-                            return Ok(Value::List(items));
+                            Ok(Value::list(items))
                         }
-                        Err(e) => return Err(e.to_string()),
+                        Err(e) => Err(e.into()),
                     }
                 }
-                _ => return Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(value),
+                }
+                .into()),
             },
-            None => {
-                return Err(format!("expected 1 argument, but found {}", vs.len()));
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
             }
+            .into()),
         }
     }
 
-    pub fn remove_dir(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 1 || vs.len() < 1 {
-            return Err(format!("expected 1 argument, but found {}", vs.len()));
+    pub fn remove_dir(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if vs.len() > 1 || vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into());
         }
 
-        match vs.get(0) {
+        match vs.first() {
             Some(value) => match value {
                 Value::String(s) => {
                     let file_result = fs::remove_dir(s);
 
                     match file_result {
-                        Ok(_) => return Ok(Value::Null),
-                        Err(e) => return Err(e.to_string()),
+                        Ok(_) => Ok(Value::Null),
+                        Err(e) => Err(e.into()),
                     }
                 }
-                _ => return Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(value),
+                }
+                .into()),
             },
-            None => {
-                return Err(format!("expected 1 argument, but found {}", vs.len()));
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
             }
+            .into()),
         }
     }
 }
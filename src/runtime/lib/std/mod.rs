@@ -3,17 +3,25 @@ use std::collections::BTreeMap;
 use crate::runtime::value::Value;
 
 use self::collections::Collections;
+use self::debug::Debug;
 use self::env::Env;
 use self::fs::Fs;
 use self::system::System;
 
 pub mod collections;
+pub mod debug;
 pub mod env;
 pub mod fs;
 pub mod system;
 
 pub struct Std(BTreeMap<String, Value>);
 
+impl Default for Std {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Std {
     pub fn new() -> Self {
         Self(BTreeMap::new())
@@ -23,12 +31,13 @@ impl Std {
         let mut std = Std::new();
 
         // std modules
-        std.declare("system", Value::Module(System::exports()));
-        std.declare("fs", Value::Module(Fs::exports()));
-        std.declare("env", Value::Module(Env::exports()));
-        std.declare("collections", Value::Module(Collections::exports()));
+        std.declare("system", Value::Module(System::exports(), vec![]));
+        std.declare("fs", Value::Module(Fs::exports(), vec![]));
+        std.declare("env", Value::Module(Env::exports(), vec![]));
+        std.declare("collections", Value::Module(Collections::exports(), vec![]));
+        std.declare("debug", Value::Module(Debug::exports(), vec![]));
 
-        return std.items();
+        std.items()
     }
 
     pub fn declare(&mut self, name: &str, value: Value) {
@@ -36,6 +45,6 @@ impl Std {
     }
 
     fn items(self) -> BTreeMap<String, Value> {
-        return self.0;
+        self.0
     }
 }
@@ -4,6 +4,12 @@ use crate::runtime::value::Value;
 
 pub struct System(BTreeMap<String, Value>);
 
+impl Default for System {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl System {
     pub fn new() -> Self {
         Self(BTreeMap::new())
@@ -13,19 +19,19 @@ impl System {
         let mut system = System::new();
 
         // system functions
-        system.declare("platform", Value::BuiltInFn(ak_system::_platform));
-        system.declare("free_mem", Value::BuiltInFn(ak_system::_free_mem));
-        system.declare("total_mem", Value::BuiltInFn(ak_system::_total_mem));
-        system.declare("free_disk", Value::BuiltInFn(ak_system::_free_disk));
-        system.declare("total_disk", Value::BuiltInFn(ak_system::_total_disk));
-        system.declare("cpu_speed", Value::BuiltInFn(ak_system::_cpu_speed));
-        system.declare("cpus", Value::BuiltInFn(ak_system::_cpus));
-        system.declare("arch", Value::BuiltInFn(ak_system::_arch));
-        system.declare("version", Value::BuiltInFn(ak_system::_version));
-        system.declare("processes", Value::BuiltInFn(ak_system::_processes));
-        system.declare("family", Value::BuiltInFn(ak_system::_family));
-
-        return system.items();
+        system.declare("platform", Value::native(ak_system::_platform));
+        system.declare("free_mem", Value::native(ak_system::_free_mem));
+        system.declare("total_mem", Value::native(ak_system::_total_mem));
+        system.declare("free_disk", Value::native(ak_system::_free_disk));
+        system.declare("total_disk", Value::native(ak_system::_total_disk));
+        system.declare("cpu_speed", Value::native(ak_system::_cpu_speed));
+        system.declare("cpus", Value::native(ak_system::_cpus));
+        system.declare("arch", Value::native(ak_system::_arch));
+        system.declare("version", Value::native(ak_system::_version));
+        system.declare("processes", Value::native(ak_system::_processes));
+        system.declare("family", Value::native(ak_system::_family));
+
+        system.items()
     }
 
     pub fn declare(&mut self, name: &str, value: Value) {
@@ -33,106 +39,151 @@ impl System {
     }
 
     pub fn items(self) -> BTreeMap<String, Value> {
-        return self.0;
+        self.0
     }
 }
 
 pub mod ak_system {
     use std::env;
 
+    use crate::runtime::error::{ErrorKind, RuntimeError};
     use crate::runtime::value::Value;
 
-    pub fn _platform(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 0 {
-            return Err(format!("expected 0 arguments, but found {}", vs.len()));
+    pub fn _platform(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if !vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 0,
+                found: vs.len(),
+            }
+            .into());
         }
 
         Ok(Value::String(env::consts::OS.to_string()))
     }
 
-    pub fn _free_mem(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 0 {
-            return Err(format!("expected 0 arguments, but found {}", vs.len()));
+    pub fn _free_mem(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if !vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 0,
+                found: vs.len(),
+            }
+            .into());
         }
 
         let mem_info = sys_info::mem_info().unwrap();
         Ok(Value::Int(mem_info.free as i32))
     }
 
-    pub fn _total_mem(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 0 {
-            return Err(format!("expected 0 arguments, but found {}", vs.len()));
+    pub fn _total_mem(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if !vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 0,
+                found: vs.len(),
+            }
+            .into());
         }
 
         let mem_info = sys_info::mem_info().unwrap();
         Ok(Value::Int(mem_info.total as i32))
     }
 
-    pub fn _total_disk(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 0 {
-            return Err(format!("expected 0 arguments, but found {}", vs.len()));
+    pub fn _total_disk(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if !vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 0,
+                found: vs.len(),
+            }
+            .into());
         }
 
         let disk_info = sys_info::disk_info().unwrap();
         Ok(Value::Int(disk_info.total as i32))
     }
 
-    pub fn _free_disk(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 0 {
-            return Err(format!("expected 0 arguments, but found {}", vs.len()));
+    pub fn _free_disk(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if !vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 0,
+                found: vs.len(),
+            }
+            .into());
         }
 
         let disk_info = sys_info::disk_info().unwrap();
         Ok(Value::Int(disk_info.free as i32))
     }
 
-    pub fn _cpus(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 0 {
-            return Err(format!("expected 0 arguments, but found {}", vs.len()));
+    pub fn _cpus(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if !vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 0,
+                found: vs.len(),
+            }
+            .into());
         }
 
         let cpu_num = sys_info::cpu_num().unwrap();
         Ok(Value::Int(cpu_num as i32))
     }
 
-    pub fn _cpu_speed(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 0 {
-            return Err(format!("expected 0 arguments, but found {}", vs.len()));
+    pub fn _cpu_speed(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if !vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 0,
+                found: vs.len(),
+            }
+            .into());
         }
 
         let cpu_speed = sys_info::cpu_speed().unwrap();
         Ok(Value::Int(cpu_speed as i32))
     }
 
-    pub fn _version(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 0 {
-            return Err(format!("expected 0 arguments, but found {}", vs.len()));
+    pub fn _version(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if !vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 0,
+                found: vs.len(),
+            }
+            .into());
         }
 
         let version = sys_info::os_release().unwrap();
         Ok(Value::String(version))
     }
 
-    pub fn _processes(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 0 {
-            return Err(format!("expected 0 arguments, but found {}", vs.len()));
+    pub fn _processes(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if !vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 0,
+                found: vs.len(),
+            }
+            .into());
         }
 
         let processes = sys_info::proc_total().unwrap();
         Ok(Value::Int(processes as i32))
     }
 
-    pub fn _arch(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 0 {
-            return Err(format!("expected 0 arguments, but found {}", vs.len()));
+    pub fn _arch(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if !vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 0,
+                found: vs.len(),
+            }
+            .into());
         }
 
         Ok(Value::String(env::consts::ARCH.to_string()))
     }
 
-    pub fn _family(vs: Vec<Value>) -> Result<Value, String> {
-        if vs.len() > 0 {
-            return Err(format!("expected 0 arguments, but found {}", vs.len()));
+    pub fn _family(vs: Vec<Value>) -> Result<Value, RuntimeError> {
+        if !vs.is_empty() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: 0,
+                found: vs.len(),
+            }
+            .into());
         }
 
         Ok(Value::String(env::consts::FAMILY.to_string()))
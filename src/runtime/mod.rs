@@ -1,27 +1,46 @@
-use ::std::collections::HashMap;
+use ::std::cmp::Ordering;
+use ::std::collections::{BTreeMap, HashMap};
 use ::std::fmt;
+use ::std::path::{Path, PathBuf};
 
-use ::std::sync::{Arc, Mutex};
+use ::std::sync::{Arc, Mutex, MutexGuard};
 
-use crate::ast::{Arg, Statement};
+use crate::ast::{Arg, Field, Method, MethodSig, Statement, Variant};
 
-use self::eval::statement::{eval_statements, Escape};
+use self::error::{ErrorKind, RuntimeError};
+use self::eval::module::ModuleRegistry;
 use self::prototypes::object::object_proto;
 use self::value::{check_list_items, BuiltinType, Value};
+use crate::span::Span;
 
+pub mod context;
+pub mod error;
 pub mod eval;
 pub mod lib;
 pub mod prototypes;
+pub mod trace;
 pub mod value;
+pub mod vm;
 
+pub use context::Context;
 pub use lib::StdLib;
 pub use prototypes::Prototypes;
 
-#[derive(Debug, Clone)]
-pub struct ScopeStack(Vec<Arc<Mutex<Scope>>>);
+#[derive(Clone)]
+pub struct ScopeStack(Vec<Arc<Mutex<Scope>>>, Arc<Mutex<ModuleRegistry>>);
 
 pub type Scope = HashMap<String, (Value, DeclType, Type)>;
 
+/// what runs the programs of an interpreter
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Engine {
+    /// programs are compiled to bytecode first
+    #[default]
+    Vm,
+    /// programs are evaluated from the syntax tree, kept to compare against the vm
+    TreeWalker,
+}
+
 #[derive(Debug, Clone)]
 pub enum DeclType {
     Mutable,
@@ -38,6 +57,69 @@ pub enum Type {
     Builtin(BuiltinType),
 }
 
+impl Type {
+    /// `object`, `function` and `module` are builtin names without a `type` declaration behind them
+    pub fn is_opaque(&self) -> bool {
+        match self {
+            Type::Alias(name) => ["object", "function", "module"].contains(&name.as_str()),
+            _ => false,
+        }
+    }
+
+    /// name of the prototype that holds the methods of values of this type
+    pub fn prototype(&self) -> String {
+        match self {
+            Type::Alias(name) => name.to_string(),
+            Type::Builtin(BuiltinType::List(_)) => "list".to_string(),
+            Type::Builtin(BuiltinType::Tuple(_)) => "tuple".to_string(),
+            Type::Builtin(BuiltinType::Fn(..)) => "function".to_string(),
+            Type::Builtin(t) => t.to_string(),
+        }
+    }
+}
+
+/// type of a function with these arguments and return type
+fn signature(args: &[Arg], ret_type: &Option<Type>) -> Type {
+    let args = args.iter().map(|arg| arg.datatype.clone()).collect();
+    let ret_type = match ret_type {
+        Some(ret_type) => ret_type.clone(),
+        None => Type::Builtin(BuiltinType::Null),
+    };
+
+    Type::Builtin(BuiltinType::Fn(args, Box::new(ret_type)))
+}
+
+/// a list that never held an item takes the item type of the name it is bound to
+fn take_item_type(value: &Value, datatype: &Type) {
+    if let (Value::List(_, item_type), Type::Builtin(BuiltinType::List(t))) = (value, datatype) {
+        item_type.set(*t.clone());
+    }
+}
+
+fn register_item(
+    items: &mut BTreeMap<String, Value>,
+    path: &[String],
+    value: Value,
+) -> Result<(), RuntimeError> {
+    let (name, rest) = path.split_first().expect("path shouldn't be empty");
+
+    if rest.is_empty() {
+        items.insert(name.to_string(), value);
+        return Ok(());
+    }
+
+    match items
+        .entry(name.to_string())
+        .or_insert_with(|| Value::Module(BTreeMap::new(), vec![]))
+    {
+        Value::Module(items, _) => register_item(items, rest, value),
+        _ => Err(ErrorKind::AlreadyDefined {
+            name: name.to_string(),
+        }
+        .into()),
+    }
+}
+
 impl Simple for Type {
     fn simple(value: &Value) -> String {
         match value {
@@ -46,14 +128,20 @@ impl Simple for Type {
             Value::Float(_) => "float".to_string(),
             Value::String(_) => "string".to_string(),
             Value::Bool(_) => "bool".to_string(),
-            Value::List(_) => "list".to_string(),
+            Value::List(..) => "list".to_string(),
             Value::Object(_) => "object".to_string(),
             Value::BuiltInFn(_) => "function".to_string(),
             Value::BuiltInMethod(_, _) => "function".to_string(),
             Value::Func(..) => "function".to_string(),
-            Value::Module(_) => "module".to_string(),
+            Value::Closure(_) => "function".to_string(),
+            Value::Module(..) => "module".to_string(),
             Value::Tuple(_) => "tuple".to_string(),
             Value::Type(_, _) => "type".to_string(),
+            Value::Struct(..) => "type".to_string(),
+            Value::Instance(name, _) => name.to_string(),
+            Value::Enum(..) => "type".to_string(),
+            Value::Variant(name, ..) => name.to_string(),
+            Value::Trait(..) => "type".to_string(),
         }
     }
 }
@@ -87,12 +175,13 @@ impl From<&Value> for Type {
             Value::Float(_) => Type::Builtin(BuiltinType::Float),
             Value::String(_) => Type::Builtin(BuiltinType::String),
             Value::Bool(_) => Type::Builtin(BuiltinType::Bool),
-            Value::List(l) => match l.get(0) {
-                Some(value) => Type::Builtin(BuiltinType::List(Box::new(Type::from(value)))),
-                None => Type::Builtin(BuiltinType::List(Box::new(Type::Builtin(
-                    BuiltinType::Null,
-                )))),
-            },
+            // a list that never held an item is a `null[]`
+            Value::List(_, item_type) => Type::Builtin(BuiltinType::List(Box::new(
+                item_type
+                    .get()
+                    .cloned()
+                    .unwrap_or(Type::Builtin(BuiltinType::Null)),
+            ))),
             Value::Tuple(values) => {
                 let mut types = Vec::new();
 
@@ -102,49 +191,18 @@ impl From<&Value> for Type {
 
                 Type::Builtin(BuiltinType::Tuple(types))
             }
-            Value::Func(args, ret_type, block) => {
-                let mut scopes = ScopeStack::new(vec![Arc::new(Mutex::new(StdLib::exports()))]);
-                let mut args_types = Vec::new();
-
-                for arg in args {
-                    args_types.push(arg.datatype.clone());
-                    scopes
-                        .declare(
-                            &arg.ident,
-                            Value::from(arg.datatype.clone()),
-                            &Some(arg.datatype.clone()),
-                            DeclType::Mutable,
-                        )
-                        .unwrap();
-                }
-
-                if let Some(ret_type) = ret_type {
-                    return Type::Builtin(BuiltinType::Fn(args_types, Box::new(ret_type.clone())));
-                } else {
-                    let ret = eval_statements(&mut scopes, block, &Prototypes::exports()).unwrap();
-
-                    match ret {
-                        Escape::Return(value) => {
-                            return Type::Builtin(BuiltinType::Fn(
-                                args_types,
-                                Box::new(Type::from(&value)),
-                            ))
-                        }
-
-                        _ => {
-                            return Type::Builtin(BuiltinType::Fn(
-                                args_types,
-                                Box::new(Type::Builtin(BuiltinType::Null)),
-                            ))
-                        }
-                    }
-                }
+            Value::Func(args, ret_type, ..) => signature(args, ret_type),
+            Value::Closure(closure) => {
+                signature(&closure.function.args, &closure.function.ret_type)
             }
             Value::Type(_, t) => t.clone(),
             Value::Object(_) => Type::Alias("object".to_string()),
             Value::BuiltInFn(_) => Type::Alias("function".to_string()),
             Value::BuiltInMethod(_, _) => Type::Alias("function".to_string()),
-            Value::Module(_) => Type::Alias("module".to_string()),
+            Value::Module(..) => Type::Alias("module".to_string()),
+            Value::Struct(name, _) | Value::Instance(name, _) => Type::Alias(name.to_string()),
+            Value::Enum(name, _) | Value::Variant(name, ..) => Type::Alias(name.to_string()),
+            Value::Trait(name, _) => Type::Alias(name.to_string()),
         }
     }
 }
@@ -161,35 +219,100 @@ impl From<Type> for String {
 impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Type::Builtin(t) => write!(f, "{}", t.to_string()),
+            Type::Builtin(t) => write!(f, "{}", t),
             Type::Alias(t) => write!(f, "{}", t),
         }
     }
 }
 
+// closures keep the stack they were created in, which can contain the closure itself,
+// so the scopes are compared by identity and never printed
+impl fmt::Debug for ScopeStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ScopeStack({} scopes)", self.0.len())
+    }
+}
+
+impl PartialEq for ScopeStack {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().zip(&other.0).all(|(a, b)| Arc::ptr_eq(a, b))
+    }
+}
+
+impl PartialOrd for ScopeStack {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            return Some(Ordering::Equal);
+        }
+        None
+    }
+}
+
 impl ScopeStack {
     pub fn new(scopes: Vec<Arc<Mutex<Scope>>>) -> ScopeStack {
-        ScopeStack(scopes)
+        ScopeStack(scopes, Arc::new(Mutex::new(ModuleRegistry::default())))
+    }
+
+    /// a stack with only the outermost scope, where the builtins live
+    fn global(&self) -> ScopeStack {
+        ScopeStack(vec![self.0[0].clone()], self.1.clone())
     }
 
     fn new_from_push(&self, scope: Scope) -> ScopeStack {
         let mut scopes = self.0.clone();
         scopes.push(Arc::new(Mutex::new(scope)));
 
-        ScopeStack::new(scopes)
+        ScopeStack(scopes, self.1.clone())
+    }
+
+    /// the file modules this interpreter has loaded, shared by every stack made from it
+    fn modules(&self) -> MutexGuard<'_, ModuleRegistry> {
+        self.1.lock().unwrap()
+    }
+
+    pub(crate) fn engine(&self) -> Engine {
+        self.modules().engine
+    }
+
+    pub(crate) fn set_engine(&self, engine: Engine) {
+        self.modules().engine = engine;
+    }
+
+    /// the project root is set once, by the first file the interpreter runs
+    pub(crate) fn set_root(&self, dir: &Path) {
+        self.modules().root.get_or_insert_with(|| dir.to_path_buf());
+    }
+
+    pub(crate) fn root(&self) -> Option<PathBuf> {
+        self.modules().root.clone()
     }
 
     fn push(&mut self, scope: Scope) {
         self.0.push(Arc::new(Mutex::new(scope)));
     }
 
+    fn pop(&mut self) {
+        self.0.pop();
+    }
+
+    /// a stack with `scope` under the innermost one, declarations still go to the innermost scope
+    fn insert_below(&self, scope: Scope) -> ScopeStack {
+        let mut scopes = self.0.clone();
+        let at = scopes.len() - 1;
+        scopes.insert(at, Arc::new(Mutex::new(scope)));
+
+        ScopeStack(scopes, self.1.clone())
+    }
+
     fn declare(
         &mut self,
         name: &String,
         value: Value,
         datatype: &Option<Type>,
         decl_type: DeclType,
-    ) -> Result<(), String> {
+    ) -> Result<(), RuntimeError> {
+        let datatype = self.value_type(&value, datatype)?;
+
         let mut current_scope = self
             .0
             .last()
@@ -197,138 +320,143 @@ impl ScopeStack {
             .lock()
             .unwrap();
 
-        if let Value::Object(props) = &value {
+        if current_scope.contains_key(name) {
+            return Err(ErrorKind::AlreadyDefined {
+                name: name.to_string(),
+            }
+            .into());
+        }
+
+        current_scope.insert(name.to_string(), (value, decl_type, datatype));
+
+        Ok(())
+    }
+
+    /// checks `value` before it is bound to a name of type `datatype`, returns the type of the binding
+    fn value_type(&self, value: &Value, datatype: &Option<Type>) -> Result<Type, RuntimeError> {
+        if let Value::Object(props) = value {
+            let props = props.lock();
             let obj_proto = object_proto();
             let mut keys: Vec<String> = vec![];
 
-            for prop in props {
+            for prop in props.iter() {
                 if keys.contains(&prop.key) {
-                    return Err(format!("duplicate property '{}'", prop.key));
+                    return Err(ErrorKind::DuplicateProperty {
+                        name: prop.key.to_string(),
+                    }
+                    .into());
                 }
                 keys.push(prop.key.to_string());
             }
 
-            let res = props
-                .into_iter()
-                .find(|kv| obj_proto.get(&kv.key).is_some());
+            let res = props.iter().find(|kv| obj_proto.contains_key(&kv.key));
 
             if let Some(kv) = res {
-                return Err(format!(
-                    "property `{}` is reserved in object prototype",
-                    kv.key
-                ));
+                return Err(ErrorKind::ReservedProperty {
+                    name: kv.key.to_string(),
+                }
+                .into());
             }
         }
 
-        if current_scope.contains_key(name) {
-            return Err(format!("`{}` already define in this scope", name));
-        }
-
         // all list items most be have same type
-        if let Value::List(list) = &value {
-            check_list_items(list)?;
-        }
-
-        if let Value::Func(arg, ret_type, block) = &value {
-            let mut x = self.clone();
-            let ret = eval_statements(&mut x, block, &Prototypes::exports())?;
-            if let Some(ret_t) = ret_type {
-                if let Escape::Return(val) = ret {
-                    if &Type::from(&val) != ret_t {
-                        return Err(format!(
-                            "extected `{}` found `{}` (15)",
-                            Type::from(&Value::Func(
-                                arg.to_vec(),
-                                Some(ret_t.clone()),
-                                block.clone()
-                            )),
-                            Type::from(&Value::Func(
-                                arg.to_vec(),
-                                Some(Type::from(&val)),
-                                block.clone()
-                            )),
-                        ));
-                    }
-                }
-            }
+        if let Value::List(list, _) = value {
+            check_list_items(&list.lock())?;
         }
 
         // type checking
-        if let Some(datatype) = datatype {
-            if let Type::Alias(type_name) = datatype {
-                std::mem::drop(current_scope);
-                match self.get(type_name) {
-                    Some(val) => match &val {
-                        Value::Type(_, _) => {
-                            if Type::from(&value) != self.get_type_alias(datatype)? {
-                                return Err(format!(
-                                    "expected `{}: ({})`, found `{}` (2)",
-                                    type_name,
-                                    self.get_type_alias(datatype)?,
-                                    Type::from(&value),
-                                ));
-                            }
-                            let mut current_scope = self
-                                .0
-                                .last()
-                                .expect("`ScopeStack` stack shouldn't be empty")
-                                .lock()
-                                .unwrap();
-
-                            current_scope
-                                .insert(name.to_string(), (value, decl_type, datatype.clone()));
+        let datatype = match datatype {
+            Some(Type::Alias(type_name)) => match self.get_type(type_name) {
+                Some(Value::Trait(..)) => {
+                    let expected = Type::Alias(type_name.to_string());
+                    if !self.implements(value, &expected) {
+                        return Err(ErrorKind::TypeMismatch {
+                            expected,
+                            found: Type::from(value),
                         }
-                        _ => return Err(format!("expected `type`, but `{}` is a `value`", name)),
-                    },
-                    None => return Err(format!("type `{}` is not defined (9)", type_name)),
+                        .into());
+                    }
+                    expected
                 }
-            } else {
-                if Type::from(&value) != self.get_type_alias(datatype)? {
-                    return Err(format!(
-                        "expected `{} ({})`, found `{}` (1)",
-                        datatype,
-                        self.get_type_alias(datatype)?,
-                        Type::from(&value)
-                    ));
+                Some(Value::Type(..)) | Some(Value::Struct(..)) | Some(Value::Enum(..)) => {
+                    let expected = self.get_type_alias(&Type::Alias(type_name.to_string()))?;
+                    if Type::from(value) != expected {
+                        return Err(ErrorKind::TypeMismatch {
+                            expected,
+                            found: Type::from(value),
+                        }
+                        .into());
+                    }
+                    Type::Alias(type_name.to_string())
                 }
-
-                current_scope.insert(name.to_string(), (value, decl_type, datatype.clone()));
+                Some(_) => {
+                    return Err(ErrorKind::NotAType {
+                        name: type_name.to_string(),
+                    }
+                    .into())
+                }
+                None => {
+                    return Err(ErrorKind::UndefinedType {
+                        name: type_name.to_string(),
+                    }
+                    .into())
+                }
+            },
+            Some(datatype) => {
+                let expected = self.get_type_alias(datatype)?;
+                take_item_type(value, &expected);
+                if Type::from(value) != expected {
+                    return Err(ErrorKind::TypeMismatch {
+                        expected,
+                        found: Type::from(value),
+                    }
+                    .into());
+                }
+                datatype.clone()
             }
-        } else {
-            current_scope.insert(
-                name.to_string(),
-                (value.clone(), decl_type, Type::from(&value)),
-            );
-        }
+            None => Type::from(value),
+        };
 
-        Ok(())
+        Ok(datatype)
     }
 
-    fn assgin(&mut self, name: String, value: Value) -> Result<(), String> {
+    fn assgin(&mut self, name: String, value: Value) -> Result<(), RuntimeError> {
         for scope in self.0.iter().rev() {
-            let mut unlocked_scope = scope.lock().unwrap();
-            if let Some((_, decl_type, datatype)) = unlocked_scope.clone().get(&name) {
+            // the lock is released before checking traits, which looks through every scope
+            let entry = scope.lock().unwrap().get(&name).cloned();
+            if let Some((_, decl_type, datatype)) = entry {
                 if let DeclType::Immutable = decl_type {
-                    return Err(format!("cannot mutate a immutable item '{}'", name));
+                    return Err(ErrorKind::ImmutableAssignment { name }.into());
                 }
 
-                if &&Type::from(&value) != &datatype {
-                    return Err(format!(
-                        "expected `{}`, found `{}`",
-                        datatype,
-                        Type::from(&value)
-                    ));
-                }
+                self.check_assignment(&value, &datatype)?;
 
-                unlocked_scope.insert(name, (value, DeclType::Mutable, datatype.clone()));
+                let mut unlocked_scope = scope.lock().unwrap();
+                unlocked_scope.insert(name, (value, DeclType::Mutable, datatype));
                 return Ok(());
             }
         }
 
-        Err(format!("'{}' is not defined", name))
+        Err(ErrorKind::UndefinedName { name }.into())
+    }
+
+    /// whether `value` can be assigned to a variable of type `datatype`
+    fn check_assignment(&self, value: &Value, datatype: &Type) -> Result<(), RuntimeError> {
+        take_item_type(value, datatype);
+        let value_type = Type::from(value);
+
+        if &value_type != datatype && !self.implements(value, datatype) {
+            return Err(ErrorKind::TypeMismatch {
+                expected: datatype.clone(),
+                found: value_type,
+            }
+            .into());
+        }
+
+        Ok(())
     }
 
-    fn get(&self, name: &String) -> Option<Value> {
+    pub(crate) fn get(&self, name: &String) -> Option<Value> {
         for scope in self.0.iter().rev() {
             let unlocked_scope = scope.lock().unwrap();
             if let Some(v) = unlocked_scope.get(name) {
@@ -338,16 +466,86 @@ impl ScopeStack {
         None
     }
 
+    /// the value a type is named after, a type of a module is named by its path `shapes::Point`
+    fn get_type(&self, name: &str) -> Option<Value> {
+        let mut path = name.split("::");
+        let mut value = self.get(&path.next()?.to_string())?;
+        for name in path {
+            value = match value {
+                Value::Module(items, _) => items.get(name)?.clone(),
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// puts `value` at `path` in the outermost scope, next to the builtins.
+    /// the modules on the way are created when they don't exist
+    pub(crate) fn register(&self, path: &[String], value: Value) -> Result<(), RuntimeError> {
+        let (name, rest) = path.split_first().expect("path shouldn't be empty");
+
+        // a script global of the same name would hide it from every script
+        if self.0[1..].iter().any(|scope| scope.lock().unwrap().contains_key(name)) {
+            return Err(ErrorKind::AlreadyDefined {
+                name: name.to_string(),
+            }
+            .into());
+        }
+
+        let mut builtins = self.0[0].lock().unwrap();
+
+        if rest.is_empty() {
+            let datatype = Type::from(&value);
+            builtins.insert(name.to_string(), (value, DeclType::Immutable, datatype));
+            return Ok(());
+        }
+
+        let (mut items, private) = match builtins.get(name) {
+            Some((Value::Module(items, private), ..)) => (items.clone(), private.clone()),
+            Some(_) => {
+                return Err(ErrorKind::AlreadyDefined {
+                    name: name.to_string(),
+                }
+                .into())
+            }
+            None => (BTreeMap::new(), vec![]),
+        };
+        register_item(&mut items, rest, value)?;
+
+        let module = Value::Module(items, private);
+        let datatype = Type::from(&module);
+        builtins.insert(name.to_string(), (module, DeclType::Immutable, datatype));
+        Ok(())
+    }
+
+    /// assigns `name` when it is already declared, otherwise declares it as a mutable variable
+    /// in the innermost scope
+    pub(crate) fn set(&mut self, name: &String, value: Value) -> Result<(), RuntimeError> {
+        if self.get(name).is_some() {
+            return self.assgin(name.to_string(), value);
+        }
+
+        self.declare(name, value, &None, DeclType::Mutable)
+    }
+
     fn declare_fn_statement(
         &mut self,
         fn_name: &String,
-        args: &Vec<Arg>,
-        ret_type: &Type,
-        block: &Vec<Statement>,
-    ) -> Result<(), String> {
-        let expected_ret_type = self.get_type_alias(ret_type)?;
+        args: &[Arg],
+        ret_type: &Option<Type>,
+        block: &[Statement],
+    ) -> Result<(), RuntimeError> {
+        // the function captures the scope it is declared in, which also makes it visible to itself
+        let value = Value::Func(
+            args.to_vec(),
+            ret_type.clone(),
+            block.to_vec(),
+            self.clone(),
+            Some(fn_name.to_string()),
+        );
+        let datatype = Type::from(&value);
 
-        let current_scope = self
+        let mut current_scope = self
             .0
             .last()
             .expect("`ScopeStack` stack shouldn't be empty")
@@ -355,41 +553,78 @@ impl ScopeStack {
             .unwrap();
 
         if current_scope.contains_key(fn_name) {
-            return Err(format!("'{}' already define in this scope", fn_name));
+            return Err(ErrorKind::AlreadyDefined {
+                name: fn_name.to_string(),
+            }
+            .into());
         }
 
-        let mut inner_scope = self.new_from_push(HashMap::new());
+        current_scope.insert(fn_name.to_string(), (value, DeclType::Immutable, datatype));
 
-        for arg in args {
-            inner_scope.declare_variable(
-                &arg.ident,
-                &arg.datatype,
-                &Value::from(arg.datatype.clone()),
-                DeclType::Mutable,
-            )?;
+        Ok(())
+    }
+
+    /// adds `value`, the function of `method`, to `prototype`. an `impl` block that runs
+    /// again, like one inside a function, replaces the methods it declared before
+    pub(crate) fn declare_method(
+        &self,
+        prototypes: &HashMap<String, HashMap<String, Value>>,
+        prototype: &str,
+        method: &Method,
+        value: Value,
+    ) -> Result<(), RuntimeError> {
+        // builtin methods can't be replaced from a script
+        let builtin = prototypes
+            .get(prototype)
+            .is_some_and(|proto| proto.contains_key(&method.name));
+
+        let mut modules = self.modules();
+        let methods = modules.methods.entry(prototype.to_string()).or_default();
+
+        let declared = methods
+            .get(&method.name)
+            .is_some_and(|(_, span)| span != &method.span);
+
+        if builtin || declared {
+            return Err(RuntimeError::new(ErrorKind::DuplicateMethod {
+                name: method.name.to_string(),
+                datatype: prototype.to_string(),
+            })
+            .with_span(method.span));
         }
 
-        let ret = eval_statements(&mut inner_scope, block, &Prototypes::exports())?;
+        methods.insert(method.name.to_string(), (value, method.span));
+        Ok(())
+    }
 
-        if let Escape::Return(ret_value) = ret {
-            if expected_ret_type != Type::from(&ret_value) {
-                return Err(format!(
-                    "expected `{}` found `{}` (13)",
-                    expected_ret_type,
-                    Type::from(&ret_value)
-                ));
+    /// the method bound to `this`, `self` lives in a scope of its own on top of the captured one
+    fn get_method(&self, this: &Value, name: &str) -> Result<Option<Value>, RuntimeError> {
+        let prototype = Type::simple(this);
+        let method = self
+            .modules()
+            .methods
+            .get(&prototype)
+            .and_then(|methods| methods.get(name))
+            .map(|(value, _)| value.clone());
+
+        match method {
+            Some(Value::Func(args, ret_type, block, env, name)) => {
+                let mut env = env.new_from_push(HashMap::new());
+                env.declare(
+                    &"self".to_string(),
+                    this.clone(),
+                    &None,
+                    DeclType::Immutable,
+                )?;
+
+                Ok(Some(Value::Func(args, ret_type, block, env, name)))
             }
-        } else {
-            if expected_ret_type != Type::Builtin(BuiltinType::Null) {
-                return Err(format!(
-                    "expected `{}` found `{}` (14)",
-                    expected_ret_type,
-                    Type::Builtin(BuiltinType::Null)
-                ));
+            Some(Value::Closure(closure)) => {
+                self.value_type(this, &None)?;
+                Ok(Some(Value::Closure(Arc::new(closure.bind(this.clone())))))
             }
+            _ => Ok(None),
         }
-
-        Ok(())
     }
 
     fn declare_variable(
@@ -398,101 +633,200 @@ impl ScopeStack {
         datatype: &Type,
         value: &Value,
         decl_type: DeclType,
-    ) -> Result<(), String> {
-        let extected_type = self.get_type_alias(datatype)?;
-        let value_type = self.get_type_alias(&Type::from(value))?;
-
-        if let Value::Func(args, ret_type, block) = &value {
-            if &extected_type != &value_type {
-                return Err(format!(
-                    "expected `{}` found `{}` (16)",
-                    extected_type, value_type
-                ));
+    ) -> Result<(), RuntimeError> {
+        let extected_type = self.variable_type(datatype, value)?;
+
+        let mut current_scope = self
+            .0
+            .last()
+            .expect("`ScopeStack` stack shouldn't be empty")
+            .lock()
+            .unwrap();
+
+        if current_scope.contains_key(name) {
+            return Err(ErrorKind::AlreadyDefined {
+                name: name.to_string(),
             }
+            .into());
+        }
 
-            if let Some(ret_type) = ret_type {
-                let value_type = Type::from(&Value::Func(
-                    args.clone(),
-                    Some(self.get_type_alias(ret_type)?),
-                    block.clone(),
-                ));
-
-                let current_scope = self
-                    .0
-                    .last()
-                    .expect("`ScopeStack` stack shouldn't be empty")
-                    .lock()
-                    .unwrap();
-
-                if current_scope.contains_key(name) {
-                    return Err(format!("'{}' already define in this scope", name));
-                }
+        current_scope.insert(name.to_string(), (value.clone(), decl_type, extected_type));
+
+        Ok(())
+    }
 
-                let mut inner_scope = self.new_from_push(HashMap::new());
+    /// the type a variable declared as `datatype` gets, `value` has to fit it
+    fn variable_type(&self, datatype: &Type, value: &Value) -> Result<Type, RuntimeError> {
+        let value_type = self.type_of(value)?;
+        let extected_type = if datatype == &Type::from(value) {
+            value_type.clone()
+        } else {
+            self.get_type_alias(datatype)?
+        };
+
+        // a trait accepts any value whose type implements it
+        if extected_type != value_type && !self.implements(value, &extected_type) {
+            return Err(ErrorKind::TypeMismatch {
+                expected: extected_type,
+                found: value_type,
+            }
+            .into());
+        }
 
-                for arg in args {
-                    inner_scope.declare_variable(
-                        &arg.ident,
-                        &arg.datatype,
-                        &Value::from(arg.datatype.clone()),
-                        DeclType::Mutable,
-                    )?;
-                }
+        Ok(extected_type)
+    }
 
-                let ret = eval_statements(&mut inner_scope, block, &Prototypes::exports())?;
-
-                if let Escape::Return(ret_value) = ret {
-                    let ret_type = Type::from(&Value::Func(
-                        args.clone(),
-                        Some(Type::from(&ret_value)),
-                        block.clone(),
-                    ));
-                    if ret_type != value_type {
-                        return Err(format!(
-                            "expected `{}` found `{}` (13)",
-                            value_type, ret_type
-                        ));
-                    }
-                } else {
-                    let ret_type = Type::from(&Value::Func(
-                        args.clone(),
-                        Some(Type::Builtin(BuiltinType::Null)),
-                        block.clone(),
-                    ));
-                    if value_type != ret_type {
-                        return Err(format!(
-                            "expected `{}` found `{}` (14)",
-                            value_type, ret_type
-                        ));
-                    }
+    fn declare_struct(&mut self, name: &String, fields: &Vec<Field>) -> Result<(), RuntimeError> {
+        let mut names: Vec<&String> = vec![];
+
+        for field in fields {
+            if names.contains(&&field.name) {
+                return Err(RuntimeError::new(ErrorKind::DuplicateProperty {
+                    name: field.name.to_string(),
+                })
+                .with_span(field.span));
+            }
+            names.push(&field.name);
+        }
+
+        self.declare(
+            name,
+            Value::Struct(name.to_string(), fields.to_vec()),
+            &None,
+            DeclType::Immutable,
+        )
+    }
+
+    fn declare_enum(&mut self, name: &String, variants: &Vec<Variant>) -> Result<(), RuntimeError> {
+        let mut names: Vec<&String> = vec![];
+
+        for variant in variants {
+            if names.contains(&&variant.name) {
+                return Err(RuntimeError::new(ErrorKind::AlreadyDefined {
+                    name: variant.name.to_string(),
+                })
+                .with_span(variant.span));
+            }
+            names.push(&variant.name);
+        }
+
+        self.declare(
+            name,
+            Value::Enum(name.to_string(), variants.to_vec()),
+            &None,
+            DeclType::Immutable,
+        )
+    }
+
+    fn declare_trait(
+        &mut self,
+        name: &String,
+        methods: &Vec<MethodSig>,
+    ) -> Result<(), RuntimeError> {
+        let mut names: Vec<&String> = vec![];
+
+        for method in methods {
+            if names.contains(&&method.name) {
+                return Err(RuntimeError::new(ErrorKind::AlreadyDefined {
+                    name: method.name.to_string(),
+                })
+                .with_span(method.span));
+            }
+            names.push(&method.name);
+        }
+
+        self.declare(
+            name,
+            Value::Trait(name.to_string(), methods.to_vec()),
+            &None,
+            DeclType::Immutable,
+        )
+    }
+
+    /// checks that `methods` has every method of the trait with the same signature,
+    /// and records that `prototype` implements it
+    fn declare_impl(
+        &mut self,
+        prototype: &str,
+        trait_name: &String,
+        methods: &[Method],
+        span: Span,
+    ) -> Result<(), RuntimeError> {
+        let (name, required) = match self.get(trait_name) {
+            Some(Value::Trait(name, required)) => (name, required),
+            Some(_) => {
+                return Err(ErrorKind::NotATrait {
+                    name: trait_name.to_string(),
                 }
+                .into())
             }
-        } else {
-            let mut current_scope = self
-                .0
-                .last()
-                .expect("`ScopeStack` stack shouldn't be empty")
-                .lock()
-                .unwrap();
-
-            if current_scope.contains_key(name) {
-                return Err(format!("`{}` already define in this scope", name));
+            None => {
+                return Err(ErrorKind::UndefinedType {
+                    name: trait_name.to_string(),
+                }
+                .into())
             }
+        };
+
+        for sig in &required {
+            let method = match methods.iter().find(|m| m.name == sig.name) {
+                Some(method) => method,
+                None => {
+                    return Err(ErrorKind::MissingMethod {
+                        name: sig.name.to_string(),
+                        datatype: trait_name.to_string(),
+                    }
+                    .into())
+                }
+            };
 
-            if &extected_type != &value_type {
-                return Err(format!(
-                    "expected `{}` found `{}`",
-                    extected_type, value_type
-                ));
+            let expected = self.get_type_alias(&signature(&sig.args, &sig.ret_type))?;
+            let found = self.get_type_alias(&signature(&method.args, &method.ret_type))?;
+
+            if expected != found {
+                return Err(
+                    RuntimeError::new(ErrorKind::TypeMismatch { expected, found })
+                        .with_span(method.span),
+                );
             }
+        }
+
+        let mut modules = self.modules();
+        let traits = modules.impls.entry(prototype.to_string()).or_default();
 
-            current_scope.insert(name.to_string(), (value.clone(), decl_type, value_type));
+        if traits.get(&name).is_some_and(|declared| declared != &span) {
+            return Err(ErrorKind::DuplicateImpl {
+                name,
+                datatype: prototype.to_string(),
+            }
+            .into());
         }
 
+        traits.insert(name, span);
         Ok(())
     }
 
-    fn declare_type_alias(&mut self, type_name: &String, datatype: &Type) -> Result<(), String> {
+    /// whether `datatype` is a trait that the type of `value` implements
+    fn implements(&self, value: &Value, datatype: &Type) -> bool {
+        let name = match datatype {
+            Type::Alias(name) => match self.get_type(name) {
+                Some(Value::Trait(name, _)) => name,
+                _ => return false,
+            },
+            _ => return false,
+        };
+
+        self.modules()
+            .impls
+            .get(&Type::simple(value))
+            .is_some_and(|traits| traits.contains_key(&name))
+    }
+
+    fn declare_type_alias(
+        &mut self,
+        type_name: &String,
+        datatype: &Type,
+    ) -> Result<(), RuntimeError> {
         let mut current_scope = self
             .0
             .last()
@@ -501,7 +835,10 @@ impl ScopeStack {
             .unwrap();
 
         if current_scope.contains_key(type_name) {
-            return Err(format!("'{}' already define in this scope", type_name));
+            return Err(ErrorKind::AlreadyDefined {
+                name: type_name.to_string(),
+            }
+            .into());
         }
 
         current_scope.insert(
@@ -516,18 +853,29 @@ impl ScopeStack {
         Ok(())
     }
 
-    fn get_type_alias(&self, datatype: &Type) -> Result<Type, String> {
+    /// type of a runtime value with its aliases resolved
+    fn type_of(&self, value: &Value) -> Result<Type, RuntimeError> {
+        match value {
+            // structs and enums are typed by their name, which may live in another module
+            Value::Instance(name, _) | Value::Variant(name, ..) => {
+                Ok(Type::Alias(name.to_string()))
+            }
+            value => self.get_type_alias(&Type::from(value)),
+        }
+    }
+
+    fn get_type_alias(&self, datatype: &Type) -> Result<Type, RuntimeError> {
         match datatype {
             Type::Builtin(bt) => match bt {
                 BuiltinType::Fn(a, rt) => match *rt.clone() {
                     Type::Alias(t) => {
-                        return Ok(Type::Builtin(BuiltinType::Fn(
+                        Ok(Type::Builtin(BuiltinType::Fn(
                             a.clone(),
                             Box::new(self.get_type_alias(&Type::Alias(t))?),
                         )))
                     }
                     Type::Builtin(b) => {
-                        return Ok(Type::Builtin(BuiltinType::Fn(
+                        Ok(Type::Builtin(BuiltinType::Fn(
                             a.clone(),
                             Box::new(self.get_type_alias(&Type::Builtin(b))?),
                         )))
@@ -552,31 +900,36 @@ impl ScopeStack {
                     Ok(Type::Builtin(BuiltinType::Tuple(b_types)))
                 }
                 BuiltinType::List(data_type) => {
-                    return Ok(Type::Builtin(BuiltinType::List(Box::new(
-                        self.get_type_alias(&*data_type.clone())?,
+                    Ok(Type::Builtin(BuiltinType::List(Box::new(
+                        self.get_type_alias(&data_type.clone())?,
                     ))))
                 }
-                s => return Ok(Type::Builtin(s.clone())),
+                s => Ok(Type::Builtin(s.clone())),
             },
 
-            Type::Alias(tn) => match self.get(tn) {
+            Type::Alias(_) if datatype.is_opaque() => Ok(datatype.clone()),
+            Type::Alias(tn) => match self.get_type(tn) {
+                // structs, enums and traits are nominal, their name is the type
+                Some(Value::Struct(name, _))
+                | Some(Value::Enum(name, _))
+                | Some(Value::Trait(name, _)) => Ok(Type::Alias(name)),
                 Some(t) => match &Type::from(&t) {
                     Type::Alias(s) => {
-                        return self.get_type_alias(&Type::Alias(s.to_string()));
+                        self.get_type_alias(&Type::Alias(s.to_string()))
                     }
                     Type::Builtin(b) => match b {
                         BuiltinType::Fn(a, rt) => match *rt.clone() {
                             Type::Alias(t) => {
-                                return Ok(Type::Builtin(BuiltinType::Fn(
+                                Ok(Type::Builtin(BuiltinType::Fn(
                                     a.clone(),
                                     Box::new(self.get_type_alias(&Type::Alias(t))?),
-                                )));
+                                )))
                             }
                             Type::Builtin(b) => {
-                                return Ok(Type::Builtin(BuiltinType::Fn(
+                                Ok(Type::Builtin(BuiltinType::Fn(
                                     a.clone(),
                                     Box::new(self.get_type_alias(&Type::Builtin(b))?),
-                                )));
+                                )))
                             }
                         },
                         BuiltinType::Tuple(items) => {
@@ -601,14 +954,19 @@ impl ScopeStack {
                             Ok(Type::Builtin(BuiltinType::Tuple(b_types)))
                         }
                         BuiltinType::List(data_type) => {
-                            return Ok(Type::Builtin(BuiltinType::List(Box::new(
-                                self.get_type_alias(&*data_type.clone())?,
+                            Ok(Type::Builtin(BuiltinType::List(Box::new(
+                                self.get_type_alias(&data_type.clone())?,
                             ))))
                         }
-                        f => return Ok(Type::Builtin(f.clone())),
+                        f => Ok(Type::Builtin(f.clone())),
                     },
                 },
-                None => return Err(format!("type `{}` is not defined (10)", tn)),
+                None => {
+                    Err(ErrorKind::UndefinedType {
+                        name: tn.to_string(),
+                    }
+                    .into())
+                }
             },
         }
     }
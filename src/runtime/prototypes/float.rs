@@ -10,11 +10,11 @@ pub fn float_proto() -> HashMap<String, Value> {
 
     float_proto.insert(
         String::from("pow"),
-        Value::BuiltInMethod(_pow, None),
+        Value::native_method(_pow),
     );
     float_proto.insert(
         String::from("to_string"),
-        Value::BuiltInMethod(_to_string, None),
+        Value::native_method(_to_string),
     );
 
     float_proto
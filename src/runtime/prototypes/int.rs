@@ -1,4 +1,6 @@
+use crate::runtime::error::{ErrorKind, RuntimeError};
 use crate::runtime::value::Value;
+use crate::runtime::{Simple, Type};
 use std::collections::HashMap;
 
 pub use super::string::_to_string;
@@ -6,27 +8,44 @@ pub use super::string::_to_string;
 pub fn int_proto() -> HashMap<String, Value> {
     let mut int_proto = HashMap::new();
 
-    int_proto.insert(String::from("pow"), Value::BuiltInMethod(_pow, None));
-    int_proto.insert(String::from("to_string"), Value::BuiltInMethod(_to_string, None));
+    int_proto.insert(String::from("pow"), Value::native_method(_pow));
+    int_proto.insert(String::from("to_string"), Value::native_method(_to_string));
 
     int_proto
 }
 
-pub fn _pow(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    match vs.get(0) {
+pub fn _pow(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    match vs.first() {
         Some(value) => match value {
             Value::Int(n) => match this {
-                Value::Int(n2) => return Ok(Value::Float((n2 as f32).powi(*n))),
-                Value::Float(n2) => return Ok(Value::Float(n2.powi(*n))),
-                _ => return Err(format!("invalid this argument")),
+                Value::Int(n2) => Ok(Value::Float((n2 as f32).powi(*n))),
+                Value::Float(n2) => Ok(Value::Float(n2.powi(*n))),
+                _ => Err(ErrorKind::UndefinedProperty {
+                    name: "pow".to_string(),
+                    prototype: Type::simple(&this),
+                }
+                .into()),
             },
             Value::Float(n) => match this {
-                Value::Int(n2) => return Ok(Value::Float((n2 as f32).powf(*n))),
-                Value::Float(n2) => return Ok(Value::Float((n2 as f32).powf(*n))),
-                _ => return Err(format!("invalid this argument")),
+                Value::Int(n2) => Ok(Value::Float((n2 as f32).powf(*n))),
+                Value::Float(n2) => Ok(Value::Float(n2.powf(*n))),
+                _ => Err(ErrorKind::UndefinedProperty {
+                    name: "pow".to_string(),
+                    prototype: Type::simple(&this),
+                }
+                .into()),
             },
-            _ => return Err(format!("the first argument most be a number")),
+            _ => Err(ErrorKind::InvalidArgument {
+                position: 1,
+                expected: Type::Alias("number".to_string()),
+                found: Type::from(value),
+            }
+            .into()),
         },
-        None => Err(format!("the first argument is required")),
+        None => Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: 0,
+        }
+        .into()),
     }
 }
@@ -1,166 +1,415 @@
 use std::collections::HashMap;
 
+use crate::runtime::error::{ErrorKind, RuntimeError};
 use crate::runtime::value::Value;
-use crate::runtime::Type;
+use crate::runtime::value::{check_list_items, BuiltinType, NativeMethod};
+use crate::runtime::Simple;
+use crate::runtime::{Context, Type};
 
 use super::string::{_contains, _len, _to_string};
 
 pub fn list_proto() -> HashMap<String, Value> {
     let mut list_proto = HashMap::new();
 
-    list_proto.insert(String::from("push"), Value::BuiltInMethod(_push, None));
-    list_proto.insert(String::from("pop"), Value::BuiltInMethod(_pop, None));
-    list_proto.insert(String::from("at"), Value::BuiltInMethod(_at, None));
-    list_proto.insert(String::from("len"), Value::BuiltInMethod(_len, None));
-    list_proto.insert(String::from("rev"), Value::BuiltInMethod(_rev, None));
-    list_proto.insert(String::from("join"), Value::BuiltInMethod(_join, None));
-    list_proto.insert(String::from("clear"), Value::BuiltInMethod(_clear, None));
+    list_proto.insert(String::from("push"), Value::native_method(_push));
+    list_proto.insert(String::from("pop"), Value::native_method(_pop));
+    list_proto.insert(String::from("at"), Value::native_method(_at));
+    list_proto.insert(String::from("len"), Value::native_method(_len));
+    list_proto.insert(String::from("rev"), Value::native_method(_rev));
+    list_proto.insert(String::from("join"), Value::native_method(_join));
+    list_proto.insert(String::from("clear"), Value::native_method(_clear));
+    list_proto.insert(String::from("contains"), Value::native_method(_contains));
+    list_proto.insert(String::from("to_string"), Value::native_method(_to_string));
+
+    // methods that take a function
+    list_proto.insert(
+        String::from("map"),
+        Value::BuiltInMethod(NativeMethod::new(_map), None),
+    );
     list_proto.insert(
-        String::from("contains"),
-        Value::BuiltInMethod(_contains, None),
+        String::from("filter"),
+        Value::BuiltInMethod(NativeMethod::new(_filter), None),
     );
     list_proto.insert(
-        String::from("to_string"),
-        Value::BuiltInMethod(_to_string, None),
+        String::from("sort_by"),
+        Value::BuiltInMethod(NativeMethod::new(_sort_by), None),
+    );
+    list_proto.insert(
+        String::from("reduce"),
+        Value::BuiltInMethod(NativeMethod::new(_reduce), None),
     );
 
     list_proto
 }
 
-pub fn _at(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 1 || vs.len() < 1 {
-        return Err(format!("expected 1 argument, but found {}", vs.len()));
+pub fn _at(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if vs.len() > 1 || vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
-        Value::String(s) => match vs.get(0) {
+        Value::String(s) => match vs.first() {
             Some(value) => match value {
                 Value::Int(i) => {
                     if let Some(res) = s.chars().nth(*i as usize) {
-                        return Ok(Value::String(res.to_string()));
+                        Ok(Value::String(res.to_string()))
                     } else {
-                        return Err(format!("index out of bounds"));
+                        Err(ErrorKind::IndexOutOfBounds {
+                            index: *i,
+                            len: s.chars().count(),
+                        }
+                        .into())
                     }
                 }
-                _ => return Err(format!("the first argument most be an integer")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::Int),
+                    found: Type::from(value),
+                }
+                .into()),
             },
-            None => return Err(format!("expected 1 argument, but found {}", vs.len())),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into()),
         },
-        Value::List(l) | Value::Tuple(l) => match vs.get(0) {
-            Some(value) => match value {
-                Value::Int(i) => {
-                    if let Some(val) = l.get(*i as usize) {
-                        return Ok(Value::from(val));
-                    } else {
-                        return Err(format!("index out of bounds"));
+        Value::List(l, _) => item_at(&l.lock(), &vs),
+        Value::Tuple(l) => item_at(&l, &vs),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "at".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
+    }
+}
+
+fn item_at(items: &[Value], vs: &[Value]) -> Result<Value, RuntimeError> {
+    match vs.first() {
+        Some(value) => match value {
+            Value::Int(i) => {
+                if let Some(val) = items.get(*i as usize) {
+                    Ok(Value::from(val))
+                } else {
+                    Err(ErrorKind::IndexOutOfBounds {
+                        index: *i,
+                        len: items.len(),
                     }
+                    .into())
                 }
-                _ => return Err(format!("the first argument most be an integer")),
-            },
-            None => return Err(format!("expected 1 argument, but found {}", vs.len())),
+            }
+            _ => Err(ErrorKind::InvalidArgument {
+                position: 1,
+                expected: Type::Builtin(BuiltinType::Int),
+                found: Type::from(value),
+            }
+            .into()),
         },
-        _ => Err(format!(
-            "at() dose not exist in '{:?}' prototype",
-            String::from(Type::from(&this))
-        )),
+        None => Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: vs.len(),
+        }
+        .into()),
     }
 }
 
-pub fn _push(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 1 || vs.len() < 1 {
-        return Err(format!("expected 1 argument, but found {}", vs.len()));
+pub fn _push(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if vs.len() > 1 || vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
-        Value::List(list) => match vs.get(0) {
+        Value::List(list, item_type) => match vs.first() {
             Some(value) => {
-                let mut new_list = list;
-                new_list.push(value.clone());
-                return Ok(Value::List(new_list));
+                let datatype = Type::from(value);
+
+                // an empty list keeps the type of the items it held
+                if let Some(expected) = item_type.get() {
+                    if expected != &datatype {
+                        return Err(ErrorKind::TypeMismatch {
+                            expected: expected.clone(),
+                            found: datatype,
+                        }
+                        .into());
+                    }
+                }
+
+                item_type.set(datatype);
+                list.lock().push(value.clone());
+                Ok(Value::Null)
+            }
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
             }
-            None => return Err(format!("expected 1 argument, but found {}", vs.len())),
+            .into()),
         },
-        _ => {
-            return Err(format!(
-                "push() dose not exist in '{:?}' prototype",
-                String::from(Type::from(&this))
-            ))
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "push".to_string(),
+            prototype: Type::simple(&this),
         }
+        .into()),
     }
 }
 
-pub fn _pop(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 0 {
-        return Err(format!("expected 1 argument, but found {}", vs.len()));
+pub fn _pop(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if !vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
-        Value::List(list) => {
-            let mut new_list = list;
-            new_list.pop();
-            return Ok(Value::List(new_list));
-        }
-        _ => {
-            return Err(format!(
-                "pop() dose not exist in '{:?}' prototype",
-                String::from(Type::from(&this))
-            ))
+        // the removed item, `null` when the list is empty
+        Value::List(list, _) => return Ok(list.lock().pop().unwrap_or(Value::Null)),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "pop".to_string(),
+            prototype: Type::simple(&this),
         }
+        .into()),
     }
 }
 
-pub fn _rev(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 0 {
-        return Err(format!("expected 0 argument, but found {}", vs.len()));
+pub fn _rev(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if !vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 0,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
-        Value::List(list) => Ok(Value::List(list.into_iter().rev().collect())),
-        _ => Err(format!(
-            "rev() dose not exist in {:?} prototype",
-            String::from(Type::from(&this))
+        Value::List(list, item_type) => Ok(Value::list_of(
+            list.get().into_iter().rev().collect(),
+            item_type.get(),
         )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "rev".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _join(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 1 || vs.len() < 1 {
-        return Err(format!("expected 1 argument, but found {}", vs.len()));
+pub fn _join(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if vs.len() > 1 || vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
-        Value::List(list) => match vs.get(0) {
+        Value::List(list, _) => match vs.first() {
             Some(value) => match value {
                 Value::String(s) => {
                     let mut string_list: Vec<String> = vec![];
-                    for i in list {
+                    for i in list.get() {
                         string_list.push(i.to_string());
                     }
                     let joined = string_list.join(s);
-                    return Ok(Value::String(joined));
+                    Ok(Value::String(joined))
+                }
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(value),
                 }
-                _ => return Err(format!("the first argument most be a string")),
+                .into()),
             },
-            None => Err(format!("expected 1 argument, but found {}", vs.len())),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into()),
         },
-        _ => Err(format!(
-            "join() dose not exist in '{:?}' prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "join".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
+    }
+}
+
+pub fn _clear(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if !vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 0,
+            found: vs.len(),
+        }
+        .into());
+    }
+
+    match this {
+        Value::List(list, _) => {
+            list.lock().clear();
+            Ok(Value::Null)
+        }
+        Value::Object(props) => {
+            props.lock().clear();
+            Ok(Value::Null)
+        }
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "clear".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
+    }
+}
+
+pub fn _map(ctx: &Context, vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if vs.len() != 1 {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: vs.len(),
+        }
+        .into());
+    }
+
+    match this {
+        Value::List(list, _) => {
+            let mut res = vec![];
+            for item in list.get() {
+                res.push(ctx.call(&vs[0], vec![item])?);
+            }
+
+            check_list_items(&res)?;
+            Ok(Value::list(res))
+        }
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "map".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
+    }
+}
+
+pub fn _filter(ctx: &Context, vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if vs.len() != 1 {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: vs.len(),
+        }
+        .into());
+    }
+
+    match this {
+        Value::List(list, item_type) => {
+            let mut res = vec![];
+            for item in list.get() {
+                match ctx.call(&vs[0], vec![item.clone()])? {
+                    Value::Bool(true) => res.push(item),
+                    Value::Bool(false) => {}
+                    value => {
+                        return Err(ErrorKind::TypeMismatch {
+                            expected: Type::Builtin(BuiltinType::Bool),
+                            found: Type::from(&value),
+                        }
+                        .into())
+                    }
+                }
+            }
+
+            // a filter that keeps nothing gives a list of the same type
+            Ok(Value::list_of(res, item_type.get()))
+        }
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "filter".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _clear(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 0 {
-        return Err(format!("expected 0 argument, but found {}", vs.len()));
+/// the function compares two items, a negative int puts the first one before the second
+pub fn _sort_by(ctx: &Context, vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if vs.len() != 1 {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
-        Value::List(_) => Ok(Value::List(vec![])),
-        Value::Object(_) => Ok(Value::Object(vec![])),
-        _ => Err(format!(
-            "clear() dose not exist in {:?} prototype",
-            String::from(Type::from(&this))
+        Value::List(list, item_type) => Ok(Value::list_of(
+            merge_sort(ctx, &vs[0], list.get())?,
+            item_type.get(),
         )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "sort_by".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
+    }
+}
+
+pub fn _reduce(ctx: &Context, vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if vs.len() != 2 {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 2,
+            found: vs.len(),
+        }
+        .into());
     }
+
+    match this {
+        Value::List(list, _) => {
+            let mut acc = vs[1].clone();
+            for item in list.get() {
+                acc = ctx.call(&vs[0], vec![acc, item])?;
+            }
+
+            Ok(acc)
+        }
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "reduce".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
+    }
+}
+
+// `slice::sort_by` can't stop at an error of the comparison and may panic
+// when a script function isn't a total order, so lists are merged by hand
+fn merge_sort(ctx: &Context, f: &Value, list: Vec<Value>) -> Result<Vec<Value>, RuntimeError> {
+    if list.len() < 2 {
+        return Ok(list);
+    }
+
+    let mut left = list;
+    let right = left.split_off(left.len() / 2);
+    let mut left = merge_sort(ctx, f, left)?.into_iter().peekable();
+    let mut right = merge_sort(ctx, f, right)?.into_iter().peekable();
+
+    let mut res = vec![];
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        match ctx.call(f, vec![a.clone(), b.clone()])? {
+            Value::Int(n) if n > 0 => res.extend(right.next()),
+            Value::Int(_) => res.extend(left.next()),
+            value => {
+                return Err(ErrorKind::TypeMismatch {
+                    expected: Type::Builtin(BuiltinType::Int),
+                    found: Type::from(&value),
+                }
+                .into())
+            }
+        }
+    }
+
+    res.extend(left);
+    res.extend(right);
+    Ok(res)
 }
@@ -12,6 +12,12 @@ pub mod tuple;
 
 pub struct Prototypes(HashMap<String, HashMap<String, Value>>);
 
+impl Default for Prototypes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Prototypes {
     pub fn new() -> Self {
         Self(HashMap::new())
@@ -25,10 +31,10 @@ impl Prototypes {
         proto.declare("list".to_string(), list::list_proto());
         proto.declare("float".to_string(), float::float_proto());
         proto.declare("null".to_string(), null::null_proto());
-        // proto.declare(ValueType::Object, object::object_proto());
+        proto.declare("object".to_string(), object::object_proto());
         proto.declare("tuple".to_string(), tuple::tuple_proto());
 
-        return proto.items();
+        proto.items()
     }
 
     pub fn declare(&mut self, t: String, proto: HashMap<String, Value>) {
@@ -36,6 +42,6 @@ impl Prototypes {
     }
 
     fn items(self) -> HashMap<String, HashMap<String, Value>> {
-        return self.0;
+        self.0
     }
 }
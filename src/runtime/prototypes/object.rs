@@ -1,182 +1,250 @@
-use crate::runtime::value::{KeyValue, Value};
+use crate::runtime::context::Context;
+use crate::runtime::error::{ErrorKind, RuntimeError};
+use crate::runtime::value::BuiltinType;
+use crate::runtime::value::{KeyValue, NativeMethod, Value};
+use crate::runtime::Simple;
 use crate::runtime::Type;
 use std::collections::HashMap;
 
 pub use super::list::_clear;
-pub use super::string::{_contains, _to_string};
+pub use super::string::_contains;
 
 pub fn object_proto() -> HashMap<String, Value> {
     let mut object_proto = HashMap::new();
 
-    object_proto.insert(String::from("get"), Value::BuiltInMethod(_obj_get, None));
-    object_proto.insert(String::from("set"), Value::BuiltInMethod(_obj_set, None));
-    object_proto.insert(String::from("keys"), Value::BuiltInMethod(_obj_keys, None));
+    object_proto.insert(String::from("get"), Value::native_method(_obj_get));
     object_proto.insert(
-        String::from("values"),
-        Value::BuiltInMethod(_obj_values, None),
+        String::from("set"),
+        Value::BuiltInMethod(NativeMethod::new(_obj_set), None),
     );
-    object_proto.insert(
-        String::from("remove"),
-        Value::BuiltInMethod(_obj_remove, None),
-    );
-    object_proto.insert(
-        String::from("contains"),
-        Value::BuiltInMethod(_contains, None),
-    );
-    object_proto.insert(String::from("clear"), Value::BuiltInMethod(_clear, None));
+    object_proto.insert(String::from("keys"), Value::native_method(_obj_keys));
+    object_proto.insert(String::from("values"), Value::native_method(_obj_values));
+    object_proto.insert(String::from("remove"), Value::native_method(_obj_remove));
+    object_proto.insert(String::from("contains"), Value::native_method(_contains));
+    object_proto.insert(String::from("clear"), Value::native_method(_clear));
 
     object_proto
 }
 
-pub fn _obj_get(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 1 || vs.len() < 1 {
-        return Err(format!("expected 1 argument, but found {}", vs.len()));
+/// a key written into an object can't hide a method of the object prototype,
+/// including the methods the host registered
+pub fn check_key(
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    name: &str,
+) -> Result<(), RuntimeError> {
+    let reserved = match prototypes.get("object") {
+        Some(proto) => proto.contains_key(name),
+        None => object_proto().contains_key(name),
+    };
+
+    if reserved {
+        return Err(ErrorKind::ReservedProperty {
+            name: name.to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+pub fn _obj_get(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if vs.len() > 1 || vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
-        Value::Object(props) => match vs.get(0) {
+        Value::Object(props) => match vs.first() {
             Some(value) => match value {
                 Value::String(s) => {
-                    let prop = props.into_iter().find(|kv| &kv.key == s);
+                    let prop = props.get().into_iter().find(|kv| &kv.key == s);
                     match prop {
-                        Some(kv) => return Ok(kv.value),
-                        None => return Err(format!("property '{}' not found", s)),
+                        Some(kv) => Ok(kv.value),
+                        None => Err(ErrorKind::UndefinedProperty {
+                            name: s.to_string(),
+                            prototype: "object".to_string(),
+                        }
+                        .into()),
                     }
                 }
-                _ => return Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(value),
+                }
+                .into()),
             },
-            None => Err(format!("expected 1 argument, but found {}", vs.len())),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into()),
         },
-        _ => Err(format!(
-            "get() dose not exist in '{:?}' prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "get".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _obj_set(vs: Vec<Value>, this: Value) -> Result<Value, String> {
+pub fn _obj_set(ctx: &Context, vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
     if vs.len() > 2 || vs.len() < 2 {
-        return Err(format!("expected 2 argument, but found {}", vs.len()));
+        return Err(ErrorKind::ArityMismatch {
+            expected: 2,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
-        Value::Object(obj) => match vs.get(0) {
+        Value::Object(obj) => match vs.first() {
             Some(v1) => match v1 {
                 Value::String(arg1) => match vs.get(1) {
                     Some(arg2) => {
-                        let mut new_obj: Vec<KeyValue> = vec![];
-                        let mut keys: Vec<String> = vec![];
-
-                        for prop in &obj {
-                            keys.push(prop.key.to_string());
-                        }
+                        check_key(ctx.prototypes(), arg1)?;
 
-                        if !keys.contains(arg1) {
-                            new_obj.push(KeyValue {
-                                key: arg1.to_string(),
-                                value: arg2.clone(),
-                            });
-                        }
+                        let mut obj = obj.lock();
 
-                        for prop in &obj {
-                            if &prop.key == arg1 {
-                                new_obj.push(KeyValue {
-                                    key: prop.key.to_string(),
+                        match obj.iter_mut().find(|prop| &prop.key == arg1) {
+                            Some(prop) => prop.value = arg2.clone(),
+                            // new keys come first
+                            None => obj.insert(
+                                0,
+                                KeyValue {
+                                    key: arg1.to_string(),
                                     value: arg2.clone(),
-                                });
-                            } else {
-                                new_obj.push(prop.clone());
-                            }
+                                },
+                            ),
                         }
-                        Ok(Value::Object(new_obj))
+                        Ok(Value::Null)
+                    }
+                    None => Err(ErrorKind::ArityMismatch {
+                        expected: 2,
+                        found: vs.len(),
                     }
-                    None => Err(format!("expected 2 argument, but found {}", vs.len())),
+                    .into()),
                 },
-                _ => Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(v1),
+                }
+                .into()),
             },
-            None => Err(format!("expected 2 argument, but found {}", vs.len())),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 2,
+                found: vs.len(),
+            }
+            .into()),
         },
-        _ => Err(format!(
-            "set() dose not exist in '{:?}' prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "set".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _obj_keys(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 0 {
-        return Err(format!("expected 0 argument, but found {}", vs.len()));
+pub fn _obj_keys(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if !vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 0,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
         Value::Object(props) => {
             let mut keys: Vec<Value> = vec![];
 
-            for prop in props {
+            for prop in props.get() {
                 keys.push(Value::String(prop.key))
             }
 
-            Ok(Value::List(keys))
+            Ok(Value::list(keys))
         }
-        _ => Err(format!(
-            "keys() dose not exist in {:?} prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "keys".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _obj_values(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 0 {
-        return Err(format!("expected 0 argument, but found {}", vs.len()));
+pub fn _obj_values(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if !vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 0,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
         Value::Object(props) => {
             let mut values: Vec<Value> = vec![];
 
-            for prop in props {
+            for prop in props.get() {
                 values.push(prop.value);
             }
 
-            Ok(Value::List(values))
+            Ok(Value::list(values))
         }
-        _ => Err(format!(
-            "values() dose not exist in {:?} prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "values".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _obj_remove(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 1 || vs.len() < 1 {
-        return Err(format!("expected 1 argument, but found {}", vs.len()));
+pub fn _obj_remove(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if vs.len() > 1 || vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
-        Value::Object(obj) => match vs.get(0) {
+        Value::Object(obj) => match vs.first() {
             Some(value) => match value {
                 Value::String(s) => {
-                    let mut keys: Vec<Value> = vec![];
-
-                    for prop in &obj {
-                        keys.push(Value::String(prop.key.to_string()));
-                    }
+                    let mut obj = obj.lock();
 
-                    if keys.contains(&Value::String(s.to_string())) {
-                        let new_obj: Vec<KeyValue> =
-                            obj.into_iter().filter(|kv| &kv.key != s).collect();
-                        return Ok(Value::Object(new_obj));
+                    // the removed value
+                    if let Some(i) = obj.iter().position(|kv| &kv.key == s) {
+                        Ok(obj.remove(i).value)
                     } else {
-                        return Err(format!("property '{}' is not defind", s));
+                        Err(ErrorKind::UndefinedProperty {
+                            name: s.to_string(),
+                            prototype: "object".to_string(),
+                        }
+                        .into())
                     }
                 }
-                _ => return Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(value),
+                }
+                .into()),
             },
-            None => Err(format!("expected 1 argument, but found {}", vs.len())),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into()),
         },
-        _ => Err(format!(
-            "get() dose not exist in '{:?}' prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "get".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
@@ -1,68 +1,64 @@
 use std::collections::HashMap;
 use std::str::FromStr;
 
-use crate::runtime::{
-    value::{value_list, Value},
-    Type,
-};
+use crate::runtime::error::{ErrorKind, RuntimeError};
+use crate::runtime::value::{value_list, BuiltinType, Value};
+use crate::runtime::{Simple, Type};
 
 use super::list::_at;
 
 pub fn string_proto() -> HashMap<String, Value> {
     let mut string_proto = HashMap::new();
 
-    string_proto.insert(String::from("len"), Value::BuiltInMethod(_len, None));
-    string_proto.insert(
-        String::from("to_string"),
-        Value::BuiltInMethod(_to_string, None),
-    );
-    string_proto.insert(String::from("at"), Value::BuiltInMethod(_at, None));
-    string_proto.insert(String::from("chars"), Value::BuiltInMethod(_chars, None));
-    string_proto.insert(String::from("split"), Value::BuiltInMethod(_split, None));
-    string_proto.insert(String::from("to_upper"), Value::BuiltInMethod(_upper, None));
-    string_proto.insert(String::from("to_lower"), Value::BuiltInMethod(_lower, None));
-    string_proto.insert(String::from("trim"), Value::BuiltInMethod(_trim, None));
-    string_proto.insert(String::from("lines"), Value::BuiltInMethod(_lines, None));
+    string_proto.insert(String::from("len"), Value::native_method(_len));
+    string_proto.insert(String::from("to_string"), Value::native_method(_to_string));
+    string_proto.insert(String::from("at"), Value::native_method(_at));
+    string_proto.insert(String::from("chars"), Value::native_method(_chars));
+    string_proto.insert(String::from("split"), Value::native_method(_split));
+    string_proto.insert(String::from("to_upper"), Value::native_method(_upper));
+    string_proto.insert(String::from("to_lower"), Value::native_method(_lower));
+    string_proto.insert(String::from("trim"), Value::native_method(_trim));
+    string_proto.insert(String::from("lines"), Value::native_method(_lines));
     string_proto.insert(
         String::from("to_numeric"),
-        Value::BuiltInMethod(_to_numeric, None),
-    );
-    string_proto.insert(
-        String::from("is_ascii"),
-        Value::BuiltInMethod(_is_ascii, None),
+        Value::native_method(_to_numeric),
     );
-    string_proto.insert(
-        String::from("contains"),
-        Value::BuiltInMethod(_contains, None),
-    );
-    string_proto.insert(String::from("repeat"), Value::BuiltInMethod(_repeat, None));
-    string_proto.insert(
-        String::from("replace"),
-        Value::BuiltInMethod(_replace, None),
-    );
-    string_proto.insert(String::from("push"), Value::BuiltInMethod(_push, None));
+    string_proto.insert(String::from("is_ascii"), Value::native_method(_is_ascii));
+    string_proto.insert(String::from("contains"), Value::native_method(_contains));
+    string_proto.insert(String::from("repeat"), Value::native_method(_repeat));
+    string_proto.insert(String::from("replace"), Value::native_method(_replace));
+    string_proto.insert(String::from("push"), Value::native_method(_push));
 
     string_proto
 }
 
-pub fn _len(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 0 {
-        return Err(format!("expected 0 argument, but found {}", vs.len()));
+pub fn _len(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if !vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 0,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
         Value::String(s) => Ok(Value::Int(s.len() as i32)),
-        Value::List(l) => Ok(Value::Int(l.len() as i32)),
-        _ => Err(format!(
-            "len dose not exist in {:?} prototype",
-            String::from(Type::from(&this))
-        )),
+        Value::List(l, _) => Ok(Value::Int(l.lock().len() as i32)),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "len".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _to_string(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 0 {
-        return Err(format!("expected 0 argument, but found {}", vs.len()));
+pub fn _to_string(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if !vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 0,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
@@ -70,20 +66,25 @@ pub fn _to_string(vs: Vec<Value>, this: Value) -> Result<Value, String> {
         Value::Int(n) => Ok(Value::String(n.to_string())),
         Value::Float(n) => Ok(Value::String(n.to_string())),
         Value::String(s) => Ok(Value::String(s.to_string())),
-        Value::List(l) => {
-            let list = value_list(l.to_vec());
-            Ok(Value::String("[".to_string() + &list + &"]"))
-        }
-        _ => Err(format!(
-            "to_string dose not exist in {:?} prototype",
-            String::from(Type::from(&this))
-        )),
+        Value::List(l, _) => {
+            let list = value_list(l.get());
+            Ok(Value::String("[".to_string() + &list + "]"))
+        }
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "to_string".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _chars(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 0 {
-        return Err(format!("expected 0 argument, but found {}", vs.len()));
+pub fn _chars(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if !vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 0,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
@@ -92,18 +93,23 @@ pub fn _chars(vs: Vec<Value>, this: Value) -> Result<Value, String> {
             for char in s.chars() {
                 chars.push(Value::String(char.to_string()));
             }
-            Ok(Value::List(chars))
+            Ok(Value::list(chars))
+        }
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "to_string".to_string(),
+            prototype: Type::simple(&this),
         }
-        _ => Err(format!(
-            "to_string dose not exist in {:?} prototype",
-            String::from(Type::from(&this))
-        )),
+        .into()),
     }
 }
 
-pub fn _to_numeric(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 0 {
-        return Err(format!("expected 0 argument, but found {}", vs.len()));
+pub fn _to_numeric(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if !vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 0,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
@@ -112,19 +118,28 @@ pub fn _to_numeric(vs: Vec<Value>, this: Value) -> Result<Value, String> {
 
             match res {
                 Ok(n) => Ok(Value::Int(n)),
-                Err(e) => Err(e.to_string()),
+                Err(_) => Err(ErrorKind::InvalidConversion {
+                    value: s,
+                    to: Type::Builtin(BuiltinType::Int),
+                }
+                .into()),
             }
         }
-        _ => Err(format!(
-            "to_string dose not exist in {:?} prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "to_string".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _lines(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 0 {
-        return Err(format!("expected 0 argument, but found {}", vs.len()));
+pub fn _lines(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if !vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 0,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
@@ -133,22 +148,27 @@ pub fn _lines(vs: Vec<Value>, this: Value) -> Result<Value, String> {
             for line in s.lines() {
                 lines.push(Value::String(line.to_string()));
             }
-            Ok(Value::List(lines))
+            Ok(Value::list(lines))
+        }
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "to_string".to_string(),
+            prototype: Type::simple(&this),
         }
-        _ => Err(format!(
-            "to_string dose not exist in {:?} prototype",
-            String::from(Type::from(&this))
-        )),
+        .into()),
     }
 }
 
-pub fn _split(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 1 || vs.len() < 1 {
-        return Err(format!("expected 1 argument, but found {}", vs.len()));
+pub fn _split(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if vs.len() > 1 || vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
-        Value::String(s) => match vs.get(0) {
+        Value::String(s) => match vs.first() {
             Some(value) => match value {
                 Value::String(val) => {
                     let mut res: Vec<Value> = vec![];
@@ -156,180 +176,288 @@ pub fn _split(vs: Vec<Value>, this: Value) -> Result<Value, String> {
                         res.push(Value::String(i.to_string()));
                     }
 
-                    return Ok(Value::List(res));
+                    Ok(Value::list(res))
+                }
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(value),
                 }
-                _ => return Err(format!("the first argument most be a string")),
+                .into()),
             },
-            None => Err(format!("expected 1 argument, but found {}", vs.len())),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into()),
         },
-        _ => Err(format!(
-            "split() dose not exist in '{:?}' prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "split".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _upper(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 0 {
-        return Err(format!("expected 0 argument, but found {}", vs.len()));
+pub fn _upper(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if !vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 0,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
         Value::String(s) => Ok(Value::String(s.to_uppercase())),
-        _ => Err(format!(
-            "upper() dose not exist in {:?} prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "upper".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _lower(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 0 {
-        return Err(format!("expected 0 argument, but found {}", vs.len()));
+pub fn _lower(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if !vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 0,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
         Value::String(s) => Ok(Value::String(s.to_lowercase())),
-        _ => Err(format!(
-            "lower() dose not exist in {:?} prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "lower".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _trim(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 0 {
-        return Err(format!("expected 0 argument, but found {}", vs.len()));
+pub fn _trim(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if !vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 0,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
         Value::String(s) => Ok(Value::String(s.trim().to_string())),
-        _ => Err(format!(
-            "trim() dose not exist in {:?} prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "trim".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _contains(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 1 || vs.len() < 1 {
-        return Err(format!("expected 1 argument, but found {}", vs.len()));
+pub fn _contains(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if vs.len() > 1 || vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
-        Value::String(s) => match vs.get(0) {
+        Value::String(s) => match vs.first() {
             Some(value) => match value {
-                Value::String(val) => return Ok(Value::Bool(s.contains(val))),
-                _ => return Err(format!("the first argument most be a string")),
+                Value::String(val) => Ok(Value::Bool(s.contains(val))),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(value),
+                }
+                .into()),
             },
-            None => Err(format!("expected 1 argument, but found {}", vs.len())),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into()),
         },
-        Value::List(list) => match vs.get(0) {
-            Some(value) => Ok(Value::Bool(list.contains(&value))),
-            None => Err(format!("expected 1 argument, but found {}", vs.len())),
+        Value::List(list, _) => match vs.first() {
+            Some(value) => Ok(Value::Bool(list.lock().contains(value))),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into()),
         },
-        Value::Object(obj) => match vs.get(0) {
+        Value::Object(obj) => match vs.first() {
             Some(value) => match value {
                 Value::String(s) => {
                     let mut keys: Vec<Value> = vec![];
 
-                    for prop in obj {
-                        keys.push(Value::String(prop.key))
+                    for prop in obj.lock().iter() {
+                        keys.push(Value::String(prop.key.to_string()))
                     }
 
                     Ok(Value::Bool(keys.contains(&Value::String(s.to_string()))))
                 }
-                _ => return Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(value),
+                }
+                .into()),
             },
-            None => Err(format!("expected 1 argument, but found {}", vs.len())),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into()),
         },
-        _ => Err(format!(
-            "contains() dose not exist in '{:?}' prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "contains".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _is_ascii(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 0 {
-        return Err(format!("expected 0 argument, but found {}", vs.len()));
+pub fn _is_ascii(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if !vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 0,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
         Value::String(s) => Ok(Value::Bool(s.is_ascii())),
-        _ => Err(format!(
-            "len dose not exist in {:?} prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "len".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _repeat(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 1 || vs.len() < 1 {
-        return Err(format!("expected 1 argument, but found {}", vs.len()));
+pub fn _repeat(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if vs.len() > 1 || vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
-        Value::String(s) => match vs.get(0) {
+        Value::String(s) => match vs.first() {
             Some(value) => match value {
-                Value::Int(val) => return Ok(Value::String(s.repeat(*val as usize))),
-                _ => return Err(format!("the first argument most be a integer")),
+                Value::Int(val) => Ok(Value::String(s.repeat(*val as usize))),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::Int),
+                    found: Type::from(value),
+                }
+                .into()),
             },
-            None => Err(format!("expected 1 argument, but found {}", vs.len())),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into()),
         },
-        _ => Err(format!(
-            "split() dose not exist in '{:?}' prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "split".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _replace(vs: Vec<Value>, this: Value) -> Result<Value, String> {
+pub fn _replace(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
     if vs.len() > 2 || vs.len() < 2 {
-        return Err(format!("expected 2 argument, but found {}", vs.len()));
+        return Err(ErrorKind::ArityMismatch {
+            expected: 2,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
-        Value::String(s) => match vs.get(0) {
+        Value::String(s) => match vs.first() {
             Some(v1) => match v1 {
                 Value::String(from) => match vs.get(1) {
                     Some(v2) => match v2 {
                         Value::String(to) => Ok(Value::String(s.replace(from, to))),
-                        _ => Err(format!("the seconde argument most be a string")),
+                        _ => Err(ErrorKind::InvalidArgument {
+                            position: 2,
+                            expected: Type::Builtin(BuiltinType::String),
+                            found: Type::from(v2),
+                        }
+                        .into()),
                     },
-                    None => Err(format!("expected 2 argument, but found {}", vs.len())),
+                    None => Err(ErrorKind::ArityMismatch {
+                        expected: 2,
+                        found: vs.len(),
+                    }
+                    .into()),
                 },
-                _ => Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(v1),
+                }
+                .into()),
             },
-            None => Err(format!("expected 2 argument, but found {}", vs.len())),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 2,
+                found: vs.len(),
+            }
+            .into()),
         },
-        _ => Err(format!(
-            "split() dose not exist in '{:?}' prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "split".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
 
-pub fn _push(vs: Vec<Value>, this: Value) -> Result<Value, String> {
-    if vs.len() > 1 || vs.len() < 1 {
-        return Err(format!("expected 1 argument, but found {}", vs.len()));
+pub fn _push(vs: Vec<Value>, this: Value) -> Result<Value, RuntimeError> {
+    if vs.len() > 1 || vs.is_empty() {
+        return Err(ErrorKind::ArityMismatch {
+            expected: 1,
+            found: vs.len(),
+        }
+        .into());
     }
 
     match this {
-        Value::String(s) => match vs.get(0) {
+        Value::String(s) => match vs.first() {
             Some(value) => match value {
                 Value::String(val) => {
                     let mut res = s;
-                    res.push_str(&val);
-                    return Ok(Value::String(res));
+                    res.push_str(val);
+                    Ok(Value::String(res))
                 }
-                _ => return Err(format!("the first argument most be a string")),
+                _ => Err(ErrorKind::InvalidArgument {
+                    position: 1,
+                    expected: Type::Builtin(BuiltinType::String),
+                    found: Type::from(value),
+                }
+                .into()),
             },
-            None => Err(format!("expected 1 argument, but found {}", vs.len())),
+            None => Err(ErrorKind::ArityMismatch {
+                expected: 1,
+                found: vs.len(),
+            }
+            .into()),
         },
-        _ => Err(format!(
-            "split() dose not exist in '{:?}' prototype",
-            String::from(Type::from(&this))
-        )),
+        _ => Err(ErrorKind::UndefinedProperty {
+            name: "split".to_string(),
+            prototype: Type::simple(&this),
+        }
+        .into()),
     }
 }
@@ -0,0 +1,97 @@
+use std::cell::RefCell;
+
+use crate::span::Span;
+
+use super::error::{ErrorKind, RuntimeError};
+
+/// how many calls can be running at once, deeper recursion fails instead of
+/// growing the vm frames and the stack of the tree-walker without bound
+pub const MAX_DEPTH: usize = 1000;
+
+// a call that finds less stack than this left runs on a new segment of `STACK_SEGMENT` bytes
+const RED_ZONE: usize = 512 * 1024;
+const STACK_SEGMENT: usize = 8 * 1024 * 1024;
+
+thread_local! {
+    static CALL_STACK: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub function: String,
+    pub module: Option<String>,
+    pub call_site: Span,
+    pub native: bool,
+}
+
+impl Frame {
+    pub fn new(function: String, module: Option<String>, call_site: Span, native: bool) -> Self {
+        Self {
+            function,
+            module,
+            call_site,
+            native,
+        }
+    }
+
+    /// function name qualified with its module path, e.g. `std::fs::read_file`
+    pub fn name(&self) -> String {
+        match &self.module {
+            Some(module) => format!("{}::{}", module, self.function),
+            None => self.function.clone(),
+        }
+    }
+}
+
+/// pops the frame it was created for when dropped, so `?` can't leave stale frames behind
+pub struct FrameGuard;
+
+impl Drop for FrameGuard {
+    fn drop(&mut self) {
+        CALL_STACK.with(|stack| stack.borrow_mut().pop());
+    }
+}
+
+pub fn enter(frame: Frame) -> Result<FrameGuard, RuntimeError> {
+    CALL_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        if stack.len() >= MAX_DEPTH {
+            return Err(RuntimeError::new(ErrorKind::StackOverflow {
+                depth: MAX_DEPTH,
+            }));
+        }
+
+        stack.push(frame);
+        Ok(FrameGuard)
+    })
+}
+
+/// runs `f`, on a new piece of stack when the current one is nearly used up. the tree-walker
+/// and natives calling back into scripts recurse on the rust stack, this lets them reach
+/// `MAX_DEPTH` on a thread of any size
+pub fn grow<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, f)
+}
+
+/// snapshot of the call stack, innermost frame first
+pub fn capture() -> Vec<Frame> {
+    CALL_STACK.with(|stack| stack.borrow().iter().rev().cloned().collect())
+}
+
+/// pairs every script function on the stack with the position it is currently at.
+/// `position` is where the innermost frame is executing, each outer frame is at the
+/// call site of the frame above it. native frames are hidden but still move the position.
+pub fn resolve(frames: &[Frame], position: Option<Span>) -> Vec<(String, Option<Span>)> {
+    let mut res = vec![];
+    let mut position = position;
+
+    for frame in frames {
+        if !frame.native {
+            res.push((frame.name(), position));
+        }
+        position = Some(frame.call_site);
+    }
+
+    res.push((String::from("<main>"), position));
+    res
+}
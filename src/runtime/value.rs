@@ -1,10 +1,15 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
-use std::fmt::Display;
-use std::ops::{Add, Div, Mul, Not, Sub};
+use std::fmt::{self, Display};
+use std::ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Neg, Not, Rem, Shl, Shr, Sub};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
 
-use crate::ast::{Arg, Block};
+use crate::ast::{Align, Arg, Block, Field, FormatSpec, Fragment, MethodSig, Variant};
 
-use super::{Simple, Type};
+use super::error::{ErrorKind, RuntimeError};
+use super::vm::Closure;
+use super::{Context, ScopeStack, Simple, Type};
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum Value {
@@ -13,17 +18,214 @@ pub enum Value {
     Float(f32),
     String(String),
     Bool(bool),
-    List(Vec<Value>),
-    Object(Vec<KeyValue>),
-    BuiltInFn(fn(Vec<Value>) -> Result<Value, String>),
-    BuiltInMethod(
-        fn(Vec<Value>, Value) -> Result<Value, String>,
-        Option<Box<Value>>,
-    ),
-    Func(Vec<Arg>, Option<Type>, Block),
-    Module(BTreeMap<String, Value>),
+    List(Shared<Vec<Value>>, ItemType),
+    Object(Shared<Vec<KeyValue>>),
+    BuiltInFn(NativeFn),
+    BuiltInMethod(NativeMethod, Option<Box<Value>>),
+    // the last item is the name it was declared with, `None` for `fn` expressions
+    Func(Vec<Arg>, Option<Type>, Block, ScopeStack, Option<String>),
+    // a function compiled for the vm
+    Closure(Arc<Closure>),
+    // exported items and the names of the private ones
+    Module(BTreeMap<String, Value>, Vec<String>),
     Tuple(Vec<Value>),
     Type(String, Type),
+    Struct(String, Vec<Field>),
+    Instance(String, Vec<KeyValue>),
+    Enum(String, Vec<Variant>),
+    Variant(String, String, Vec<Value>),
+    Trait(String, Vec<MethodSig>),
+}
+
+/// a list or object that every copy of the value points to,
+/// a change made through one copy is seen through all of them
+pub struct Shared<T>(Arc<Mutex<T>>);
+
+impl<T> Shared<T> {
+    pub fn new(value: T) -> Self {
+        Self(Arc::new(Mutex::new(value)))
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        self.0.lock().unwrap()
+    }
+}
+
+impl<T: Clone> Shared<T> {
+    /// a copy of the items, changing it doesn't change the shared value
+    pub fn get(&self) -> T {
+        self.lock().clone()
+    }
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// the type of the items of a list. it is fixed by the first item or by the type the list is
+/// declared with, and stays when the list is emptied
+#[derive(Debug, Clone, Default)]
+pub struct ItemType(Arc<OnceLock<Type>>);
+
+impl ItemType {
+    pub fn get(&self) -> Option<&Type> {
+        self.0.get()
+    }
+
+    /// fixes the type of the items, a type that is already fixed doesn't change
+    pub fn set(&self, datatype: Type) {
+        let _ = self.0.set(datatype);
+    }
+}
+
+// lists are compared by their items
+impl PartialEq for ItemType {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl PartialOrd for ItemType {
+    fn partial_cmp(&self, _: &Self) -> Option<Ordering> {
+        Some(Ordering::Equal)
+    }
+}
+
+thread_local! {
+    // the pairs of containers being compared, a container that holds itself comes back to them
+    static COMPARING: RefCell<Vec<(usize, usize)>> = const { RefCell::new(Vec::new()) };
+}
+
+impl<T: Clone> Shared<T> {
+    /// compares the items of `self` and `other` with `cmp`. the items are copied first, so no lock
+    /// is held while `cmp` compares the containers inside them. a pair that is already being
+    /// compared further up is `same`, the items that lead back to it decide the result
+    fn compare<R>(&self, other: &Self, same: R, cmp: impl FnOnce(&T, &T) -> R) -> R {
+        if Arc::ptr_eq(&self.0, &other.0) {
+            return same;
+        }
+
+        let pair = (
+            Arc::as_ptr(&self.0) as usize,
+            Arc::as_ptr(&other.0) as usize,
+        );
+        if COMPARING.with(|pairs| pairs.borrow().contains(&pair)) {
+            return same;
+        }
+
+        let (lhs, rhs) = (self.get(), other.get());
+        COMPARING.with(|pairs| pairs.borrow_mut().push(pair));
+        let res = cmp(&lhs, &rhs);
+        COMPARING.with(|pairs| pairs.borrow_mut().pop());
+        res
+    }
+}
+
+// containers are equal when their items are
+impl<T: Clone + PartialEq> PartialEq for Shared<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.compare(other, true, |lhs, rhs| lhs == rhs)
+    }
+}
+
+impl<T: Clone + PartialOrd> PartialOrd for Shared<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.compare(other, Some(Ordering::Equal), |lhs, rhs| {
+            lhs.partial_cmp(rhs)
+        })
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Shared<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.try_lock() {
+            Ok(value) => value.fmt(f),
+            // an object that holds itself
+            Err(_) => write!(f, ".."),
+        }
+    }
+}
+
+type FnBody = dyn Fn(&Context, Vec<Value>) -> Result<Value, RuntimeError> + Send + Sync;
+type MethodBody = dyn Fn(&Context, Vec<Value>, Value) -> Result<Value, RuntimeError> + Send + Sync;
+
+/// a function written in rust, it can capture state of the host
+/// and call back into the script through the `Context`
+#[derive(Clone)]
+pub struct NativeFn(Arc<FnBody>);
+
+/// a prototype method written in rust, it gets the value it is called on after the arguments
+#[derive(Clone)]
+pub struct NativeMethod(Arc<MethodBody>);
+
+impl NativeFn {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&Context, Vec<Value>) -> Result<Value, RuntimeError> + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    pub fn call(&self, ctx: &Context, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        (self.0)(ctx, args)
+    }
+}
+
+impl NativeMethod {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&Context, Vec<Value>, Value) -> Result<Value, RuntimeError> + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    pub fn call(
+        &self,
+        ctx: &Context,
+        args: Vec<Value>,
+        this: Value,
+    ) -> Result<Value, RuntimeError> {
+        (self.0)(ctx, args, this)
+    }
+}
+
+// natives are only equal to themselves
+impl PartialEq for NativeFn {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl PartialOrd for NativeFn {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self == other).then_some(Ordering::Equal)
+    }
+}
+
+impl fmt::Debug for NativeFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NativeFn")
+    }
+}
+
+impl PartialEq for NativeMethod {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl PartialOrd for NativeMethod {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self == other).then_some(Ordering::Equal)
+    }
+}
+
+impl fmt::Debug for NativeMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NativeMethod")
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
@@ -32,15 +234,15 @@ pub struct KeyValue {
     pub value: Value,
 }
 
-pub fn check_list_items(list: &Vec<Value>) -> Result<(), String> {
-    if let Some(value) = list.get(0) {
+pub fn check_list_items(list: &Vec<Value>) -> Result<(), RuntimeError> {
+    if let Some(value) = list.first() {
         for item in list {
             if Type::from(item) != Type::from(value) {
-                return Err(format!(
-                    "expected {}, found {}. help: all list items most have same datatype",
-                    Type::simple(value),
-                    Type::simple(item)
-                ));
+                return Err(ErrorKind::TypeMismatch {
+                    expected: Type::from(value),
+                    found: Type::from(item),
+                }
+                .into());
             }
         }
     }
@@ -48,6 +250,27 @@ pub fn check_list_items(list: &Vec<Value>) -> Result<(), String> {
     Ok(())
 }
 
+fn invalid_operands(op: &str, lhs: &Value, rhs: &Value) -> RuntimeError {
+    ErrorKind::InvalidOperands {
+        op: op.to_string(),
+        lhs: Type::simple(lhs),
+        rhs: Type::simple(rhs),
+    }
+    .into()
+}
+
+/// an `int` result, `None` when the operation overflowed
+fn checked_int(op: &str, res: Option<i32>) -> Result<Value, RuntimeError> {
+    match res {
+        Some(n) => Ok(Value::Int(n)),
+        None => Err(ErrorKind::Overflow { op: op.to_string() }.into()),
+    }
+}
+
+fn division_by_zero(op: &str) -> RuntimeError {
+    ErrorKind::DivisionByZero { op: op.to_string() }.into()
+}
+
 pub fn value_list(values: Vec<Value>) -> String {
     let mut res = String::new();
 
@@ -61,6 +284,82 @@ pub fn value_list(values: Vec<Value>) -> String {
     res
 }
 
+/// the string of an interpolation, `values` are the values of its fragments in order
+pub fn interpolate(fragments: &[Fragment], values: Vec<Value>) -> Result<Value, RuntimeError> {
+    let mut values = values.into_iter();
+    let mut res = String::new();
+
+    for fragment in fragments {
+        match fragment {
+            Fragment::Text(text) => res.push_str(text),
+            Fragment::Value(_, spec) => {
+                let value = values.next().expect("value of the fragment");
+                match spec {
+                    Some(spec) => res.push_str(&format_value(&value, spec)?),
+                    None => res.push_str(&value.to_string()),
+                }
+            }
+        }
+    }
+
+    Ok(Value::String(res))
+}
+
+/// writes `value` the way `spec` asks, numbers are aligned to the right by default
+pub fn format_value(value: &Value, spec: &FormatSpec) -> Result<String, RuntimeError> {
+    let text = match (value, spec.radix) {
+        (Value::Int(n), Some('x')) => format!("{:x}", n),
+        (Value::Int(n), Some('X')) => format!("{:X}", n),
+        (Value::Int(n), Some('o')) => format!("{:o}", n),
+        (Value::Int(n), Some('b')) => format!("{:b}", n),
+        (value, Some(radix)) => {
+            return Err(ErrorKind::InvalidFormat {
+                format: radix.to_string(),
+                datatype: Type::simple(value),
+            }
+            .into())
+        }
+        (Value::Float(n), None) => match spec.precision {
+            Some(precision) => format!("{:.*}", precision, n),
+            None => value.to_string(),
+        },
+        (Value::String(s), None) => match spec.precision {
+            Some(precision) => s.chars().take(precision).collect(),
+            None => s.to_string(),
+        },
+        (value, None) => value.to_string(),
+    };
+
+    let number = matches!(value, Value::Int(_) | Value::Float(_));
+    let len = text.chars().count();
+    let pad = spec.width.unwrap_or(0).saturating_sub(len);
+    if pad == 0 {
+        return Ok(text);
+    }
+
+    // zeros go after the sign
+    if spec.zero && number {
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", text.as_str()),
+        };
+        return Ok(format!("{}{}{}", sign, "0".repeat(pad), digits));
+    }
+
+    let fill = |n: usize| spec.fill.to_string().repeat(n);
+    let align = match spec.align {
+        Some(align) => align,
+        None if number => Align::Right,
+        None => Align::Left,
+    };
+
+    Ok(match align {
+        Align::Left => format!("{}{}", text, fill(pad)),
+        Align::Center => format!("{}{}{}", fill(pad / 2), text, fill(pad - pad / 2)),
+        Align::Right => format!("{}{}", fill(pad), text),
+    })
+}
+
 pub fn key_value(obj: Vec<KeyValue>) -> String {
     let mut res = String::new();
 
@@ -88,6 +387,49 @@ pub enum BuiltinType {
     Fn(Vec<Type>, Box<Type>),
 }
 
+impl Value {
+    pub fn list(items: Vec<Value>) -> Value {
+        let item_type = ItemType::default();
+        if let Some(first) = items.first() {
+            item_type.set(Type::from(first));
+        }
+        Value::List(Shared::new(items), item_type)
+    }
+
+    /// a list whose items have the type `datatype`, even while it is empty
+    pub fn list_of(items: Vec<Value>, datatype: Option<&Type>) -> Value {
+        match datatype {
+            Some(datatype) => {
+                let item_type = ItemType::default();
+                item_type.set(datatype.clone());
+                Value::List(Shared::new(items), item_type)
+            }
+            None => Value::list(items),
+        }
+    }
+
+    pub fn object(props: Vec<KeyValue>) -> Value {
+        Value::Object(Shared::new(props))
+    }
+
+    /// a native function that doesn't call back into the script
+    pub fn native<F>(f: F) -> Value
+    where
+        F: Fn(Vec<Value>) -> Result<Value, RuntimeError> + Send + Sync + 'static,
+    {
+        Value::BuiltInFn(NativeFn::new(move |_, args| f(args)))
+    }
+
+    /// a prototype method that doesn't call back into the script,
+    /// it is bound to a value when it is looked up
+    pub fn native_method<F>(f: F) -> Value
+    where
+        F: Fn(Vec<Value>, Value) -> Result<Value, RuntimeError> + Send + Sync + 'static,
+    {
+        Value::BuiltInMethod(NativeMethod::new(move |_, args, this| f(args, this)), None)
+    }
+}
+
 impl From<&Value> for Value {
     fn from(value: &Value) -> Self {
         match value {
@@ -96,16 +438,26 @@ impl From<&Value> for Value {
             Value::Float(n) => Value::Float(*n),
             Value::String(s) => Value::String(s.to_string()),
             Value::Bool(b) => Value::Bool(*b),
-            Value::List(l) => Value::List(l.to_vec()),
-            Value::BuiltInFn(f) => Value::BuiltInFn(*f),
-            Value::BuiltInMethod(f, this) => Value::BuiltInMethod(*f, this.clone()),
-            Value::Func(args, ret_type, block) => {
-                Value::Func(args.to_vec(), ret_type.clone(), block.to_vec())
-            }
-            Value::Object(props) => Value::Object(props.to_vec()),
-            Value::Module(items) => Value::Module(items.to_owned()),
+            Value::List(l, t) => Value::List(l.clone(), t.clone()),
+            Value::BuiltInFn(f) => Value::BuiltInFn(f.clone()),
+            Value::BuiltInMethod(f, this) => Value::BuiltInMethod(f.clone(), this.clone()),
+            Value::Func(args, ret_type, block, env, name) => Value::Func(
+                args.to_vec(),
+                ret_type.clone(),
+                block.to_vec(),
+                env.clone(),
+                name.clone(),
+            ),
+            Value::Closure(closure) => Value::Closure(closure.clone()),
+            Value::Object(props) => Value::Object(props.clone()),
+            Value::Module(items, private) => Value::Module(items.to_owned(), private.to_vec()),
             Value::Tuple(t) => Value::Tuple(t.to_vec()),
             Value::Type(n, t) => Value::Type(n.clone(), t.clone()),
+            Value::Struct(n, fields) => Value::Struct(n.clone(), fields.to_vec()),
+            Value::Instance(n, fields) => Value::Instance(n.clone(), fields.to_vec()),
+            Value::Enum(n, variants) => Value::Enum(n.clone(), variants.to_vec()),
+            Value::Variant(n, v, values) => Value::Variant(n.clone(), v.clone(), values.to_vec()),
+            Value::Trait(n, methods) => Value::Trait(n.clone(), methods.to_vec()),
         }
     }
 }
@@ -119,13 +471,33 @@ impl Display for Value {
             Value::String(s) => write!(f, "{}", s),
             Value::Bool(b) => write!(f, "{}", b),
             Value::BuiltInFn(_) => write!(f, "function"),
-            Value::List(v) => write!(f, "[{}]", value_list(v.to_vec())),
+            Value::List(v, _) => match v.0.try_lock() {
+                Ok(items) => write!(f, "[{}]", value_list(items.to_vec())),
+                Err(_) => write!(f, "[...]"),
+            },
             Value::BuiltInMethod(..) => write!(f, "function"),
             Value::Func(..) => write!(f, "function"),
-            Value::Object(obj) => write!(f, "{{\n{}}}", key_value(obj.to_vec())),
-            Value::Module(_) => write!(f, "module"),
+            Value::Closure(_) => write!(f, "function"),
+            Value::Object(obj) => match obj.0.try_lock() {
+                Ok(props) => write!(f, "{{\n{}}}", key_value(props.to_vec())),
+                // an object that holds itself
+                Err(_) => write!(f, "{{...}}"),
+            },
+            Value::Module(..) => write!(f, "module"),
             Value::Tuple(t) => write!(f, "({})", value_list(t.to_vec())),
             Value::Type(..) => write!(f, "type"),
+            Value::Struct(..) => write!(f, "type"),
+            Value::Instance(name, fields) => {
+                write!(f, "{} {{\n{}}}", name, key_value(fields.to_vec()))
+            }
+            Value::Enum(..) => write!(f, "type"),
+            Value::Trait(..) => write!(f, "type"),
+            Value::Variant(name, variant, values) => {
+                if values.is_empty() {
+                    return write!(f, "{}::{}", name, variant);
+                }
+                write!(f, "{}::{}({})", name, variant, value_list(values.to_vec()))
+            }
         }
     }
 }
@@ -140,9 +512,15 @@ impl From<Type> for Value {
                 BuiltinType::Float => Value::Float(f32::default()),
                 BuiltinType::Bool => Value::Bool(bool::default()),
                 BuiltinType::String => Value::String(String::default()),
-                BuiltinType::List(_) => Value::List(vec![]),
+                BuiltinType::List(t) => Value::list_of(vec![], Some(&t)),
                 BuiltinType::Tuple(_) => Value::Tuple(vec![]),
-                BuiltinType::Fn(_, ret_type) => Value::Func(vec![], Some(*ret_type), vec![]),
+                BuiltinType::Fn(_, ret_type) => Value::Func(
+                    vec![],
+                    Some(*ret_type),
+                    vec![],
+                    ScopeStack::new(vec![]),
+                    None,
+                ),
             },
         }
     }
@@ -159,7 +537,7 @@ impl Display for BuiltinType {
             BuiltinType::List(t) => {
                 let datatype = *t.clone();
 
-                return write!(f, "{}[]", datatype.to_string());
+                write!(f, "{}[]", datatype)
             }
             BuiltinType::Tuple(types) => {
                 let mut res = String::new();
@@ -171,7 +549,7 @@ impl Display for BuiltinType {
                     res.push_str(&String::from(t.clone()));
                 }
 
-                return write!(f, "({})", res);
+                write!(f, "({})", res)
             }
             BuiltinType::Fn(args, ret_type) => {
                 let mut args_types = String::new();
@@ -184,42 +562,43 @@ impl Display for BuiltinType {
                     args_types.push_str(&String::from(arg.clone()));
                 }
 
-                return write!(f, "fn({}) -> {}", args_types, ret_type.to_string());
+                write!(f, "fn({}) -> {}", args_types, ret_type)
             }
         }
     }
 }
 
 impl Not for Value {
-    type Output = Result<Value, String>;
+    type Output = Result<Value, RuntimeError>;
 
     fn not(self) -> Self::Output {
         match &self {
             Value::Bool(b) => Ok(Value::Bool(!b)),
-            _ => Err(format!(
-                "cannot apply unary operator '!' to type {}",
-                Type::simple(&self)
-            )),
+            _ => Err(ErrorKind::InvalidOperand {
+                op: "!".to_string(),
+                operand: Type::simple(&self),
+            }
+            .into()),
         }
     }
 }
 
 impl Add for &Value {
-    type Output = Result<Value, String>;
+    type Output = Result<Value, RuntimeError>;
 
     fn add(self, rhs: Self) -> Self::Output {
         match self {
             Value::Int(lhs) => match rhs {
-                Value::Int(rhs) => Ok(Value::Int(lhs + rhs)),
+                Value::Int(rhs) => checked_int("+", lhs.checked_add(*rhs)),
                 Value::Float(rhs) => Ok(Value::Float(*lhs as f32 + rhs)),
-                Value::String(rhs) => Ok(Value::String(lhs.to_string() + &rhs)),
-                other => Err(format!("cannot add int to {}", Type::simple(other))),
+                Value::String(rhs) => Ok(Value::String(lhs.to_string() + rhs)),
+                other => Err(invalid_operands("+", self, other)),
             },
             Value::Float(lhs) => match rhs {
                 Value::Int(rhs) => Ok(Value::Float(lhs + *rhs as f32)),
                 Value::Float(rhs) => Ok(Value::Float(lhs + rhs)),
-                Value::String(rhs) => Ok(Value::String(lhs.to_string() + &rhs)),
-                other => Err(format!("cannot add float to {}", Type::simple(other))),
+                Value::String(rhs) => Ok(Value::String(lhs.to_string() + rhs)),
+                other => Err(invalid_operands("+", self, other)),
             },
             Value::String(lhs) => match rhs {
                 Value::Int(rhs) => Ok(Value::String(lhs.to_owned() + &rhs.to_string().to_owned())),
@@ -227,88 +606,210 @@ impl Add for &Value {
                     Ok(Value::String(lhs.to_owned() + &rhs.to_string().to_owned()))
                 }
                 Value::String(rhs) => Ok(Value::String(lhs.to_owned() + rhs)),
-                Value::List(rhs) => Ok(Value::String(
-                    lhs.to_owned() + Value::List(rhs.to_owned()).to_string().as_str(),
-                )),
-                other => Err(format!("cannot add stirng to {}", Type::simple(other))),
+                Value::List(..) => Ok(Value::String(lhs.to_owned() + rhs.to_string().as_str())),
+                other => Err(invalid_operands("+", self, other)),
             },
-            other => Err(format!(
-                "cannot add {} to {}",
-                Type::simple(other),
-                Type::simple(rhs)
-            )),
+            other => Err(invalid_operands("+", other, rhs)),
         }
     }
 }
 
 impl Mul for &Value {
-    type Output = Result<Value, String>;
+    type Output = Result<Value, RuntimeError>;
 
     fn mul(self, rhs: Self) -> Self::Output {
         match self {
             Value::Int(lhs) => match rhs {
-                Value::Int(rhs) => Ok(Value::Int(lhs * rhs)),
+                Value::Int(rhs) => checked_int("*", lhs.checked_mul(*rhs)),
                 Value::Float(rhs) => Ok(Value::Float(*lhs as f32 * rhs)),
-                other => Err(format!("cannot mul int to {}", Type::simple(other))),
+                other => Err(invalid_operands("*", self, other)),
             },
             Value::Float(lhs) => match rhs {
                 Value::Int(rhs) => Ok(Value::Float(lhs * *rhs as f32)),
                 Value::Float(rhs) => Ok(Value::Float(lhs * rhs)),
-                other => Err(format!("cannot mul float to {}", Type::simple(other))),
+                other => Err(invalid_operands("*", self, other)),
             },
-            other => Err(format!(
-                "cannot mul {} to {}",
-                Type::simple(other),
-                Type::simple(rhs)
-            )),
+            other => Err(invalid_operands("*", other, rhs)),
         }
     }
 }
 
 impl Div for &Value {
-    type Output = Result<Value, String>;
+    type Output = Result<Value, RuntimeError>;
 
     fn div(self, rhs: Self) -> Self::Output {
         match self {
             Value::Int(lhs) => match rhs {
-                Value::Int(rhs) => Ok(Value::Int(lhs / rhs)),
+                Value::Int(0) => Err(division_by_zero("/")),
+                Value::Int(rhs) => checked_int("/", lhs.checked_div(*rhs)),
                 Value::Float(rhs) => Ok(Value::Float(*lhs as f32 / rhs)),
-                other => Err(format!("cannot div int to {}", Type::simple(other))),
+                other => Err(invalid_operands("/", self, other)),
             },
             Value::Float(lhs) => match rhs {
                 Value::Int(rhs) => Ok(Value::Float(lhs / *rhs as f32)),
                 Value::Float(rhs) => Ok(Value::Float(lhs / rhs)),
-                other => Err(format!("cannot div float to {}", Type::simple(other))),
+                other => Err(invalid_operands("/", self, other)),
             },
-            other => Err(format!(
-                "cannot div {} to {}",
-                Type::simple(other),
-                Type::simple(rhs)
-            )),
+            other => Err(invalid_operands("/", other, rhs)),
         }
     }
 }
 
 impl Sub for &Value {
-    type Output = Result<Value, String>;
+    type Output = Result<Value, RuntimeError>;
 
     fn sub(self, rhs: Self) -> Self::Output {
         match self {
             Value::Int(lhs) => match rhs {
-                Value::Int(rhs) => Ok(Value::Int(lhs - rhs)),
+                Value::Int(rhs) => checked_int("-", lhs.checked_sub(*rhs)),
                 Value::Float(rhs) => Ok(Value::Float(*lhs as f32 - rhs)),
-                other => Err(format!("cannot sub float to {}", Type::simple(other))),
+                other => Err(invalid_operands("-", self, other)),
             },
             Value::Float(lhs) => match rhs {
                 Value::Int(rhs) => Ok(Value::Float(lhs - *rhs as f32)),
                 Value::Float(rhs) => Ok(Value::Float(lhs - rhs)),
-                other => Err(format!("cannot sub float to {}", Type::simple(other))),
+                other => Err(invalid_operands("-", self, other)),
+            },
+            other => Err(invalid_operands("-", other, rhs)),
+        }
+    }
+}
+
+impl Rem for &Value {
+    type Output = Result<Value, RuntimeError>;
+
+    fn rem(self, rhs: Self) -> Self::Output {
+        match self {
+            Value::Int(lhs) => match rhs {
+                Value::Int(0) => Err(division_by_zero("%")),
+                Value::Int(rhs) => checked_int("%", lhs.checked_rem(*rhs)),
+                Value::Float(rhs) => Ok(Value::Float(*lhs as f32 % rhs)),
+                other => Err(invalid_operands("%", self, other)),
+            },
+            Value::Float(lhs) => match rhs {
+                Value::Int(rhs) => Ok(Value::Float(lhs % *rhs as f32)),
+                Value::Float(rhs) => Ok(Value::Float(lhs % rhs)),
+                other => Err(invalid_operands("%", self, other)),
+            },
+            other => Err(invalid_operands("%", other, rhs)),
+        }
+    }
+}
+
+impl BitAnd for &Value {
+    type Output = Result<Value, RuntimeError>;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Value::Int(lhs), Value::Int(rhs)) => Ok(Value::Int(lhs & rhs)),
+            (Value::Bool(lhs), Value::Bool(rhs)) => Ok(Value::Bool(lhs & rhs)),
+            _ => Err(invalid_operands("&", self, rhs)),
+        }
+    }
+}
+
+impl BitOr for &Value {
+    type Output = Result<Value, RuntimeError>;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Value::Int(lhs), Value::Int(rhs)) => Ok(Value::Int(lhs | rhs)),
+            (Value::Bool(lhs), Value::Bool(rhs)) => Ok(Value::Bool(lhs | rhs)),
+            _ => Err(invalid_operands("|", self, rhs)),
+        }
+    }
+}
+
+impl BitXor for &Value {
+    type Output = Result<Value, RuntimeError>;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Value::Int(lhs), Value::Int(rhs)) => Ok(Value::Int(lhs ^ rhs)),
+            (Value::Bool(lhs), Value::Bool(rhs)) => Ok(Value::Bool(lhs ^ rhs)),
+            _ => Err(invalid_operands("^", self, rhs)),
+        }
+    }
+}
+
+impl Shl for &Value {
+    type Output = Result<Value, RuntimeError>;
+
+    fn shl(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Value::Int(lhs), Value::Int(rhs)) => {
+                let res = u32::try_from(*rhs)
+                    .ok()
+                    .and_then(|rhs| lhs.checked_shl(rhs));
+                checked_int("<<", res)
+            }
+            _ => Err(invalid_operands("<<", self, rhs)),
+        }
+    }
+}
+
+impl Shr for &Value {
+    type Output = Result<Value, RuntimeError>;
+
+    fn shr(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Value::Int(lhs), Value::Int(rhs)) => {
+                let res = u32::try_from(*rhs)
+                    .ok()
+                    .and_then(|rhs| lhs.checked_shr(rhs));
+                checked_int(">>", res)
+            }
+            _ => Err(invalid_operands(">>", self, rhs)),
+        }
+    }
+}
+
+impl Neg for Value {
+    type Output = Result<Value, RuntimeError>;
+
+    fn neg(self) -> Self::Output {
+        match &self {
+            Value::Int(n) => checked_int("-", n.checked_neg()),
+            Value::Float(n) => Ok(Value::Float(-n)),
+            _ => Err(ErrorKind::InvalidOperand {
+                op: "-".to_string(),
+                operand: Type::simple(&self),
+            }
+            .into()),
+        }
+    }
+}
+
+impl Value {
+    /// `lhs ** rhs`, a negative exponent of an `int` truncates like `/` does
+    pub fn pow(&self, rhs: &Value) -> Result<Value, RuntimeError> {
+        match (self, rhs) {
+            (Value::Int(lhs), Value::Int(rhs)) if *rhs >= 0 => {
+                checked_int("**", lhs.checked_pow(*rhs as u32))
+            }
+            (Value::Int(lhs), Value::Int(rhs)) => match *lhs {
+                0 => Err(division_by_zero("**")),
+                1 => Ok(Value::Int(1)),
+                -1 if rhs % 2 == 0 => Ok(Value::Int(1)),
+                -1 => Ok(Value::Int(-1)),
+                _ => Ok(Value::Int(0)),
             },
-            other => Err(format!(
-                "cannot sub {} to {}",
-                Type::simple(other),
-                Type::simple(rhs)
-            )),
+            (Value::Int(lhs), Value::Float(rhs)) => Ok(Value::Float((*lhs as f32).powf(*rhs))),
+            (Value::Float(lhs), Value::Int(rhs)) => Ok(Value::Float(lhs.powi(*rhs))),
+            (Value::Float(lhs), Value::Float(rhs)) => Ok(Value::Float(lhs.powf(*rhs))),
+            _ => Err(invalid_operands("**", self, rhs)),
+        }
+    }
+
+    /// `~value`, the bits of an `int` flipped
+    pub fn complement(self) -> Result<Value, RuntimeError> {
+        match &self {
+            Value::Int(n) => Ok(Value::Int(!n)),
+            _ => Err(ErrorKind::InvalidOperand {
+                op: "~".to_string(),
+                operand: Type::simple(&self),
+            }
+            .into()),
         }
     }
 }
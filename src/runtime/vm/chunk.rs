@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use crate::ast::{Arg, BinaryOpKind, Expr, Fragment, Pattern, Prop, Statement, UnaryOpKind};
+use crate::runtime::error::ErrorKind;
+use crate::runtime::value::Value;
+use crate::runtime::Type;
+use crate::span::Span;
+
+/// where a local variable lives
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    /// a slot of the running frame
+    Slot(u32),
+    /// a cell of the running frame, closures created in the frame can share it
+    Cell(u32),
+    /// a cell captured by the running closure
+    Upvalue(u32),
+}
+
+/// the checks a value goes through before it is bound to a name
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Check {
+    /// `let` and `const`, with the index of the declared type
+    Variable(Option<u32>),
+    /// parameters, the declared type must match exactly
+    Value(Option<u32>),
+    /// the item of a `for` loop
+    Item,
+    None,
+}
+
+/// why a block is left early
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flow {
+    Return,
+    Break,
+    Continue,
+}
+
+impl Flow {
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Flow::Return => "return",
+            Flow::Break => "break",
+            Flow::Continue => "continue",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Constant(u32),
+    Pop,
+
+    Load(Target),
+    // assigns the value on top, `Define` declares it
+    Store(Target),
+    Define(Target, Check),
+    // leaves the variable undefined until its declaration runs again
+    Clear(Target),
+    LoadGlobal(u32),
+    StoreGlobal(u32),
+    DefineGlobal(u32, Check, bool),
+    // a name inside a module path, the items of the module come first
+    LoadItem(u32, Option<Target>),
+
+    // statements and expressions the tree-walker runs in the frame environment
+    Exec(u32),
+    Eval(u32),
+    PushScope,
+    PopScope,
+
+    Jump(u32),
+    JumpIfFalse(u32),
+    Iterate(u32),
+    Next(u32, u32),
+    Match(u32),
+    Escape(Flow),
+    Fail(u32),
+
+    Closure(u32),
+    Call(u32, u32),
+    Return,
+    Impl(u32, u32),
+
+    List(u32),
+    Tuple(u32),
+    Object(u32),
+    Struct(u32),
+    Range,
+    Interpolate(u32),
+    Module(u32, u32),
+    EndModule,
+    Variant(u32),
+
+    CheckIndexable,
+    Index,
+    Member(u32),
+    // `?.name` and `?.[index]`, a missing key or an index past the end is `null`
+    SafeMember(u32),
+    SafeIndex,
+    // a `?.` on `null`, jumps to the end of the chain and leaves the `null` as its value
+    JumpIfNull(u32),
+    // writes the value under the target and the indexes, jumps when the target doesn't need storing back
+    SetPath(u32, u32),
+    // `a[i] += 1`: pushes the value the path leads to, the target and the indexes stay under it.
+    // `UpdatePath` then writes the value on top of them like `SetPath`
+    ReadPath(u32),
+    UpdatePath(u32, u32),
+    ProtoCall(u32, u32),
+    NotCallable,
+
+    // the left side of `&&`, `||` or `??`, jumps past the right side when it decides the result
+    ShortCircuit(BinaryOpKind, u32),
+    Binary(BinaryOpKind),
+    Unary(UnaryOpKind),
+}
+
+/// describes a call in the stack trace
+#[derive(Debug, Clone)]
+pub struct CallSite {
+    pub name: String,
+    pub module: Option<String>,
+}
+
+/// the path of a module call and the item it names.
+/// `local` is set when the first name is a local variable, its value is on the stack
+#[derive(Debug, Clone)]
+pub struct ModulePath {
+    pub paths: Vec<String>,
+    pub item: Option<String>,
+    pub local: bool,
+}
+
+/// a variant built through the path of its enum, `None` when the path isn't a variant
+#[derive(Debug, Clone)]
+pub struct VariantCall {
+    pub name: Option<String>,
+    pub args: Vec<Span>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    // the names the pattern can bind and where they are stored
+    pub bindings: Vec<(String, Target)>,
+    pub start: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Chunk {
+    pub code: Vec<Op>,
+    pub spans: Vec<Span>,
+    pub constants: Vec<Value>,
+    pub names: Vec<String>,
+    pub types: Vec<Type>,
+    pub errors: Vec<ErrorKind>,
+    pub functions: Vec<Arc<Function>>,
+    // run by the tree-walker, with the variables they use
+    pub statements: Vec<(Statement, Vec<(String, Target)>)>,
+    pub exprs: Vec<(Expr, Vec<(String, Target)>)>,
+    pub sites: Vec<CallSite>,
+    pub keys: Vec<Vec<String>>,
+    pub structs: Vec<(String, Vec<Prop>)>,
+    pub modules: Vec<ModulePath>,
+    pub variants: Vec<VariantCall>,
+    pub matches: Vec<Vec<MatchArm>>,
+    // what assignments write through, `None` is an item whose index is on the stack
+    pub paths: Vec<Vec<Option<String>>>,
+    // the values of the fragments are on the stack
+    pub interpolations: Vec<Vec<Fragment>>,
+}
+
+/// a compiled function, or the top level of a program
+#[derive(Debug, Clone, Default)]
+pub struct Function {
+    // the name it was declared with, `None` for `fn` expressions
+    pub name: Option<String>,
+    pub args: Vec<Arg>,
+    pub ret_type: Option<Type>,
+    // where the arguments are stored, `self` comes first in methods
+    pub params: Vec<Target>,
+    pub method: bool,
+    // cells of the enclosing frame the function captures, by their target there
+    pub captures: Vec<(String, Target)>,
+    // names of the slots, a variable read before its declaration is reported with it
+    pub slots: Vec<String>,
+    pub cells: Vec<String>,
+    pub chunk: Chunk,
+}
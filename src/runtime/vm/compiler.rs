@@ -0,0 +1,1189 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::ast::{
+    Access, Arg, ArmBody, BinaryOpKind, Block, Branch, Expr, ExprKind, Fragment, LValue, MatchArm,
+    Program, Slot, Statement, StatementKind,
+};
+use crate::resolver::{declarations, pattern_names};
+use crate::runtime::error::ErrorKind;
+use crate::runtime::eval::expression::call_name;
+use crate::runtime::value::Value;
+use crate::runtime::Type;
+use crate::span::Span;
+
+use super::chunk::{
+    self, CallSite, Check, Chunk, Flow, Function, ModulePath, Op, Target, VariantCall,
+};
+
+#[derive(Default)]
+struct Scope {
+    // where the functions, `let` and `const` of the block are stored, in the order they are declared
+    declarations: Vec<Target>,
+    declared: usize,
+    // the block pushed a scope on the frame environment
+    env: bool,
+}
+
+/// where `return`, `break` and `continue` lead to
+enum Exit {
+    Loop {
+        start: u32,
+        breaks: Vec<usize>,
+        envs: usize,
+    },
+    // the block of an `if` or `match` expression, what it returns is its value
+    Block {
+        ends: Vec<usize>,
+        envs: usize,
+    },
+    Function,
+    // a statement at the top level of the program
+    Program(Span),
+}
+
+/// a function being compiled
+struct State {
+    function: Function,
+    scopes: Vec<Scope>,
+    exits: Vec<Exit>,
+    // where the slots the resolver gave are stored and whether they can be assigned, by index
+    layout: Vec<(Target, bool)>,
+    // the slot the next declaration gets, slots are given in the same order as the resolver
+    next: u32,
+    // slots the functions declared inside this one use, they are cells
+    captured: HashSet<u32>,
+    // scopes pushed on the frame environment
+    envs: usize,
+    // inside the path of a module call, its items come before the variables
+    modules: usize,
+    // the jumps out of the `?.` chains being compiled, they go to the end of their chain
+    chains: Vec<Vec<usize>>,
+}
+
+impl State {
+    fn new(block: &Block) -> Self {
+        let mut found = vec![];
+        for statement in block {
+            statement_uses(statement, 0, &mut found);
+        }
+
+        let captured = found
+            .into_iter()
+            .filter(|(_, slot, level)| *level > 0 && slot.depth == *level)
+            .map(|(_, slot, _)| slot.index)
+            .collect();
+
+        Self {
+            function: Function::default(),
+            scopes: vec![],
+            exits: vec![],
+            layout: vec![],
+            next: 0,
+            captured,
+            envs: 0,
+            modules: 0,
+            chains: vec![],
+        }
+    }
+}
+
+/// compiles the top level of a program. what isn't declared in a block is a global,
+/// it lives in the environment the program runs in
+pub fn compile(program: &Program) -> Function {
+    let mut compiler = Compiler {
+        states: vec![State::new(&program.statements)],
+    };
+    let count = program.statements.len();
+
+    for (i, statement) in program.statements.iter().enumerate() {
+        compiler.state().exits.push(Exit::Program(statement.span));
+
+        match &statement.kind {
+            // the value of the program
+            StatementKind::Expression(expr) if i + 1 == count => {
+                compiler.expr(expr);
+                compiler.emit(Op::Return, statement.span);
+            }
+            _ => compiler.statement(statement),
+        }
+
+        compiler.state().exits.pop();
+    }
+
+    let span = program
+        .statements
+        .last()
+        .map(|s| s.span)
+        .unwrap_or_default();
+    compiler.constant(Value::Null, span);
+    compiler.emit(Op::Return, span);
+
+    compiler.states.pop().expect("top level state").function
+}
+
+struct Compiler {
+    states: Vec<State>,
+}
+
+impl Compiler {
+    fn state(&mut self) -> &mut State {
+        self.states.last_mut().expect("compiler state")
+    }
+
+    fn chunk(&mut self) -> &mut Chunk {
+        &mut self.state().function.chunk
+    }
+
+    fn emit(&mut self, op: Op, span: Span) -> usize {
+        let chunk = self.chunk();
+        chunk.code.push(op);
+        chunk.spans.push(span);
+        chunk.code.len() - 1
+    }
+
+    fn here(&mut self) -> u32 {
+        self.chunk().code.len() as u32
+    }
+
+    /// points the jump at `at` to the next instruction
+    fn patch(&mut self, at: usize) {
+        let to = self.here();
+
+        match &mut self.chunk().code[at] {
+            Op::Jump(target) | Op::JumpIfFalse(target) | Op::Next(_, target) => *target = to,
+            Op::Module(_, target)
+            | Op::SetPath(_, target)
+            | Op::UpdatePath(_, target)
+            | Op::ShortCircuit(_, target)
+            | Op::JumpIfNull(target) => *target = to,
+            op => unreachable!("{:?} doesn't jump", op),
+        }
+    }
+
+    fn constant(&mut self, value: Value, span: Span) {
+        let chunk = self.chunk();
+        chunk.constants.push(value);
+        let index = chunk.constants.len() as u32 - 1;
+
+        self.emit(Op::Constant(index), span);
+    }
+
+    fn name(&mut self, name: &str) -> u32 {
+        let names = &mut self.chunk().names;
+
+        match names.iter().position(|n| n == name) {
+            Some(i) => i as u32,
+            None => {
+                names.push(name.to_string());
+                names.len() as u32 - 1
+            }
+        }
+    }
+
+    fn datatype(&mut self, datatype: &Option<Type>) -> Option<u32> {
+        let types = &mut self.chunk().types;
+
+        datatype.as_ref().map(|datatype| {
+            types.push(datatype.clone());
+            types.len() as u32 - 1
+        })
+    }
+
+    fn fail(&mut self, error: ErrorKind, span: Span) {
+        let errors = &mut self.chunk().errors;
+        errors.push(error);
+        let index = errors.len() as u32 - 1;
+
+        self.emit(Op::Fail(index), span);
+    }
+
+    /// declarations outside of blocks of the top level are globals
+    fn is_global(&self) -> bool {
+        self.states.len() == 1 && self.states[0].scopes.is_empty()
+    }
+
+    /// where the next slot of the resolver is stored,
+    /// code compiled twice gets the same targets both times
+    fn slot(&mut self, name: &str, mutable: bool) -> Target {
+        let state = self.state();
+        let index = state.next;
+        state.next += 1;
+
+        if let Some((target, _)) = state.layout.get(index as usize) {
+            return *target;
+        }
+
+        let target = if state.captured.contains(&index) {
+            state.function.cells.push(name.to_string());
+            Target::Cell(state.function.cells.len() as u32 - 1)
+        } else {
+            state.function.slots.push(name.to_string());
+            Target::Slot(state.function.slots.len() as u32 - 1)
+        };
+        state.layout.push((target, mutable));
+        target
+    }
+
+    fn begin_scope(&mut self, block: &Block, span: Span) {
+        let env = block.iter().any(runs_in_env);
+        if env {
+            self.state().envs += 1;
+            self.emit(Op::PushScope, span);
+        }
+
+        let mut targets = vec![];
+        for declaration in declarations(block).into_iter().filter(|d| d.slot) {
+            let target = self.slot(declaration.name, declaration.mutable);
+            // closures made by an earlier run of the block keep their own cell
+            if let Target::Cell(_) = target {
+                self.emit(Op::Clear(target), declaration.span);
+            }
+            targets.push(target);
+        }
+
+        self.state().scopes.push(Scope {
+            declarations: targets,
+            declared: 0,
+            env,
+        });
+    }
+
+    fn end_scope(&mut self, span: Span) {
+        let scope = self.state().scopes.pop().expect("scope");
+
+        if scope.env {
+            self.state().envs -= 1;
+            self.emit(Op::PopScope, span);
+        }
+    }
+
+    /// binds the value on top of the stack to the next declaration of the block
+    fn declare(&mut self, name: &str, check: Check, mutable: bool, span: Span) {
+        if self.is_global() {
+            let name = self.name(name);
+            self.emit(Op::DefineGlobal(name, check, mutable), span);
+            return;
+        }
+
+        let scope = self.state().scopes.last_mut().expect("scope");
+        let target = scope.declarations[scope.declared];
+        scope.declared += 1;
+        self.emit(Op::Define(target, check), span);
+    }
+
+    /// binds the value on top of the stack to a variable that isn't declared by a statement
+    fn bind(&mut self, name: &str, check: Check, mutable: bool, span: Span) {
+        let target = self.slot(name, mutable);
+        if let Target::Cell(_) = target {
+            self.emit(Op::Clear(target), span);
+        }
+        self.emit(Op::Define(target, check), span);
+    }
+
+    /// the target of a variable the resolver found `depth` functions out
+    fn resolve(&mut self, name: &str, slot: Slot) -> (Target, bool) {
+        let level = self.states.len() - 1;
+        self.resolve_at(level, name, slot)
+    }
+
+    fn resolve_at(&mut self, level: usize, name: &str, slot: Slot) -> (Target, bool) {
+        if slot.depth == 0 {
+            return self.states[level].layout[slot.index as usize];
+        }
+
+        // a variable of an enclosing function, it is captured from the frame creating the closure
+        let outer = Slot {
+            depth: slot.depth - 1,
+            index: slot.index,
+        };
+        let (target, mutable) = self.resolve_at(level - 1, name, outer);
+
+        let captures = &mut self.states[level].function.captures;
+        let index = match captures.iter().position(|(_, t)| *t == target) {
+            Some(i) => i,
+            None => {
+                captures.push((name.to_string(), target));
+                captures.len() - 1
+            }
+        };
+
+        (Target::Upvalue(index as u32), mutable)
+    }
+
+    fn load(&mut self, name: &str, slot: &Option<Slot>, span: Span) {
+        let target = slot.map(|slot| self.resolve(name, slot).0);
+
+        if self.state().modules > 0 {
+            let name = self.name(name);
+            self.emit(Op::LoadItem(name, target), span);
+            return;
+        }
+
+        match target {
+            Some(target) => self.emit(Op::Load(target), span),
+            None => {
+                let name = self.name(name);
+                self.emit(Op::LoadGlobal(name), span)
+            }
+        };
+    }
+
+    fn assign(&mut self, name: &String, slot: &Option<Slot>, span: Span) {
+        match slot.map(|slot| self.resolve(name, slot)) {
+            Some((_, false)) => self.fail(
+                ErrorKind::ImmutableAssignment {
+                    name: name.to_string(),
+                },
+                span,
+            ),
+            Some((target, true)) => {
+                self.emit(Op::Store(target), span);
+            }
+            None => {
+                let name = self.name(name);
+                self.emit(Op::StoreGlobal(name), span);
+            }
+        }
+    }
+
+    /// loads the variable of `lvalue` and the indexes of its path, returns the path
+    fn path(&mut self, lvalue: &LValue, span: Span) -> u32 {
+        self.load(&lvalue.name, &lvalue.slot, span);
+
+        let mut path = vec![];
+        for access in &lvalue.path {
+            match access {
+                Access::Index(index) => {
+                    self.expr(index);
+                    path.push(None);
+                }
+                Access::Property(name) => path.push(Some(name.to_string())),
+            }
+        }
+
+        let paths = &mut self.chunk().paths;
+        paths.push(path);
+        paths.len() as u32 - 1
+    }
+
+    /// leaves the blocks up to the nearest one that handles `flow`
+    fn exit(&mut self, flow: Flow, span: Span) {
+        let state = self.state();
+        let envs = state.envs;
+
+        let index = state.exits.iter().rposition(|exit| match exit {
+            Exit::Loop { .. } => flow != Flow::Return,
+            _ => true,
+        });
+
+        match index.map(|i| &state.exits[i]) {
+            Some(Exit::Loop {
+                start,
+                envs: target,
+                ..
+            }) => {
+                let (start, pops) = (*start, envs - target);
+                for _ in 0..pops {
+                    self.emit(Op::PopScope, span);
+                }
+
+                match flow {
+                    Flow::Continue => {
+                        self.emit(Op::Jump(start), span);
+                    }
+                    _ => {
+                        let at = self.emit(Op::Jump(0), span);
+                        if let Some(Exit::Loop { breaks, .. }) =
+                            index.map(|i| &mut self.state().exits[i])
+                        {
+                            breaks.push(at);
+                        }
+                    }
+                }
+            }
+            Some(Exit::Block { envs: target, .. }) => {
+                let pops = envs - target;
+                // `break` and `continue` end the block without a value
+                if flow != Flow::Return {
+                    self.constant(Value::Null, span);
+                }
+                for _ in 0..pops {
+                    self.emit(Op::PopScope, span);
+                }
+
+                let at = self.emit(Op::Jump(0), span);
+                if let Some(Exit::Block { ends, .. }) = index.map(|i| &mut self.state().exits[i]) {
+                    ends.push(at);
+                }
+            }
+            Some(Exit::Function) if flow == Flow::Return => {
+                self.emit(Op::Return, span);
+            }
+            Some(Exit::Function) => {
+                self.emit(Op::Escape(flow), span);
+            }
+            Some(Exit::Program(statement)) => {
+                let statement = *statement;
+                self.emit(Op::Escape(flow), statement);
+            }
+            None => unreachable!("code is always inside a function or the top level"),
+        }
+    }
+
+    fn block(&mut self, block: &Block, span: Span) {
+        self.begin_scope(block, span);
+
+        for statement in block {
+            self.statement(statement);
+        }
+
+        self.end_scope(span);
+    }
+
+    /// a block that leaves its value on the stack, `null` unless it returns one
+    fn expr_block(&mut self, block: &Block, span: Span) {
+        let envs = self.state().envs;
+        self.state().exits.push(Exit::Block { ends: vec![], envs });
+
+        self.block(block, span);
+        self.constant(Value::Null, span);
+
+        if let Some(Exit::Block { ends, .. }) = self.state().exits.pop() {
+            for end in ends {
+                self.patch(end);
+            }
+        }
+    }
+
+    fn loop_body(&mut self, start: u32, block: &Block, span: Span) {
+        let envs = self.state().envs;
+        self.state().exits.push(Exit::Loop {
+            start,
+            breaks: vec![],
+            envs,
+        });
+
+        self.block(block, span);
+        self.emit(Op::Jump(start), span);
+
+        if let Some(Exit::Loop { breaks, .. }) = self.state().exits.pop() {
+            for at in breaks {
+                self.patch(at);
+            }
+        }
+    }
+
+    /// compiles a function into the chunk of the current one, returns its index there
+    fn function(
+        &mut self,
+        name: Option<&String>,
+        args: &Vec<Arg>,
+        ret_type: &Option<Type>,
+        block: &Block,
+        method: bool,
+        span: Span,
+    ) -> u32 {
+        let mut state = State::new(block);
+        state.function.name = name.cloned();
+        state.function.args = args.clone();
+        state.function.ret_type = ret_type.clone();
+        state.function.method = method;
+        state.scopes.push(Scope::default());
+        state.exits.push(Exit::Function);
+        self.states.push(state);
+
+        let mut params = vec![];
+        if method {
+            params.push(self.slot("self", false));
+        }
+        for arg in args {
+            params.push(self.slot(&arg.ident, true));
+        }
+        self.state().function.params = params;
+
+        self.block(block, span);
+        self.constant(Value::Null, span);
+        self.emit(Op::Return, span);
+
+        let function = self.states.pop().expect("function state").function;
+        let functions = &mut self.chunk().functions;
+        functions.push(Arc::new(function));
+        functions.len() as u32 - 1
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        let span = statement.span;
+
+        match &statement.kind {
+            StatementKind::Expression(expr) => {
+                self.expr(expr);
+                self.emit(Op::Pop, span);
+            }
+            StatementKind::Let(name, datatype, rhs) => {
+                self.expr(rhs);
+                let datatype = self.datatype(datatype);
+                self.declare(name, Check::Variable(datatype), true, span);
+            }
+            StatementKind::Const(name, datatype, rhs) => {
+                self.expr(rhs);
+                let datatype = self.datatype(datatype);
+                self.declare(name, Check::Variable(datatype), false, span);
+            }
+            StatementKind::Assignment(lvalue, rhs) => {
+                self.expr(rhs);
+
+                if lvalue.path.is_empty() {
+                    return self.assign(&lvalue.name, &lvalue.slot, span);
+                }
+
+                let path = self.path(lvalue, span);
+
+                // instances are values, the updated variable is stored back
+                let stored = self.emit(Op::SetPath(path, 0), span);
+                self.assign(&lvalue.name, &lvalue.slot, span);
+                self.patch(stored);
+            }
+            StatementKind::CompoundAssignment(lvalue, op, rhs) => {
+                if lvalue.path.is_empty() {
+                    self.load(&lvalue.name, &lvalue.slot, span);
+                    self.expr(rhs);
+                    self.emit(Op::Binary(*op), span);
+                    return self.assign(&lvalue.name, &lvalue.slot, span);
+                }
+
+                // the indexes are evaluated once, before the right side
+                let path = self.path(lvalue, span);
+                self.emit(Op::ReadPath(path), span);
+                self.expr(rhs);
+                self.emit(Op::Binary(*op), span);
+
+                let stored = self.emit(Op::UpdatePath(path, 0), span);
+                self.assign(&lvalue.name, &lvalue.slot, span);
+                self.patch(stored);
+            }
+            StatementKind::If(branches, else_block) => {
+                let mut ends = vec![];
+
+                for branch in branches {
+                    self.expr(&branch.condition);
+                    let next = self.emit(Op::JumpIfFalse(0), branch.condition.span);
+                    self.block(&branch.statements, branch.span);
+                    ends.push(self.emit(Op::Jump(0), span));
+                    self.patch(next);
+                }
+
+                if let Some(block) = else_block {
+                    self.block(block, span);
+                }
+
+                for end in ends {
+                    self.patch(end);
+                }
+            }
+            StatementKind::Return(expr) => {
+                self.expr(expr);
+                self.exit(Flow::Return, span);
+            }
+            StatementKind::Break => self.exit(Flow::Break, span),
+            StatementKind::Continue => self.exit(Flow::Continue, span),
+            StatementKind::Fn(name, args, ret_type, block) => {
+                let function = self.function(Some(name), args, ret_type, block, false, span);
+                self.emit(Op::Closure(function), span);
+                self.declare(name, Check::None, false, span);
+            }
+            StatementKind::For(name, iter, block) => {
+                self.state().scopes.push(Scope::default());
+
+                // the iterated list and the position in it
+                let list = self.state().function.slots.len() as u32;
+                self.state().function.slots.push(String::new());
+                self.state().function.slots.push(String::new());
+
+                self.expr(iter);
+                self.emit(Op::Iterate(list), iter.span);
+
+                let start = self.here();
+                let next = self.emit(Op::Next(list, 0), span);
+                self.bind(name, Check::Item, true, span);
+                self.bind("index", Check::None, false, span);
+
+                self.loop_body(start, block, span);
+                self.patch(next);
+                self.end_scope(span);
+            }
+            StatementKind::While(cond, block) => {
+                let start = self.here();
+                self.expr(cond);
+                let exit = self.emit(Op::JumpIfFalse(0), cond.span);
+
+                self.loop_body(start, block, span);
+                self.patch(exit);
+            }
+            StatementKind::Export(statement) => self.statement(statement),
+            StatementKind::Impl(_, _, methods) => {
+                let first = self.chunk().functions.len() as u32;
+                for method in methods {
+                    self.function(
+                        Some(&method.name),
+                        &method.args,
+                        &method.ret_type,
+                        &method.block,
+                        true,
+                        method.span,
+                    );
+                }
+
+                let statements = &mut self.chunk().statements;
+                statements.push((statement.clone(), vec![]));
+                let index = statements.len() as u32 - 1;
+                self.emit(Op::Impl(index, first), span);
+            }
+            StatementKind::Match(expr, arms) => self.match_arms(expr, arms, false, span),
+            StatementKind::Import(..)
+            | StatementKind::Module(..)
+            | StatementKind::Type(..)
+            | StatementKind::Struct(..)
+            | StatementKind::Enum(..)
+            | StatementKind::Trait(..) => self.exec(statement),
+        }
+    }
+
+    /// runs a declaration with the tree-walker, the names it declares shadow the variables
+    fn exec(&mut self, statement: &Statement) {
+        let locals = match &unexported(statement).kind {
+            StatementKind::Module(_, block) => self.visible(block),
+            _ => vec![],
+        };
+
+        let statements = &mut self.chunk().statements;
+        statements.push((statement.clone(), locals));
+        let index = statements.len() as u32 - 1;
+        self.emit(Op::Exec(index), statement.span);
+    }
+
+    /// the variables `block` uses, the tree-walker gets their values when it runs it
+    fn visible(&mut self, block: &Block) -> Vec<(String, Target)> {
+        let mut found = vec![];
+        for statement in block {
+            statement_uses(statement, 0, &mut found);
+        }
+
+        let mut locals: Vec<(String, Target)> = vec![];
+        for (name, slot, level) in found {
+            // variables of the functions declared in the block
+            if slot.depth < level || locals.iter().any(|(n, _)| *n == name) {
+                continue;
+            }
+
+            let slot = Slot {
+                depth: slot.depth - level,
+                index: slot.index,
+            };
+            let (target, _) = self.resolve(&name, slot);
+            locals.push((name, target));
+        }
+        locals
+    }
+
+    fn match_arms(&mut self, expr: &Expr, arms: &Vec<MatchArm>, value: bool, span: Span) {
+        self.expr(expr);
+
+        let table = self.chunk().matches.len() as u32;
+        self.chunk().matches.push(vec![]);
+        self.emit(Op::Match(table), span);
+
+        let mut ends = vec![];
+        for arm in arms {
+            let start = self.here();
+            self.state().scopes.push(Scope::default());
+
+            let mut names = vec![];
+            pattern_names(&arm.pattern, &mut names);
+
+            let mut bindings = vec![];
+            for name in names {
+                let target = self.slot(&name, false);
+                bindings.push((name, target));
+            }
+
+            match &arm.body {
+                ArmBody::Expr(expr) => {
+                    self.expr(expr);
+                    if !value {
+                        self.emit(Op::Pop, expr.span);
+                    }
+                }
+                ArmBody::Block(block) if value => self.expr_block(block, arm.span),
+                ArmBody::Block(block) => self.block(block, arm.span),
+            }
+
+            self.state().scopes.pop();
+            ends.push(self.emit(Op::Jump(0), span));
+
+            self.chunk().matches[table as usize].push(chunk::MatchArm {
+                pattern: arm.pattern.clone(),
+                bindings,
+                start,
+            });
+        }
+
+        for end in ends {
+            self.patch(end);
+        }
+    }
+
+    fn if_expr(&mut self, branches: &Vec<Branch>, else_block: &Option<Block>, span: Span) {
+        let mut ends = vec![];
+
+        for branch in branches {
+            self.expr(&branch.condition);
+            let next = self.emit(Op::JumpIfFalse(0), branch.condition.span);
+            self.expr_block(&branch.statements, branch.span);
+            ends.push(self.emit(Op::Jump(0), span));
+            self.patch(next);
+        }
+
+        match else_block {
+            Some(block) => self.expr_block(block, span),
+            None => self.constant(Value::Null, span),
+        }
+
+        for end in ends {
+            self.patch(end);
+        }
+    }
+
+    fn call(&mut self, calle: &Expr, args: &Vec<Expr>, module: Option<String>, span: Span) {
+        let name = call_name(calle);
+
+        self.expr(calle);
+        for arg in args {
+            self.expr(arg);
+        }
+
+        let sites = &mut self.chunk().sites;
+        sites.push(CallSite { name, module });
+        let site = sites.len() as u32 - 1;
+        self.emit(Op::Call(args.len() as u32, site), span);
+    }
+
+    fn module_call(&mut self, paths: &[String], slot: &Option<Slot>, tail: &Expr, span: Span) {
+        let item = match &tail.kind {
+            ExprKind::Call(calle, _) => match &calle.kind {
+                ExprKind::Identifier(name, _) => Some(name.to_string()),
+                _ => None,
+            },
+            ExprKind::Identifier(name, _) | ExprKind::Struct(name, _) => Some(name.to_string()),
+            _ => None,
+        };
+
+        let local = match slot {
+            Some(slot) => {
+                let (target, _) = self.resolve(&paths[0], *slot);
+                self.emit(Op::Load(target), span);
+                true
+            }
+            None => false,
+        };
+
+        let modules = &mut self.chunk().modules;
+        modules.push(ModulePath {
+            paths: paths.to_vec(),
+            item,
+            local,
+        });
+        let index = modules.len() as u32 - 1;
+        let variant = self.emit(Op::Module(index, 0), span);
+
+        let next = self.state().next;
+        self.state().modules += 1;
+        match &tail.kind {
+            // calls through a module path are recorded with the path in the backtrace
+            ExprKind::Call(calle, args) => self.call(calle, args, Some(paths.join("::")), span),
+            _ => self.expr(tail),
+        }
+        self.state().modules -= 1;
+
+        self.emit(Op::EndModule, span);
+        let end = self.emit(Op::Jump(0), span);
+
+        // `Shape::Circle(1.0)` looks like a module call but constructs an enum variant
+        self.patch(variant);
+        let variant = match &tail.kind {
+            ExprKind::Identifier(name, _) => VariantCall {
+                name: Some(name.to_string()),
+                args: vec![],
+            },
+            ExprKind::Call(calle, args) => match &calle.kind {
+                ExprKind::Identifier(name, _) => {
+                    // the arguments were compiled for the module already, they keep their slots
+                    self.state().next = next;
+                    for arg in args {
+                        self.expr(arg);
+                    }
+
+                    VariantCall {
+                        name: Some(name.to_string()),
+                        args: args.iter().map(|arg| arg.span).collect(),
+                    }
+                }
+                _ => VariantCall {
+                    name: None,
+                    args: vec![],
+                },
+            },
+            _ => VariantCall {
+                name: None,
+                args: vec![],
+            },
+        };
+
+        let variants = &mut self.chunk().variants;
+        variants.push(variant);
+        let index = variants.len() as u32 - 1;
+        self.emit(Op::Variant(index), span);
+
+        self.patch(end);
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        let span = expr.span;
+
+        match &expr.kind {
+            ExprKind::Null => self.constant(Value::Null, span),
+            ExprKind::Int(n) => self.constant(Value::Int(*n), span),
+            ExprKind::Float(n) => self.constant(Value::Float(*n), span),
+            ExprKind::String(s) => self.constant(Value::String(s.to_string()), span),
+            ExprKind::Bool(b) => self.constant(Value::Bool(*b), span),
+            ExprKind::List(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+                self.emit(Op::List(items.len() as u32), span);
+            }
+            ExprKind::Tuple(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+                self.emit(Op::Tuple(items.len() as u32), span);
+            }
+            ExprKind::Object(props) => {
+                for prop in props {
+                    self.expr(&prop.value);
+                }
+
+                let keys = &mut self.chunk().keys;
+                keys.push(props.iter().map(|prop| prop.key.to_string()).collect());
+                let index = keys.len() as u32 - 1;
+                self.emit(Op::Object(index), span);
+            }
+            ExprKind::Struct(name, props) => {
+                for prop in props {
+                    self.expr(&prop.value);
+                }
+
+                let structs = &mut self.chunk().structs;
+                structs.push((name.to_string(), props.clone()));
+                let index = structs.len() as u32 - 1;
+                self.emit(Op::Struct(index), span);
+            }
+            ExprKind::Identifier(name, slot) => self.load(name, slot, span),
+            ExprKind::Call(calle, args) => self.call(calle, args, None, span),
+            ExprKind::MethodCall(object, calle) => {
+                self.expr(object);
+                self.member(calle, false, span);
+            }
+            ExprKind::SafeMethodCall(object, calle) => {
+                self.expr(object);
+                self.chain_exit(span);
+                self.member(calle, true, span);
+            }
+            ExprKind::SafeChain(chain) => {
+                self.state().chains.push(vec![]);
+                self.expr(chain);
+
+                let exits = self.state().chains.pop().expect("chain should exist");
+                for exit in exits {
+                    self.patch(exit);
+                }
+            }
+            ExprKind::ModuleCall(paths, slot, tail) => self.module_call(paths, slot, tail, span),
+            ExprKind::Index(target, index) => {
+                self.expr(target);
+                self.emit(Op::CheckIndexable, span);
+                self.expr(index);
+                self.emit(Op::Index, span);
+            }
+            ExprKind::SafeIndex(target, index) => {
+                self.expr(target);
+                self.chain_exit(span);
+                self.emit(Op::CheckIndexable, span);
+                self.expr(index);
+                self.emit(Op::SafeIndex, span);
+            }
+            ExprKind::BinaryOp(
+                lhs,
+                op @ (BinaryOpKind::And | BinaryOpKind::Or | BinaryOpKind::Coalesce),
+                rhs,
+            ) => {
+                self.expr(lhs);
+                let exit = self.emit(Op::ShortCircuit(*op, 0), span);
+                self.expr(rhs);
+                self.emit(Op::Binary(*op), span);
+                self.patch(exit);
+            }
+            ExprKind::BinaryOp(lhs, op, rhs) => {
+                self.expr(lhs);
+                self.expr(rhs);
+                self.emit(Op::Binary(*op), span);
+            }
+            ExprKind::UnaryOp(op, value) => {
+                self.expr(value);
+                self.emit(Op::Unary(*op), span);
+            }
+            ExprKind::Fn(args, ret_type, block) => {
+                let function = self.function(None, args, ret_type, block, false, span);
+                self.emit(Op::Closure(function), span);
+            }
+            ExprKind::Module(block) => {
+                let locals = self.visible(block);
+
+                let exprs = &mut self.chunk().exprs;
+                exprs.push((expr.clone(), locals));
+                let index = exprs.len() as u32 - 1;
+                self.emit(Op::Eval(index), span);
+            }
+            ExprKind::If(branches, else_block) => self.if_expr(branches, else_block, span),
+            ExprKind::Match(value, arms) => self.match_arms(value, arms, true, span),
+            ExprKind::Range(start, end) => {
+                self.expr(start);
+                self.expr(end);
+                self.emit(Op::Range, span);
+            }
+            ExprKind::Interpolation(fragments) => {
+                for fragment in fragments {
+                    if let Fragment::Value(value, _) = fragment {
+                        self.expr(value);
+                    }
+                }
+
+                let interpolations = &mut self.chunk().interpolations;
+                interpolations.push(fragments.clone());
+                let index = interpolations.len() as u32 - 1;
+                self.emit(Op::Interpolate(index), span);
+            }
+        };
+    }
+
+    /// a `?.` on `null` skips the rest of the chain
+    fn chain_exit(&mut self, span: Span) {
+        let exit = self.emit(Op::JumpIfNull(0), span);
+        self.state()
+            .chains
+            .last_mut()
+            .expect("`?.` should be inside a chain")
+            .push(exit);
+    }
+
+    /// the member `calle` of the object on the stack, `safe` when it is looked up through `?.`
+    fn member(&mut self, calle: &Expr, safe: bool, span: Span) {
+        let member = |name| match safe {
+            true => Op::SafeMember(name),
+            false => Op::Member(name),
+        };
+
+        match &calle.kind {
+            ExprKind::Identifier(name, _) => {
+                let name = self.name(name);
+                self.emit(member(name), span);
+            }
+            ExprKind::Int(n) => {
+                let name = self.name(&n.to_string());
+                self.emit(member(name), span);
+            }
+            ExprKind::Call(method, args) => match &method.kind {
+                ExprKind::Identifier(name, _) => {
+                    for arg in args {
+                        self.expr(arg);
+                    }
+
+                    let name = self.name(name);
+                    self.emit(Op::ProtoCall(name, args.len() as u32), calle.span);
+                }
+                _ => {
+                    self.emit(Op::NotCallable, span);
+                }
+            },
+            _ => {
+                self.emit(Op::NotCallable, span);
+            }
+        }
+    }
+}
+
+fn unexported(statement: &Statement) -> &Statement {
+    match &statement.kind {
+        StatementKind::Export(inner) => unexported(inner),
+        _ => statement,
+    }
+}
+
+/// declarations the tree-walker runs, they are stored in the frame environment
+fn runs_in_env(statement: &Statement) -> bool {
+    matches!(
+        unexported(statement).kind,
+        StatementKind::Import(..)
+            | StatementKind::Module(..)
+            | StatementKind::Type(..)
+            | StatementKind::Struct(..)
+            | StatementKind::Enum(..)
+            | StatementKind::Trait(..)
+            | StatementKind::Impl(..)
+    )
+}
+
+/// collects the variables the resolver found in `statement`, with how many
+/// functions deep they are used
+fn statement_uses(statement: &Statement, level: u32, found: &mut Vec<(String, Slot, u32)>) {
+    let block = |block: &Block, level: u32, found: &mut Vec<(String, Slot, u32)>| {
+        for statement in block {
+            statement_uses(statement, level, found);
+        }
+    };
+
+    match &statement.kind {
+        StatementKind::Let(_, _, expr)
+        | StatementKind::Const(_, _, expr)
+        | StatementKind::Expression(expr)
+        | StatementKind::Return(expr) => expr_uses(expr, level, found),
+        StatementKind::Assignment(lvalue, expr)
+        | StatementKind::CompoundAssignment(lvalue, _, expr) => {
+            if let Some(slot) = lvalue.slot {
+                found.push((lvalue.name.to_string(), slot, level));
+            }
+            for access in &lvalue.path {
+                if let Access::Index(index) = access {
+                    expr_uses(index, level, found);
+                }
+            }
+            expr_uses(expr, level, found);
+        }
+        StatementKind::If(branches, else_block) => {
+            for branch in branches {
+                expr_uses(&branch.condition, level, found);
+                block(&branch.statements, level, found);
+            }
+            if let Some(else_block) = else_block {
+                block(else_block, level, found);
+            }
+        }
+        StatementKind::Fn(_, _, _, body) => block(body, level + 1, found),
+        StatementKind::Module(_, body) => block(body, level, found),
+        StatementKind::For(_, iter, body) | StatementKind::While(iter, body) => {
+            expr_uses(iter, level, found);
+            block(body, level, found);
+        }
+        StatementKind::Match(expr, arms) => {
+            expr_uses(expr, level, found);
+            for arm in arms {
+                match &arm.body {
+                    ArmBody::Expr(expr) => expr_uses(expr, level, found),
+                    ArmBody::Block(body) => block(body, level, found),
+                }
+            }
+        }
+        StatementKind::Impl(_, _, methods) => {
+            for method in methods {
+                block(&method.block, level + 1, found);
+            }
+        }
+        StatementKind::Export(statement) => statement_uses(statement, level, found),
+        StatementKind::Import(..)
+        | StatementKind::Type(..)
+        | StatementKind::Struct(..)
+        | StatementKind::Enum(..)
+        | StatementKind::Trait(..)
+        | StatementKind::Break
+        | StatementKind::Continue => {}
+    }
+}
+
+fn expr_uses(expr: &Expr, level: u32, found: &mut Vec<(String, Slot, u32)>) {
+    let exprs = |exprs: &Vec<Expr>, found: &mut Vec<(String, Slot, u32)>| {
+        for expr in exprs {
+            expr_uses(expr, level, found);
+        }
+    };
+    let block = |block: &Block, level: u32, found: &mut Vec<(String, Slot, u32)>| {
+        for statement in block {
+            statement_uses(statement, level, found);
+        }
+    };
+
+    match &expr.kind {
+        ExprKind::Identifier(name, slot) => {
+            if let Some(slot) = slot {
+                found.push((name.to_string(), *slot, level));
+            }
+        }
+        ExprKind::List(items) | ExprKind::Tuple(items) => exprs(items, found),
+        ExprKind::Object(props) | ExprKind::Struct(_, props) => {
+            for prop in props {
+                expr_uses(&prop.value, level, found);
+            }
+        }
+        ExprKind::Call(calle, args) => {
+            expr_uses(calle, level, found);
+            exprs(args, found);
+        }
+        ExprKind::MethodCall(lhs, rhs)
+        | ExprKind::SafeMethodCall(lhs, rhs)
+        | ExprKind::Index(lhs, rhs)
+        | ExprKind::SafeIndex(lhs, rhs)
+        | ExprKind::BinaryOp(lhs, _, rhs)
+        | ExprKind::Range(lhs, rhs) => {
+            expr_uses(lhs, level, found);
+            expr_uses(rhs, level, found);
+        }
+        ExprKind::ModuleCall(paths, slot, tail) => {
+            if let Some(slot) = slot {
+                found.push((paths[0].to_string(), *slot, level));
+            }
+            expr_uses(tail, level, found);
+        }
+        ExprKind::UnaryOp(_, expr) | ExprKind::SafeChain(expr) => expr_uses(expr, level, found),
+        ExprKind::Interpolation(fragments) => {
+            for fragment in fragments {
+                if let Fragment::Value(expr, _) = fragment {
+                    expr_uses(expr, level, found);
+                }
+            }
+        }
+        ExprKind::Fn(_, _, body) => block(body, level + 1, found),
+        ExprKind::Module(body) => block(body, level, found),
+        ExprKind::If(branches, else_block) => {
+            for branch in branches {
+                expr_uses(&branch.condition, level, found);
+                block(&branch.statements, level, found);
+            }
+            if let Some(else_block) = else_block {
+                block(else_block, level, found);
+            }
+        }
+        ExprKind::Match(expr, arms) => {
+            expr_uses(expr, level, found);
+            for arm in arms {
+                match &arm.body {
+                    ArmBody::Expr(expr) => expr_uses(expr, level, found),
+                    ArmBody::Block(body) => block(body, level, found),
+                }
+            }
+        }
+        ExprKind::Null
+        | ExprKind::Int(_)
+        | ExprKind::Float(_)
+        | ExprKind::String(_)
+        | ExprKind::Bool(_) => {}
+    }
+}
@@ -0,0 +1,830 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use crate::ast::{Fragment, StatementKind};
+use crate::runtime::error::{ErrorKind, RuntimeError};
+use crate::runtime::eval::expression::{
+    binary_value, call_proto_method, call_value, check_indexable, eval_expression, get_member,
+    get_module_from, get_path_from, get_safe_member, index_value, instance_value, match_pattern,
+    range_value, safe_index_value, short_circuit, unary_value, variant_value,
+};
+use crate::runtime::eval::statement::{assign_path, eval_statement, read_path, Step};
+use crate::runtime::trace::{self, Frame, FrameGuard};
+use crate::runtime::value::{interpolate, BuiltinType, KeyValue, Value};
+use crate::runtime::{DeclType, Scope, ScopeStack, Simple, Type};
+use crate::span::Span;
+
+use super::chunk::{Check, Function, Op, Target};
+use super::{Cell, Closure, Slot};
+
+pub struct Vm<'a> {
+    prototypes: &'a HashMap<String, HashMap<String, Value>>,
+    stack: Vec<Value>,
+    frames: Vec<CallFrame>,
+}
+
+struct CallFrame {
+    closure: Arc<Closure>,
+    ip: usize,
+    // where the values of the frame start on the stack
+    base: usize,
+    slots: Vec<Option<Slot>>,
+    cells: Vec<Cell>,
+    env: ScopeStack,
+    // items of the modules whose path is being evaluated, innermost last
+    modules: Vec<BTreeMap<String, Value>>,
+    // the top level of a program isn't in the stack trace
+    _guard: Option<FrameGuard>,
+}
+
+impl CallFrame {
+    fn new(closure: Arc<Closure>, base: usize) -> Self {
+        let function = &closure.function;
+
+        Self {
+            ip: 0,
+            base,
+            slots: vec![None; function.slots.len()],
+            cells: (0..function.cells.len()).map(|_| empty_cell()).collect(),
+            env: closure.env.clone(),
+            modules: vec![],
+            _guard: None,
+            closure,
+        }
+    }
+
+    fn cell(&self, target: Target) -> &Cell {
+        match target {
+            Target::Cell(i) => &self.cells[i as usize],
+            Target::Upvalue(i) => &self.closure.upvalues[i as usize],
+            Target::Slot(_) => unreachable!("slots aren't cells"),
+        }
+    }
+
+    fn get(&self, target: Target) -> Option<Slot> {
+        match target {
+            Target::Slot(i) => self.slots[i as usize].clone(),
+            target => self.cell(target).lock().unwrap().clone(),
+        }
+    }
+
+    fn set(&mut self, target: Target, slot: Slot) {
+        match target {
+            Target::Slot(i) => self.slots[i as usize] = Some(slot),
+            target => *self.cell(target).lock().unwrap() = Some(slot),
+        }
+    }
+
+    fn clear(&mut self, target: Target) {
+        match target {
+            Target::Slot(i) => self.slots[i as usize] = None,
+            Target::Cell(i) => self.cells[i as usize] = empty_cell(),
+            Target::Upvalue(_) => unreachable!("variables are declared in their own frame"),
+        }
+    }
+
+    /// the name of the variable at `target`, for errors about it
+    fn name(&self, target: Target) -> String {
+        let function = &self.closure.function;
+
+        match target {
+            Target::Slot(i) => function.slots[i as usize].to_string(),
+            Target::Cell(i) => function.cells[i as usize].to_string(),
+            Target::Upvalue(i) => function.captures[i as usize].0.to_string(),
+        }
+    }
+
+    fn load(&self, target: Target) -> Result<Value, RuntimeError> {
+        match self.get(target) {
+            Some(slot) => Ok(slot.value),
+            None => Err(ErrorKind::UndefinedName {
+                name: self.name(target),
+            }
+            .into()),
+        }
+    }
+}
+
+fn empty_cell() -> Cell {
+    Arc::new(Mutex::new(None))
+}
+
+impl<'a> Vm<'a> {
+    pub fn new(prototypes: &'a HashMap<String, HashMap<String, Value>>) -> Self {
+        Self {
+            prototypes,
+            stack: vec![],
+            frames: vec![],
+        }
+    }
+
+    /// runs the top level of a program
+    pub fn main(mut self, closure: Arc<Closure>) -> Result<Value, RuntimeError> {
+        self.frames.push(CallFrame::new(closure, 0));
+        self.run()
+    }
+
+    pub fn call(
+        mut self,
+        closure: Arc<Closure>,
+        args: Vec<Value>,
+        name: String,
+        module: Option<String>,
+        span: Span,
+    ) -> Result<Value, RuntimeError> {
+        self.enter(closure, args, name, module, span)?;
+        self.run()
+    }
+
+    fn run(&mut self) -> Result<Value, RuntimeError> {
+        self.execute().map_err(|e| self.fail(e))
+    }
+
+    /// points the error to the instruction that raised it, and the frames that led to it
+    fn fail(&mut self, e: RuntimeError) -> RuntimeError {
+        let mut e = e;
+
+        if let Some(frame) = self.frames.last() {
+            e = e.with_span(frame.closure.function.chunk.spans[frame.ip - 1]);
+
+            if frame._guard.is_some() {
+                e = e.with_trace(trace::capture());
+            }
+        }
+
+        self.frames.clear();
+        e
+    }
+
+    fn frame(&mut self) -> &mut CallFrame {
+        self.frames.last_mut().expect("vm should have a frame")
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("vm stack shouldn't be empty")
+    }
+
+    fn pop_n(&mut self, n: u32) -> Vec<Value> {
+        let at = self.stack.len() - n as usize;
+        self.stack.split_off(at)
+    }
+
+    /// pushes a frame that runs `closure`, the arguments are checked like in the tree-walker
+    fn enter(
+        &mut self,
+        closure: Arc<Closure>,
+        args: Vec<Value>,
+        name: String,
+        module: Option<String>,
+        span: Span,
+    ) -> Result<(), RuntimeError> {
+        let function = closure.function.clone();
+
+        if function.args.len() != args.len() {
+            return Err(ErrorKind::ArityMismatch {
+                expected: function.args.len(),
+                found: args.len(),
+            }
+            .into());
+        }
+
+        let mut frame = CallFrame::new(closure.clone(), self.stack.len());
+        let mut params = function.params.iter();
+
+        if function.method {
+            let this = closure
+                .this
+                .clone()
+                .expect("methods are bound before they are called");
+            let target = *params.next().expect("methods take `self`");
+            let datatype = Type::from(&this);
+            frame.set(
+                target,
+                Slot {
+                    value: this,
+                    datatype,
+                },
+            );
+        }
+
+        for ((arg, value), target) in function.args.iter().zip(args).zip(params) {
+            let datatype = closure
+                .env
+                .value_type(&value, &Some(arg.datatype.clone()))?;
+            frame.set(*target, Slot { value, datatype });
+        }
+
+        frame._guard = Some(trace::enter(Frame::new(name, module, span, false))?);
+        self.frames.push(frame);
+        Ok(())
+    }
+
+    fn execute(&mut self) -> Result<Value, RuntimeError> {
+        loop {
+            let frame = self.frame();
+            let function = frame.closure.function.clone();
+            let chunk = &function.chunk;
+            let op = chunk.code[frame.ip];
+            let span = chunk.spans[frame.ip];
+            frame.ip += 1;
+
+            match op {
+                Op::Constant(i) => self.stack.push(chunk.constants[i as usize].clone()),
+                Op::Pop => {
+                    self.pop();
+                }
+                Op::Load(target) => {
+                    let value = self.frame().load(target)?;
+                    self.stack.push(value);
+                }
+                Op::Store(target) => {
+                    let value = self.pop();
+                    let frame = self.frame();
+
+                    let slot = match frame.get(target) {
+                        Some(slot) => slot,
+                        None => {
+                            return Err(ErrorKind::UndefinedName {
+                                name: frame.name(target),
+                            }
+                            .into())
+                        }
+                    };
+
+                    frame.env.check_assignment(&value, &slot.datatype)?;
+                    frame.set(
+                        target,
+                        Slot {
+                            value,
+                            datatype: slot.datatype,
+                        },
+                    );
+                }
+                Op::Define(target, check) => {
+                    let value = self.pop();
+                    let frame = self.frame();
+
+                    let datatype = match check {
+                        Check::Variable(datatype) => {
+                            let datatype = match datatype {
+                                Some(i) => chunk.types[i as usize].clone(),
+                                None => Type::from(&value),
+                            };
+                            frame.env.variable_type(&datatype, &value)?
+                        }
+                        Check::Value(datatype) => {
+                            let datatype = datatype.map(|i| chunk.types[i as usize].clone());
+                            frame.env.value_type(&value, &datatype)?
+                        }
+                        Check::Item => frame.env.value_type(&value, &Some(Type::from(&value)))?,
+                        Check::None => Type::from(&value),
+                    };
+
+                    frame.set(target, Slot { value, datatype });
+                }
+                Op::Clear(target) => self.frame().clear(target),
+                Op::LoadGlobal(name) => {
+                    let name = &chunk.names[name as usize];
+                    let value = self.global(name)?;
+                    self.stack.push(value);
+                }
+                Op::StoreGlobal(name) => {
+                    let value = self.pop();
+                    let name = chunk.names[name as usize].to_string();
+                    self.frame().env.assgin(name, value)?;
+                }
+                Op::DefineGlobal(name, check, mutable) => {
+                    let value = self.pop();
+                    let name = &chunk.names[name as usize];
+                    let decl_type = match mutable {
+                        true => DeclType::Mutable,
+                        false => DeclType::Immutable,
+                    };
+                    let env = &mut self.frame().env;
+
+                    match check {
+                        Check::Variable(datatype) => {
+                            let datatype = match datatype {
+                                Some(i) => chunk.types[i as usize].clone(),
+                                None => Type::from(&value),
+                            };
+                            env.declare_variable(name, &datatype, &value, decl_type)?;
+                        }
+                        Check::Value(datatype) => {
+                            let datatype = datatype.map(|i| chunk.types[i as usize].clone());
+                            env.declare(name, value, &datatype, decl_type)?;
+                        }
+                        Check::Item => {
+                            let datatype = Some(Type::from(&value));
+                            env.declare(name, value, &datatype, decl_type)?;
+                        }
+                        Check::None => env.declare(name, value, &None, decl_type)?,
+                    }
+                }
+                Op::LoadItem(name, target) => {
+                    let name = &chunk.names[name as usize];
+                    let frame = self.frame();
+
+                    let item = frame.modules.iter().rev().find_map(|items| items.get(name));
+                    let value = match (item, target) {
+                        (Some(value), _) => value.clone(),
+                        (None, Some(target)) => frame.load(target)?,
+                        (None, None) => self.global(name)?,
+                    };
+                    self.stack.push(value);
+                }
+
+                Op::Exec(i) => {
+                    let (statement, locals) = &chunk.statements[i as usize];
+                    let frame = self.frame();
+
+                    let mut env = match locals.is_empty() {
+                        true => frame.env.clone(),
+                        false => {
+                            let scope = frame.snapshot(locals);
+                            frame.env.insert_below(scope)
+                        }
+                    };
+                    eval_statement(&mut env, statement, self.prototypes)?;
+                }
+                Op::Eval(i) => {
+                    let (expr, locals) = &chunk.exprs[i as usize];
+                    let frame = self.frame();
+
+                    let mut env = frame.env.new_from_push(frame.snapshot(locals));
+                    let value = eval_expression(&mut env, expr, self.prototypes)?;
+                    self.stack.push(value);
+                }
+                Op::PushScope => self.frame().env.push(HashMap::new()),
+                Op::PopScope => self.frame().env.pop(),
+
+                Op::Jump(to) => self.frame().ip = to as usize,
+                Op::JumpIfFalse(to) => match self.pop() {
+                    Value::Bool(true) => {}
+                    Value::Bool(false) => self.frame().ip = to as usize,
+                    other => {
+                        return Err(ErrorKind::TypeMismatch {
+                            expected: Type::Builtin(BuiltinType::Bool),
+                            found: Type::from(&other),
+                        }
+                        .into())
+                    }
+                },
+                Op::Iterate(list) => {
+                    let value = match self.pop() {
+                        // the loop goes over the items the list had when it started
+                        Value::List(values, _) => Value::Tuple(values.get()),
+                        value @ Value::Tuple(_) => value,
+                        value => {
+                            return Err(ErrorKind::NotIterable {
+                                datatype: Type::simple(&value),
+                            }
+                            .into())
+                        }
+                    };
+
+                    let frame = self.frame();
+                    frame.set(Target::Slot(list), hidden(value));
+                    frame.set(Target::Slot(list + 1), hidden(Value::Int(0)));
+                }
+                // the hidden slots are read in place, cloning the list would copy it on every step
+                Op::Next(list, exit) => {
+                    let frame = self.frame();
+                    let index = match &frame.slots[list as usize + 1] {
+                        Some(Slot {
+                            value: Value::Int(i),
+                            ..
+                        }) => *i,
+                        _ => unreachable!("`Iterate` sets the position"),
+                    };
+
+                    let item = match &frame.slots[list as usize] {
+                        Some(Slot {
+                            value: Value::Tuple(values),
+                            ..
+                        }) => values.get(index as usize).cloned(),
+                        _ => unreachable!("`Iterate` sets the list"),
+                    };
+
+                    match item {
+                        Some(item) => {
+                            frame.set(Target::Slot(list + 1), hidden(Value::Int(index + 1)));
+                            self.stack.push(Value::Int(index));
+                            self.stack.push(item);
+                        }
+                        None => frame.ip = exit as usize,
+                    }
+                }
+                Op::Match(table) => {
+                    let value = self.pop();
+                    let prototypes = self.prototypes;
+                    let frame = self.frame();
+                    let mut found = None;
+
+                    for arm in &chunk.matches[table as usize] {
+                        let mut bindings = vec![];
+
+                        if match_pattern(
+                            &mut frame.env,
+                            prototypes,
+                            &arm.pattern,
+                            &value,
+                            &mut bindings,
+                        )? {
+                            found = Some((arm, bindings));
+                            break;
+                        }
+                    }
+
+                    let (arm, bindings) = match found {
+                        Some(found) => found,
+                        None => {
+                            return Err(ErrorKind::NonExhaustiveMatch {
+                                missing: value.to_string(),
+                            }
+                            .into())
+                        }
+                    };
+
+                    for (_, target) in &arm.bindings {
+                        frame.clear(*target);
+                    }
+
+                    let mut bound: Vec<String> = vec![];
+                    for (name, value) in bindings {
+                        if bound.contains(&name) {
+                            return Err(ErrorKind::AlreadyDefined { name }.into());
+                        }
+
+                        let target = arm
+                            .bindings
+                            .iter()
+                            .find(|(n, _)| n == &name)
+                            .map(|(_, target)| *target)
+                            .expect("the names a pattern binds have a target");
+                        let datatype = frame.env.value_type(&value, &None)?;
+                        frame.set(target, Slot { value, datatype });
+                        bound.push(name);
+                    }
+
+                    frame.ip = arm.start as usize;
+                }
+                Op::Escape(flow) => {
+                    // only a function frame can be left, the error points to its call
+                    if self.frame()._guard.is_some() {
+                        let frame = self.frames.pop().expect("vm should have a frame");
+                        self.stack.truncate(frame.base);
+                    }
+
+                    return Err(ErrorKind::InvalidControlFlow {
+                        keyword: flow.keyword().to_string(),
+                    }
+                    .into());
+                }
+                Op::Fail(i) => return Err(chunk.errors[i as usize].clone().into()),
+
+                Op::Closure(i) => {
+                    let function = chunk.functions[i as usize].clone();
+                    let closure = self.frame().closure(function);
+                    self.stack.push(Value::Closure(Arc::new(closure)));
+                }
+                Op::Call(argc, site) => {
+                    let args = self.pop_n(argc);
+                    let callee = self.pop();
+                    let site = &chunk.sites[site as usize];
+                    let (name, module) = (site.name.to_string(), site.module.clone());
+
+                    match callee {
+                        Value::Closure(closure) => self.enter(closure, args, name, module, span)?,
+                        value => {
+                            let value =
+                                call_value(self.prototypes, value, args, name, module, span)?;
+                            self.stack.push(value);
+                        }
+                    }
+                }
+                Op::Return => {
+                    let value = self.pop();
+                    let frame = self.frames.pop().expect("vm should have a frame");
+                    self.stack.truncate(frame.base);
+
+                    if self.frames.is_empty() {
+                        return Ok(value);
+                    }
+                    self.stack.push(value);
+                }
+                Op::Impl(i, first) => self.declare_impl(&function, i, first)?,
+
+                Op::List(n) => {
+                    let values = self.pop_n(n);
+                    self.stack.push(Value::list(values));
+                }
+                Op::Tuple(n) => {
+                    let values = self.pop_n(n);
+                    self.stack.push(Value::Tuple(values));
+                }
+                Op::Object(i) => {
+                    let keys = &chunk.keys[i as usize];
+                    let values = self.pop_n(keys.len() as u32);
+
+                    let props = keys
+                        .iter()
+                        .zip(values)
+                        .map(|(key, value)| KeyValue {
+                            key: key.to_string(),
+                            value,
+                        })
+                        .collect();
+                    self.stack.push(Value::object(props));
+                }
+                Op::Struct(i) => {
+                    let (name, props) = &chunk.structs[i as usize];
+                    let values = self.pop_n(props.len() as u32);
+
+                    let value = instance_value(&self.frame().env, name, props, values)?;
+                    self.stack.push(value);
+                }
+                Op::Range => {
+                    let end = self.pop();
+                    let start = self.pop();
+                    self.stack.push(range_value(start, end)?);
+                }
+                Op::Interpolate(i) => {
+                    let fragments = &chunk.interpolations[i as usize];
+                    let count = fragments
+                        .iter()
+                        .filter(|fragment| matches!(fragment, Fragment::Value(..)))
+                        .count();
+
+                    let values = self.pop_n(count as u32);
+                    self.stack.push(interpolate(fragments, values)?);
+                }
+                Op::Module(i, variant) => {
+                    let path = &chunk.modules[i as usize];
+                    let first = match path.local {
+                        true => Some(self.pop()),
+                        false => self.frame().env.get(&path.paths[0]),
+                    };
+
+                    // `Shape::Circle(1.0)` looks like a module call but constructs an enum variant
+                    if let Some(value @ Value::Enum(..)) =
+                        get_path_from(first.clone(), &path.paths[1..])
+                    {
+                        self.stack.push(value);
+                        self.frame().ip = variant as usize;
+                        continue;
+                    }
+
+                    let prototypes = self.prototypes;
+                    let frame = self.frame();
+                    let (module, private) =
+                        get_module_from(&mut frame.env, prototypes, first, &path.paths, span)?;
+
+                    if let Some(name) = path.item.as_ref().filter(|name| private.contains(name)) {
+                        return Err(ErrorKind::PrivateItem {
+                            name: name.to_string(),
+                        }
+                        .into());
+                    }
+
+                    frame.env.push(HashMap::new());
+                    for (key, value) in &module {
+                        frame
+                            .env
+                            .declare(key, value.clone(), &None, DeclType::Immutable)?;
+                    }
+                    frame.modules.push(module);
+                }
+                Op::EndModule => {
+                    let frame = self.frame();
+                    frame.env.pop();
+                    frame.modules.pop();
+                }
+                Op::Variant(i) => {
+                    let variant = &chunk.variants[i as usize];
+                    let values = self.pop_n(variant.args.len() as u32);
+
+                    let (name, variants) = match self.pop() {
+                        Value::Enum(name, variants) => (name, variants),
+                        _ => unreachable!("`Module` pushes the enum"),
+                    };
+
+                    let variant_name = match &variant.name {
+                        Some(variant_name) => variant_name,
+                        None => return Err(ErrorKind::NotCallable { datatype: name }.into()),
+                    };
+
+                    let env = &self.frame().env;
+                    let value =
+                        variant_value(env, &name, &variants, variant_name, values, &variant.args)?;
+                    self.stack.push(value);
+                }
+
+                Op::CheckIndexable => {
+                    check_indexable(self.stack.last().expect("vm stack shouldn't be empty"))?
+                }
+                Op::Index => {
+                    let index = self.pop();
+                    let value = self.pop();
+                    self.stack.push(index_value(&value, index)?);
+                }
+                Op::Member(name) => {
+                    let value = self.pop();
+                    let name = &chunk.names[name as usize];
+
+                    let prototypes = self.prototypes;
+                    let member = get_member(&self.frame().env, prototypes, value, name)?;
+                    self.stack.push(member);
+                }
+                Op::SafeMember(name) => {
+                    let value = self.pop();
+                    let name = &chunk.names[name as usize];
+
+                    let prototypes = self.prototypes;
+                    let member = get_safe_member(&self.frame().env, prototypes, value, name)?;
+                    self.stack.push(member);
+                }
+                Op::SafeIndex => {
+                    let index = self.pop();
+                    let value = self.pop();
+                    self.stack.push(safe_index_value(&value, index)?);
+                }
+                Op::JumpIfNull(to) => {
+                    if let Some(Value::Null) = self.stack.last() {
+                        self.frame().ip = to as usize;
+                    }
+                }
+                Op::SetPath(path, stored) | Op::UpdatePath(path, stored) => {
+                    let path = &chunk.paths[path as usize];
+                    let count = path.iter().filter(|step| step.is_none()).count();
+
+                    let value = match op {
+                        Op::UpdatePath(..) => Some(self.pop()),
+                        _ => None,
+                    };
+                    let indexes = self.pop_n(count as u32);
+                    let target = self.pop();
+                    let value = match value {
+                        Some(value) => value,
+                        None => self.pop(),
+                    };
+
+                    let path = steps(path, indexes);
+                    let prototypes = self.prototypes;
+                    match assign_path(&self.frame().env, prototypes, target, &path, value)? {
+                        Some(target) => self.stack.push(target),
+                        None => self.frame().ip = stored as usize,
+                    }
+                }
+                Op::ReadPath(path) => {
+                    let path = &chunk.paths[path as usize];
+                    let count = path.iter().filter(|step| step.is_none()).count();
+
+                    let at = self.stack.len() - count - 1;
+                    let target = self.stack[at].clone();
+                    let path = steps(path, self.stack[at + 1..].to_vec());
+
+                    let prototypes = self.prototypes;
+                    let value = read_path(&self.frame().env, prototypes, target, &path)?;
+                    self.stack.push(value);
+                }
+                Op::ProtoCall(name, argc) => {
+                    let args = self.pop_n(argc);
+                    let value = self.pop();
+                    let name = &chunk.names[name as usize];
+
+                    let value = call_proto_method(self.prototypes, value, name, args, span)?;
+                    self.stack.push(value);
+                }
+                Op::NotCallable => {
+                    let value = self.pop();
+                    return Err(ErrorKind::NotCallable {
+                        datatype: Type::simple(&value),
+                    }
+                    .into());
+                }
+
+                // the left side stays on the stack, it is the result when it decides it
+                Op::ShortCircuit(op, to) => {
+                    let lhs = self.stack.last().expect("vm stack shouldn't be empty");
+                    if short_circuit(&op, lhs)?.is_some() {
+                        self.frame().ip = to as usize;
+                    }
+                }
+                Op::Binary(op) => {
+                    let rhs = self.pop();
+                    let lhs = self.pop();
+                    self.stack.push(binary_value(&op, lhs, rhs)?);
+                }
+                Op::Unary(op) => {
+                    let value = self.pop();
+                    self.stack.push(unary_value(&op, value)?);
+                }
+            }
+        }
+    }
+
+    fn global(&mut self, name: &String) -> Result<Value, RuntimeError> {
+        match self.frame().env.get(name) {
+            Some(value) => Ok(value),
+            None => Err(ErrorKind::UndefinedName {
+                name: name.to_string(),
+            }
+            .into()),
+        }
+    }
+
+    /// the methods of an `impl` block are compiled like functions, the rest is
+    /// checked like in the tree-walker
+    fn declare_impl(
+        &mut self,
+        function: &Function,
+        i: u32,
+        first: u32,
+    ) -> Result<(), RuntimeError> {
+        let chunk = &function.chunk;
+        let statement = &chunk.statements[i as usize].0;
+        let (datatype, trait_name, methods) = match &statement.kind {
+            StatementKind::Impl(datatype, trait_name, methods) => (datatype, trait_name, methods),
+            _ => unreachable!("`Impl` points to an impl block"),
+        };
+
+        let prototypes = self.prototypes;
+        let frame = self.frame();
+        let prototype = frame
+            .env
+            .get_type_alias(&Type::from(datatype.to_string()))?
+            .prototype();
+
+        if let Some(trait_name) = trait_name {
+            frame
+                .env
+                .declare_impl(&prototype, trait_name, methods, statement.span)?;
+        }
+
+        for (n, method) in methods.iter().enumerate() {
+            let function = chunk.functions[first as usize + n].clone();
+            let closure = Value::Closure(Arc::new(frame.closure(function)));
+
+            frame
+                .env
+                .declare_method(prototypes, &prototype, method, closure)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl CallFrame {
+    /// a closure of `function` created in this frame
+    fn closure(&self, function: Arc<Function>) -> Closure {
+        let upvalues = function
+            .captures
+            .iter()
+            .map(|(_, target)| self.cell(*target).clone())
+            .collect();
+
+        Closure {
+            function,
+            upvalues,
+            env: self.env.clone(),
+            this: None,
+        }
+    }
+
+    /// a scope with the values of `locals`, for code the tree-walker runs
+    fn snapshot(&self, locals: &Vec<(String, Target)>) -> Scope {
+        let mut scope = HashMap::new();
+
+        for (name, target) in locals {
+            if let Some(slot) = self.get(*target) {
+                scope.insert(
+                    name.to_string(),
+                    (slot.value, DeclType::Immutable, slot.datatype),
+                );
+            }
+        }
+
+        scope
+    }
+}
+
+// the list of a `for` loop and the position in it aren't variables of the script
+fn hidden(value: Value) -> Slot {
+    Slot {
+        value,
+        datatype: Type::Builtin(BuiltinType::Null),
+    }
+}
+
+// the steps of a path of the chunk, its indexes are the values on the stack
+fn steps(path: &[Option<String>], indexes: Vec<Value>) -> Vec<Step> {
+    let mut indexes = indexes.into_iter();
+
+    path.iter()
+        .map(|step| match step {
+            Some(name) => Step::Property(name.to_string()),
+            None => Step::Index(indexes.next().expect("index of the path")),
+        })
+        .collect()
+}
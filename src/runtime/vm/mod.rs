@@ -0,0 +1,111 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::ast::{Program, StatementKind};
+use crate::runtime::error::RuntimeError;
+use crate::runtime::value::Value;
+use crate::runtime::{ScopeStack, Type};
+use crate::span::Span;
+
+use self::chunk::Function;
+use self::compiler::compile;
+use self::machine::Vm;
+
+pub mod chunk;
+mod compiler;
+mod machine;
+
+/// a compiled function with the variables it captured
+pub struct Closure {
+    pub function: Arc<Function>,
+    upvalues: Vec<Cell>,
+    // declarations the tree-walker made where the function was created, like structs and imports
+    env: ScopeStack,
+    // the value a method is called on
+    this: Option<Value>,
+}
+
+/// a variable closures can share, empty until its declaration runs
+pub type Cell = Arc<Mutex<Option<Slot>>>;
+
+#[derive(Debug, Clone)]
+pub struct Slot {
+    pub value: Value,
+    pub datatype: Type,
+}
+
+impl Closure {
+    /// the method bound to the value it is called on
+    pub fn bind(&self, this: Value) -> Closure {
+        Closure {
+            function: self.function.clone(),
+            upvalues: self.upvalues.clone(),
+            env: self.env.clone(),
+            this: Some(this),
+        }
+    }
+}
+
+// like `ScopeStack`, the captured variables can hold the closure itself
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Closure({} upvalues)", self.upvalues.len())
+    }
+}
+
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.function, &other.function)
+            && self.upvalues.len() == other.upvalues.len()
+            && self
+                .upvalues
+                .iter()
+                .zip(&other.upvalues)
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+            && self.env == other.env
+            && self.this == other.this
+    }
+}
+
+impl PartialOrd for Closure {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self == other).then_some(Ordering::Equal)
+    }
+}
+
+/// compiles `program` and runs it in the innermost scope of `scopes`, like `eval_program_in_scope`.
+/// the program has to be resolved first, its variables are read from the slots the resolver gave them
+pub fn run(
+    scopes: &mut ScopeStack,
+    program: &Program,
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+) -> Result<Option<Value>, RuntimeError> {
+    let closure = Closure {
+        function: Arc::new(compile(program)),
+        upvalues: vec![],
+        env: scopes.clone(),
+        this: None,
+    };
+
+    let value = Vm::new(prototypes).main(Arc::new(closure))?;
+
+    match program.statements.last().map(|s| &s.kind) {
+        Some(StatementKind::Expression(_)) => Ok(Some(value)),
+        _ => Ok(None),
+    }
+}
+
+/// calls `closure` with arguments that are already evaluated,
+/// `name`, `module` and `span` describe the call in the stack trace
+pub fn call(
+    prototypes: &HashMap<String, HashMap<String, Value>>,
+    closure: &Arc<Closure>,
+    args: Vec<Value>,
+    name: String,
+    module: Option<String>,
+    span: Span,
+) -> Result<Value, RuntimeError> {
+    Vm::new(prototypes).call(closure.clone(), args, name, module, span)
+}
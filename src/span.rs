@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+
+// the sources that are alive, whoever added a source owns it and it leaves the table when dropped
+fn sources() -> &'static Mutex<HashMap<usize, Weak<Source>>> {
+    static SOURCES: OnceLock<Mutex<HashMap<usize, Weak<Source>>>> = OnceLock::new();
+    SOURCES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// ids start at 1, the default id belongs to no source
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Eq, Hash)]
+pub struct SourceId(usize);
+
+#[derive(Debug)]
+pub struct Source {
+    pub id: SourceId,
+    pub path: String,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Eq, Hash)]
+pub struct Span {
+    pub source: SourceId,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(source: SourceId, start: usize, end: usize) -> Self {
+        Self { source, start, end }
+    }
+
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.source, self.start, other.end)
+    }
+
+    /// the file path, line and column where the span starts
+    pub fn position(&self) -> Option<(String, usize, usize)> {
+        let source = Source::get(self.source)?;
+        let (line, col) = source.line_col(self.start);
+
+        Some((source.path.clone(), line, col))
+    }
+}
+
+impl Source {
+    /// makes `code` known to spans, it stays known as long as the returned source is kept
+    pub fn add(path: &str, code: &str) -> Arc<Source> {
+        let id = SourceId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        let source = Arc::new(Source {
+            id,
+            path: path.to_string(),
+            code: code.to_string(),
+        });
+
+        sources()
+            .lock()
+            .unwrap()
+            .insert(id.0, Arc::downgrade(&source));
+        source
+    }
+
+    pub fn get(id: SourceId) -> Option<Arc<Source>> {
+        let sources = sources().lock().unwrap();
+        sources.get(&id.0).and_then(Weak::upgrade)
+    }
+
+    /// 1-based line and column (in chars) of a byte offset
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let mut offset = offset.min(self.code.len());
+        while !self.code.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let before = &self.code[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let col = before[line_start..].chars().count() + 1;
+
+        (line, col)
+    }
+
+    pub fn line(&self, line: usize) -> &str {
+        self.code
+            .lines()
+            .nth(line - 1)
+            .unwrap_or("")
+            .trim_end_matches('\r')
+    }
+}
+
+impl Drop for Source {
+    fn drop(&mut self) {
+        sources().lock().unwrap().remove(&self.id.0);
+    }
+}
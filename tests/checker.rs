@@ -0,0 +1,82 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use betadin::{Interpreter, Value};
+
+use common::both;
+
+#[test]
+fn every_error_is_reported_before_anything_runs() {
+    let mut interpreter = Interpreter::new();
+    let code = "let ran = true;
+        let a: int = \"a\";
+        let b: bool = 1;
+        fn f() -> string {
+            return 2;
+        }";
+
+    let error = interpreter.eval_str(code).unwrap_err();
+    assert!(error.kind().is_none());
+    assert_eq!(error.diagnostics.len(), 3);
+    assert!(error.to_string().contains("expected `int`, found `string`"));
+    assert!(error.to_string().contains("expected `bool`, found `int`"));
+    assert!(error.to_string().contains("expected `string`, found `int`"));
+    assert_eq!(interpreter.get_global("ran"), None);
+}
+
+#[test]
+fn function_bodies_only_run_when_called() {
+    let calls = both(|interpreter| {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        interpreter
+            .register_fn("count", move |_, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(Value::Null)
+            })
+            .unwrap();
+
+        let code = "fn f(l: int[]) -> int {
+                count();
+                return l[5];
+            }";
+        interpreter.eval_str(code).unwrap();
+        let declared = calls.load(Ordering::SeqCst);
+
+        assert!(interpreter.eval_str("f([1]);").is_err());
+        (declared, calls.load(Ordering::SeqCst))
+    });
+    assert_eq!(calls, (0, 1));
+}
+
+#[test]
+fn functions_can_return_from_an_endless_loop() {
+    let mut interpreter = Interpreter::new();
+    let code = "fn first(n: int) -> int {
+            let i = 0;
+            while true {
+                i += 1;
+                while true {
+                    break;
+                }
+                if i == n {
+                    return i;
+                }
+            }
+        }
+        first(4)";
+    assert_eq!(interpreter.eval_str(code).unwrap(), Value::Int(4));
+
+    // a `break` ends the loop, the function would give `null`
+    let code = "fn stop() -> int {
+            while true {
+                if true {
+                    break;
+                }
+            }
+        }";
+    let error = interpreter.eval_str(code).unwrap_err();
+    assert!(error.to_string().contains("expected `int`, found `null`"));
+}
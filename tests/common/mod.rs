@@ -0,0 +1,55 @@
+//! helpers shared by the integration tests, every program runs on both engines
+#![allow(dead_code)]
+
+use std::fmt::Debug;
+use std::fs;
+use std::path::PathBuf;
+
+use betadin::{Engine, Interpreter, Value};
+
+/// runs `run` with an interpreter of each engine, the engines have to agree on the result
+pub fn both<T: PartialEq + Debug>(run: impl Fn(&mut Interpreter) -> T) -> T {
+    let mut results = vec![];
+
+    for engine in [Engine::Vm, Engine::TreeWalker] {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_engine(engine);
+        results.push(run(&mut interpreter));
+    }
+
+    assert_eq!(results[0], results[1], "the engines disagree");
+    results.remove(0)
+}
+
+/// the value of the last expression of `code`
+pub fn eval(code: &str) -> Value {
+    both(|interpreter| match interpreter.eval_str(code) {
+        Ok(value) => value,
+        Err(error) => panic!("`{}` failed: {}", code, error),
+    })
+}
+
+/// the rendered errors of `code`, with their backtraces
+pub fn error(code: &str) -> String {
+    both(|interpreter| match interpreter.eval_str(code) {
+        Ok(value) => panic!("`{}` didn't fail, it gave {:?}", code, value),
+        Err(error) => error.to_string(),
+    })
+}
+
+/// whether `code` fails on both engines
+pub fn fails(code: &str) -> bool {
+    both(|interpreter| interpreter.eval_str(code).is_err())
+}
+
+/// a tuple of ints, what `(a, b, ..)` evaluates to
+pub fn list(items: &[i32]) -> Value {
+    Value::Tuple(items.iter().map(|i| Value::Int(*i)).collect())
+}
+
+/// an empty directory for the files of a test
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("betadin-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
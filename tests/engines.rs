@@ -0,0 +1,548 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use betadin::{ErrorKind, Value};
+
+use common::{both, error, eval, list, temp_dir};
+
+#[test]
+fn loops() {
+    let code = "let res = [0];
+        res.pop();
+        for i in 0..4 {
+            if i == 1 { continue; }
+            if i == 3 { break; }
+            res.push(i);
+        }
+        for item in (5, 6) {
+            res.push(item);
+        }
+        let n = 0;
+        while n < 2 {
+            n += 1;
+            res.push(n * 10);
+        }
+        let total = 0;
+        for i in 1..3 {
+            for j in 1..3 {
+                total += i * j;
+            }
+        }
+        res.push(total);
+        (res[0], res[1], res[2], res[3], res[4], res[5], res.len())";
+    assert_eq!(eval(code), list(&[0, 2, 5, 6, 10, 20, 7]));
+}
+
+#[test]
+fn a_loop_goes_over_the_items_the_list_had_when_it_started() {
+    let code = "let l = [1, 2];
+        let seen = 0;
+        for item in l {
+            l.push(item);
+            seen += 1;
+        }
+        (seen, l.len())";
+    assert_eq!(eval(code), list(&[2, 4]));
+}
+
+#[test]
+fn an_emptied_list_keeps_the_type_of_its_items() {
+    let rendered = error(
+        "let l = [1];
+        l.pop();
+        l.push(\"s\");",
+    );
+    assert!(
+        rendered.contains("expected `int`, found `string`"),
+        "{}",
+        rendered
+    );
+
+    let rendered = error(
+        "let l = [1];
+        l[0] = \"s\";",
+    );
+    assert!(
+        rendered.contains("expected `int`, found `string`"),
+        "{}",
+        rendered
+    );
+
+    let code = "let none = [1, 2].filter(fn(x: int) -> bool { return x > 5; });
+        none.push(3);
+        none";
+    assert_eq!(eval(code).to_string(), "[3]");
+}
+
+#[test]
+fn closures_capture_variables() {
+    let code = "fn counter() -> fn() -> int {
+            let count = 0;
+            return fn() -> int {
+                count += 1;
+                return count;
+            };
+        }
+        let a = counter();
+        let b = counter();
+        a();
+        a();
+        (a(), b())";
+    assert_eq!(eval(code), list(&[3, 1]));
+
+    let code = "let fns = [fn() -> int { return -1; }];
+        fns.pop();
+        for i in 0..2 {
+            fns.push(fn() -> int { return i * 10; });
+        }
+        let x = 1;
+        let add = fn(n: int) -> int { return n + x; };
+        x = 5;
+        (fns[0](), fns[2](), add(1))";
+    assert_eq!(eval(code), list(&[0, 20, 6]));
+}
+
+#[test]
+fn break_and_continue_inside_match() {
+    let code = "enum Step {
+            Skip,
+            Stop,
+            Add(int),
+        }
+        let steps = [Step::Add(1), Step::Skip, Step::Add(2), Step::Stop, Step::Add(4)];
+        let total = 0;
+        let seen = 0;
+        for step in steps {
+            seen += 1;
+            match step {
+                Skip => {
+                    continue;
+                }
+                Stop => {
+                    break;
+                }
+                Add(n) => {
+                    total += n;
+                }
+            }
+        }
+        (total, seen)";
+    assert_eq!(eval(code), list(&[3, 4]));
+}
+
+#[test]
+fn modules_structs_and_enums() {
+    let code = "module shapes {
+            export struct Point {
+                x: int,
+                y: int,
+            }
+
+            export fn origin() -> Point {
+                return Point { x: 0, y: 0 };
+            }
+        }
+        enum Dir {
+            Left,
+            Right(int),
+        }
+        impl Dir {
+            fn amount(self) -> int {
+                return match self {
+                    Left => -1,
+                    Right(n) => n,
+                };
+            }
+        }
+        let m = module {
+            export let y = 10;
+        };
+        let p = shapes::origin();
+        p.x = 3;
+        let left = Dir::Left;
+        let right = Dir::Right(7);
+        (p.x, p.y, m::y, left.amount(), right.amount())";
+    assert_eq!(eval(code), list(&[3, 0, 10, -1, 7]));
+}
+
+#[test]
+fn written_keys_cannot_hide_object_methods() {
+    let written = |code: &str| {
+        both(|interpreter| {
+            interpreter
+                .register_method("object", "len", |_, _, this| match this {
+                    Value::Object(props) => Ok(Value::Int(props.get().len() as i32)),
+                    _ => Ok(Value::Null),
+                })
+                .unwrap();
+            match interpreter.eval_str(code) {
+                Ok(value) => format!("{:?}", value),
+                Err(error) => error.to_string(),
+            }
+        })
+    };
+
+    assert!(written("let o = {a: 1}; o.len = 3; o.len()").contains("`len` is reserved"));
+    assert!(written("let o = {a: 1}; o.set(\"len\", 3); o.len()").contains("`len` is reserved"));
+    assert!(written("let o = {a: 1}; o.set(\"get\", 3);").contains("`get` is reserved"));
+    assert_eq!(
+        written("let o = {a: 1}; o.b = 2; o.set(\"c\", 3); o.len()"),
+        "Int(3)"
+    );
+}
+
+#[test]
+fn file_modules() {
+    let dir = temp_dir("file_modules");
+    fs::create_dir_all(dir.join("lib")).unwrap();
+    fs::write(
+        dir.join("lib").join("math.ak"),
+        "fn twice(n: int) -> int {
+            return n * 2;
+        }
+
+        export fn quad(n: int) -> int {
+            return twice(twice(n));
+        }",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("main.ak"),
+        "import lib::math;
+        import lib::math::{quad};
+        (math::quad(1), quad(2));",
+    )
+    .unwrap();
+
+    let value = both(|interpreter| interpreter.eval_file(dir.join("main.ak")).unwrap());
+    assert_eq!(value, list(&[4, 8]));
+}
+
+#[test]
+fn files_that_import_each_other_are_reported() {
+    let dir = temp_dir("circular");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.ak"), "import b;\nexport let x = 1;").unwrap();
+    fs::write(dir.join("b.ak"), "import a;\nexport let y = 2;").unwrap();
+    fs::write(dir.join("main.ak"), "import a;\na::x;").unwrap();
+
+    let chain = both(|interpreter| {
+        let error = interpreter.eval_file(dir.join("main.ak")).unwrap_err();
+        assert!(error.to_string().contains("circular import"));
+
+        match error.kind() {
+            Some(ErrorKind::CircularImport { chain }) => chain
+                .iter()
+                .map(|path| Path::new(path).file_name().unwrap().to_owned())
+                .collect::<Vec<_>>(),
+            other => panic!("expected a circular import, found {:?}", other),
+        }
+    });
+    assert_eq!(chain, ["a.ak", "b.ak", "a.ak"]);
+}
+
+#[test]
+fn a_file_imported_twice_runs_once() {
+    let dir = temp_dir("cache");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("counter.ak"),
+        "count();
+        export let items = [1];",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("other.ak"),
+        "import counter::{items};
+        export fn push() {
+            items.push(2);
+        }",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("main.ak"),
+        "import counter;
+        import other;
+        import counter::{items};
+        other::push();
+        (counter::items, items);",
+    )
+    .unwrap();
+
+    let (value, runs) = both(|interpreter| {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counter = runs.clone();
+        interpreter
+            .register_fn("count", move |_, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(Value::Null)
+            })
+            .unwrap();
+
+        let value = interpreter.eval_file(dir.join("main.ak")).unwrap();
+        (value.to_string(), runs.load(Ordering::SeqCst))
+    });
+
+    // every import shares the items of the first one
+    assert_eq!(value, "([1, 2], [1, 2])");
+    assert_eq!(runs, 1);
+}
+
+#[test]
+fn methods_go_with_their_type_to_other_files() {
+    let dir = temp_dir("methods");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("shapes.ak"),
+        "export struct P {
+            x: int,
+        }
+
+        impl P {
+            fn double(self) -> int {
+                return self.x * 2;
+            }
+        }
+
+        export fn make() -> P {
+            return P { x: 4 };
+        }",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("main.ak"),
+        "import shapes;
+        shapes::make().double();",
+    )
+    .unwrap();
+
+    let value = both(|interpreter| interpreter.eval_file(dir.join("main.ak")).unwrap());
+    assert_eq!(value, Value::Int(8));
+}
+
+#[test]
+fn modules_can_give_their_types_methods() {
+    let code = "module m {
+            export struct Q {
+                y: int,
+            }
+
+            impl Q {
+                fn triple(self) -> int {
+                    return self.y * 3;
+                }
+            }
+
+            export fn make() -> Q {
+                return Q { y: 5 };
+            }
+        }
+        m::make().triple()";
+    assert_eq!(eval(code), Value::Int(15));
+
+    let rendered = error(
+        "module m {
+            println(1);
+        }",
+    );
+    assert!(rendered.contains("expression statement is not supported in modules"));
+}
+
+#[test]
+fn imported_types_keep_their_kind() {
+    let dir = temp_dir("imported-types");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("shapes.ak"),
+        "export struct P {
+            x: int,
+        }
+
+        export trait Show {
+            fn show(self) -> int;
+        }
+
+        impl Show for P {
+            fn show(self) -> int {
+                return self.x * 10;
+            }
+        }
+
+        export fn make() -> P {
+            return P { x: 1 };
+        }",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("main.ak"),
+        "import shapes::{P, Show, make};
+        import shapes;
+
+        fn show(item: Show) -> int {
+            return item.show();
+        }
+
+        let p: P = make();
+        let q: shapes::P = P { x: 2 };
+        let r: shapes::Show = q;
+        show(p) + r.show();",
+    )
+    .unwrap();
+
+    let value = both(|interpreter| interpreter.eval_file(dir.join("main.ak")).unwrap());
+    assert_eq!(value, Value::Int(30));
+
+    // the checker knows the imported declarations, the errors are found before anything runs
+    for (code, message) in [
+        ("let p: P = 1;", "expected `P`, found `int`"),
+        ("P { y: 1 };", "`y` does not exist in the `P` prototype"),
+        (
+            "fn show(item: Show) {} show(1);",
+            "expected `Show` for argument 1, found `int`",
+        ),
+        ("let p: shapes::Q = 1;", "type `shapes::Q` is not defined"),
+    ] {
+        fs::write(
+            dir.join("bad.ak"),
+            format!(
+                "import shapes::{{P, Show}};
+                import shapes;
+                let ran = true;
+                {}",
+                code
+            ),
+        )
+        .unwrap();
+
+        let rendered = both(|interpreter| {
+            let error = interpreter.eval_file(dir.join("bad.ak")).unwrap_err();
+            assert_eq!(interpreter.get_global("ran"), None);
+            error.to_string()
+        });
+        assert!(rendered.contains(message), "{}", rendered);
+    }
+}
+
+#[test]
+fn types_of_inline_modules_can_be_imported() {
+    let code = "module m {
+            export struct P {
+                x: int,
+            }
+        }
+        import m::{P};
+        let p: P = P { x: 2 };
+        let q: m::P = p;
+        q.x";
+    assert_eq!(eval(code), Value::Int(2));
+
+    let rendered = error(
+        "module m {
+            export struct P {
+                x: int,
+            }
+        }
+        let p: m::P = 1;",
+    );
+    assert!(rendered.contains("expected `P`, found `int`"));
+}
+
+#[test]
+fn a_method_is_declared_once_per_type() {
+    let rendered = error(
+        "struct P {
+            x: int,
+        }
+        impl P {
+            fn a(self) -> int { return 1; }
+        }
+        impl P {
+            fn a(self) -> int { return 2; }
+        }",
+    );
+    assert!(rendered.contains("method `a` is already defined for `P`"));
+
+    // an `impl` block that runs again declares the same methods
+    let code = "struct P {
+            x: int,
+        }
+        fn setup() {
+            impl P {
+                fn a(self) -> int { return self.x; }
+            }
+        }
+        setup();
+        setup();
+        P { x: 7 }.a()";
+    assert_eq!(eval(code), Value::Int(7));
+}
+
+#[test]
+fn runtime_errors_have_the_same_backtrace() {
+    let rendered = error(
+        "fn inner(l: int[]) -> int {
+            return l[5];
+        }
+        fn outer() -> int {
+            return inner([1]);
+        }
+        outer();",
+    );
+    assert!(rendered.contains("index 5 is out of bounds for length 1"));
+    assert!(rendered.contains("0: inner"));
+    assert!(rendered.contains("1: outer"));
+    assert!(rendered.contains("2: <main>"));
+
+    let rendered = error("let x = 1; let y = 0; x / y");
+    assert!(rendered.contains("cannot divide by zero"));
+}
+
+#[test]
+fn callbacks_are_named_after_their_function() {
+    let rendered = error(
+        "fn boom(x: int) -> int {
+            return x / 0;
+        }
+        [1].map(boom);",
+    );
+    assert!(rendered.contains("0: boom"), "{}", rendered);
+    assert!(!rendered.contains("<anonymous>"), "{}", rendered);
+
+    let rendered = error("[1].map(fn(x: int) -> int { return x / 0; });");
+    assert!(rendered.contains("0: <anonymous>"), "{}", rendered);
+}
+
+// runs on the small stack of a test thread, like a host calling the library
+#[test]
+fn deep_recursion() {
+    let code = "fn depth(n: int) -> int {
+            if n == 0 {
+                return 0;
+            }
+            return depth(n - 1) + 1;
+        }
+        depth(900)";
+    assert_eq!(eval(code), Value::Int(900));
+
+    let rendered = error(
+        "fn forever(n: int) -> int {
+            return forever(n + 1);
+        }
+        forever(0);",
+    );
+    assert!(rendered.contains("stack overflow"));
+    assert!(rendered.contains("repeated 999 more times"));
+
+    // every call goes through `map`, a native calling back into the script
+    let rendered = error(
+        "fn down(n: int) -> int {
+            return [n + 1].map(down)[0];
+        }
+        down(0);",
+    );
+    assert!(rendered.contains("stack overflow"));
+}
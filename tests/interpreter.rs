@@ -0,0 +1,162 @@
+mod common;
+
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use betadin::{ErrorKind, Interpreter, Value};
+
+use common::temp_dir;
+
+#[test]
+fn runtime_errors_keep_their_kind() {
+    let mut interpreter = Interpreter::new();
+
+    let error = interpreter.eval_str("let l = [1]; l[3]").unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        Some(ErrorKind::IndexOutOfBounds { index: 3, len: 1 })
+    ));
+    assert!(error.to_string().contains("index 3 is out of bounds"));
+
+    let error = interpreter.call_function("missing", vec![]).unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        Some(ErrorKind::UndefinedName { .. })
+    ));
+}
+
+#[test]
+fn host_functions_cannot_be_hidden_by_script_globals() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_str("let log = 1; let host = 2;").unwrap();
+
+    let error = interpreter
+        .register_fn("log", |_, _| Ok(Value::Null))
+        .unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        Some(ErrorKind::AlreadyDefined { name }) if name == "log"
+    ));
+    assert!(interpreter
+        .register_fn("host::log", |_, _| Ok(Value::Null))
+        .is_err());
+
+    interpreter
+        .register_fn("print_twice", |_, _| Ok(Value::Int(2)))
+        .unwrap();
+    assert_eq!(
+        interpreter.eval_str("print_twice()").unwrap(),
+        Value::Int(2)
+    );
+}
+
+#[test]
+fn errors_found_before_running_have_no_kind() {
+    let mut interpreter = Interpreter::new();
+
+    let error = interpreter.eval_str("let x: int = \"a\";").unwrap_err();
+    assert!(error.kind().is_none());
+    assert_eq!(error.diagnostics.len(), 1);
+    assert!(error.to_string().contains("expected `int`"));
+}
+
+#[test]
+fn errors_render_after_the_interpreter_is_gone() {
+    let error = Interpreter::new().eval_str("1 / 0").unwrap_err();
+    assert!(error.to_string().contains("1 / 0"));
+}
+
+#[test]
+fn errors_of_an_expression_point_into_it_as_written() {
+    let mut interpreter = Interpreter::new();
+
+    let error = interpreter.eval_str("\"a{\"").unwrap_err();
+    assert!(error.to_string().contains("unclosed `{` in string"));
+    assert!(!error.to_string().contains(';'));
+
+    let error = interpreter.eval_str("1 / 0").unwrap_err();
+    assert!(error.to_string().contains("1 / 0"));
+    assert!(!error.to_string().contains(';'));
+}
+
+#[test]
+fn type_of_does_not_run_the_expression() {
+    let mut interpreter = Interpreter::new();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    interpreter
+        .register_fn("count", move |_, _| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(Value::Int(1))
+        })
+        .unwrap();
+
+    assert_eq!(interpreter.type_of("count()").unwrap(), None);
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    let datatype = interpreter.type_of("1 + 2").unwrap();
+    assert_eq!(datatype.map(|t| t.to_string()), Some("int".to_string()));
+}
+
+#[test]
+fn only_expressions_can_leave_out_the_semicolon() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        interpreter.eval_str("let x = 2; x * 3").unwrap(),
+        Value::Int(6)
+    );
+    assert_eq!(interpreter.eval_str("let y = 2").unwrap(), Value::Null);
+
+    let dir = temp_dir("semicolon");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.ak"), "let z = 1").unwrap();
+
+    let error = interpreter.eval_file(dir.join("main.ak")).unwrap_err();
+    assert!(error.to_string().contains("unexpected end of file"));
+}
+
+#[test]
+fn imports_fall_back_to_the_directory_of_the_main_script() {
+    let dir = temp_dir("root");
+    fs::create_dir_all(dir.join("lib")).unwrap();
+    fs::create_dir_all(dir.join("util")).unwrap();
+    fs::write(
+        dir.join("util").join("num.ak"),
+        "export fn one() -> int { return 1; }",
+    )
+    .unwrap();
+    // `util::num` isn't next to `lib/a.ak`, it is found in the project root
+    fs::write(
+        dir.join("lib").join("a.ak"),
+        "import util::num;\nexport fn two() -> int { return num::one() + 1; }",
+    )
+    .unwrap();
+    fs::write(dir.join("main.ak"), "import lib::a;\na::two();").unwrap();
+
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_str("let before = 1;").unwrap();
+    assert_eq!(
+        interpreter.eval_file(dir.join("main.ak")).unwrap(),
+        Value::Int(2)
+    );
+}
+
+#[test]
+fn every_error_of_an_imported_file_is_reported() {
+    let dir = temp_dir("imported_errors");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("bad.ak"), "let a: int = \"a\";\nlet b: bool = 1;").unwrap();
+    fs::write(dir.join("main.ak"), "import bad;").unwrap();
+
+    let error = Interpreter::new()
+        .eval_file(dir.join("main.ak"))
+        .unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        Some(ErrorKind::InvalidModule { .. })
+    ));
+    assert_eq!(error.diagnostics.len(), 2);
+    assert!(error.to_string().contains("bad.ak:1:"));
+    assert!(error.to_string().contains("bad.ak:2:"));
+}
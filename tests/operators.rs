@@ -0,0 +1,201 @@
+mod common;
+
+use betadin::Value;
+
+use common::{error, eval, fails};
+
+/// `calls` counts how often `hit` ran, the code ends with `(<expr>, calls)`
+fn with_calls(expr: &str) -> Value {
+    eval(&format!(
+        "let calls = 0;
+        fn hit(v: bool) -> bool {{
+            calls += 1;
+            return v;
+        }}
+        let res = {};
+        (res, calls)",
+        expr
+    ))
+}
+
+fn pair(res: bool, calls: i32) -> Value {
+    Value::Tuple(vec![Value::Bool(res), Value::Int(calls)])
+}
+
+#[test]
+fn and_skips_the_right_side_when_the_left_is_false() {
+    assert_eq!(with_calls("false && hit(true)"), pair(false, 0));
+    assert_eq!(with_calls("true && hit(false)"), pair(false, 1));
+    assert_eq!(with_calls("true && hit(true)"), pair(true, 1));
+}
+
+#[test]
+fn or_skips_the_right_side_when_the_left_is_true() {
+    assert_eq!(with_calls("true || hit(false)"), pair(true, 0));
+    assert_eq!(with_calls("false || hit(true)"), pair(true, 1));
+    assert_eq!(with_calls("false || hit(false)"), pair(false, 1));
+}
+
+#[test]
+fn chains_stop_at_the_first_deciding_operand() {
+    assert_eq!(
+        with_calls("hit(false) && hit(true) && hit(true)"),
+        pair(false, 1)
+    );
+    assert_eq!(
+        with_calls("hit(true) || hit(true) || hit(true)"),
+        pair(true, 1)
+    );
+    assert_eq!(
+        with_calls("hit(false) || hit(false) || hit(true)"),
+        pair(true, 3)
+    );
+}
+
+#[test]
+fn a_null_guard_keeps_the_right_side_from_running() {
+    let code = "let o = { name: null };
+        let x = o.name;
+        x != null && x.len() > 0";
+    assert_eq!(eval(code), Value::Bool(false));
+
+    let code = "let o = { name: null };
+        let x = o.name;
+        x == null || x.len() > 0";
+    assert_eq!(eval(code), Value::Bool(true));
+}
+
+#[test]
+fn the_left_side_has_to_be_a_bool() {
+    assert!(fails("let o = { v: 1 }; o.v && true"));
+    assert!(fails("let o = { v: 1 }; o.v || true"));
+    assert!(fails("let o = { v: 1 }; true && o.v"));
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+    assert_eq!(eval("true || false && false"), Value::Bool(true));
+    assert_eq!(eval("false && false || true"), Value::Bool(true));
+    assert_eq!(eval("(true || false) && false"), Value::Bool(false));
+    assert_eq!(
+        with_calls("true || hit(false) && hit(false)"),
+        pair(true, 0)
+    );
+}
+
+#[test]
+fn comparisons_bind_tighter_than_logical_operators() {
+    assert_eq!(eval("1 < 2 && 3 > 4 || 5 == 5"), Value::Bool(true));
+    assert_eq!(eval("1 != 1 || 2 <= 1"), Value::Bool(false));
+}
+
+#[test]
+fn bitwise_operators_bind_tighter_than_comparisons() {
+    assert_eq!(eval("7 & 3 == 3"), Value::Bool(true));
+    assert_eq!(eval("1 << 2 == 4"), Value::Bool(true));
+    assert_eq!(eval("1 | 2 ^ 3 & 4"), Value::Int(3));
+}
+
+#[test]
+fn arithmetic_follows_the_usual_order() {
+    assert_eq!(eval("1 + 2 * 3"), Value::Int(7));
+    assert_eq!(eval("10 - 2 - 3"), Value::Int(5));
+    assert_eq!(eval("2 * 3 % 4"), Value::Int(2));
+    assert_eq!(eval("1 + 2 << 1"), Value::Int(6));
+    assert_eq!(eval("2 ** 3 ** 2"), Value::Int(512));
+    assert_eq!(eval("-2 ** 2"), Value::Int(-4));
+    assert_eq!(eval("let a = 5; a -1"), Value::Int(4));
+}
+
+#[test]
+fn int_literals_have_to_fit_an_int() {
+    assert_eq!(eval("2147483647"), Value::Int(i32::MAX));
+    assert_eq!(eval("-2147483648"), Value::Int(i32::MIN));
+    assert_eq!(
+        eval("let x = match -2147483648 { -2147483648 => 1, _ => 2 }; x"),
+        Value::Int(1)
+    );
+
+    for code in ["2147483648;", "1 - 2147483648;", "99999999999999999999;"] {
+        assert!(error(code).contains("integer literal out of range"), "{}", code);
+    }
+}
+
+#[test]
+fn compound_assignment_finds_its_target_once() {
+    let code = "let calls = 0;
+        fn next() -> int {
+            calls += 1;
+            return calls - 1;
+        }
+        let l = [10, 20, 30];
+        l[next()] += 1;
+        let o = { a: [[1, 2, 3], [4, 5, 6]] };
+        o.a[next()][next()] *= 5;
+        (l, o.a, calls)";
+
+    let value = eval(code);
+    assert_eq!(value.to_string(), "([11, 20, 30], [[1, 2, 3], [4, 5, 30]], 3)");
+
+    assert!(error("const c = 1; c += 1;").contains("immutable"));
+    assert!(error("let l = [1]; l[0] += \"s\";").contains("expected `int`, found `string`"));
+}
+
+#[test]
+fn coalesce_takes_the_right_side_only_for_null() {
+    assert_eq!(eval("let x = null; x ?? 4"), Value::Int(4));
+    assert_eq!(eval("0 ?? 4"), Value::Int(0));
+    assert_eq!(eval("null ?? null ?? 3"), Value::Int(3));
+    assert_eq!(eval("1 ?? 2 == 2"), Value::Int(1));
+}
+
+#[test]
+fn optional_chaining_yields_null_instead_of_failing() {
+    let cfg = "let cfg = { db: { port: 80 } }; let none = { db: null };";
+    assert_eq!(eval(&format!("{} cfg?.db?.port", cfg)), Value::Int(80));
+    assert_eq!(eval(&format!("{} none.db?.port.max", cfg)), Value::Null);
+    assert_eq!(eval(&format!("{} cfg?.cache ?? 1", cfg)), Value::Int(1));
+    assert_eq!(eval("let l = [1, 2]; l?.[5]"), Value::Null);
+    assert_eq!(eval("let s = \"abc\"; s?.len()"), Value::Int(3));
+    assert_eq!(eval("let x = null; x?.len()"), Value::Null);
+
+    assert!(fails("let x = null; x.len()"));
+    assert!(fails("let x = 5; x?.foo"));
+    assert!(fails("let x = null; (x?.a).b"));
+}
+
+#[test]
+fn null_checks_skip_the_right_side() {
+    let code = "let calls = 0;
+        fn hit() -> int {
+            calls += 1;
+            return 9;
+        }
+        let x = null;
+        let res = (1 ?? hit(), x?.foo(hit()), null ?? hit());
+        (res, calls)";
+    assert_eq!(
+        eval(code),
+        Value::Tuple(vec![
+            Value::Tuple(vec![Value::Int(1), Value::Null, Value::Int(9)]),
+            Value::Int(1),
+        ])
+    );
+}
+
+#[test]
+fn objects_that_hold_each_other_can_be_compared() {
+    let code = "let o = { x: 1 };
+        let p = { x: o };
+        o.x = p;
+        let q = { x: 2 };
+        (o == p, o == q, o == o)";
+    assert_eq!(
+        eval(code),
+        Value::Tuple(vec![
+            Value::Bool(true),
+            Value::Bool(false),
+            Value::Bool(true)
+        ])
+    );
+}
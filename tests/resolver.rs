@@ -0,0 +1,103 @@
+mod common;
+
+use betadin::Value;
+
+use common::{both, error, eval};
+
+#[test]
+fn inner_declarations_shadow_until_their_block_ends() {
+    let code = "let x = 1;
+        let seen = [0];
+        seen.pop();
+        if true {
+            seen.push(x);
+            let x = 2;
+            seen.push(x);
+        }
+        seen.push(x);
+        fn f(x: int) -> int {
+            return x * 10;
+        }
+        seen.push(f(3));
+        seen";
+    assert_eq!(eval(code).to_string(), "[1, 2, 1, 30]");
+
+    let rendered = error(
+        "if true {
+            let y = 1;
+            let y = 2;
+        }",
+    );
+    assert!(rendered.contains("`y` is already defined"), "{}", rendered);
+}
+
+#[test]
+fn closures_keep_the_variables_they_capture() {
+    let code = "fn make() -> function {
+            let n = 0;
+            return fn() -> int {
+                n += 1;
+                return n;
+            };
+        }
+        let a = make();
+        let b = make();
+        a();
+        a();
+        b();
+
+        fn outer() -> int {
+            let y = 1;
+            fn mid() -> int {
+                fn inner() -> int {
+                    return y + 1;
+                }
+                return inner();
+            }
+            y = 5;
+            return mid();
+        }
+
+        let fs = [fn() -> int { return 0; }];
+        fs.pop();
+        for i in 1..3 {
+            fs.push(fn() -> int { return i; });
+        }
+
+        (a(), b(), outer(), fs[0](), fs[2]())";
+    assert_eq!(eval(code).to_string(), "(3, 2, 6, 1, 3)");
+}
+
+#[test]
+fn globals_are_found_by_name_when_they_run() {
+    // a function can use a global declared after it
+    let code = "fn get() -> int {
+            return later;
+        }
+        let later = 9;
+        get()";
+    assert_eq!(eval(code), Value::Int(9));
+
+    // and the names defined before the program, by the host or an earlier program
+    let value = both(|interpreter| {
+        interpreter.set_global("host", Value::Int(2)).unwrap();
+        interpreter.eval_str("let earlier = 3;").unwrap();
+        interpreter.eval_str("host * earlier").unwrap()
+    });
+    assert_eq!(value, Value::Int(6));
+
+    // a name nothing defines is reported before anything runs
+    let rendered = both(|interpreter| {
+        let error = interpreter
+            .eval_str(
+                "let ran = true;
+                fn never() -> int {
+                    return missing;
+                }",
+            )
+            .unwrap_err();
+        assert_eq!(interpreter.get_global("ran"), None);
+        error.to_string()
+    });
+    assert!(rendered.contains("`missing` is not defined"), "{}", rendered);
+}